@@ -9,6 +9,7 @@
 //! * Eviction policy performance characteristics (LRU, LFU)
 //! * Key serialization overhead
 //! * TTL operations performance
+//! * Read latency as the number of entries with a TTL grows (10k, 100k, 1M)
 //!
 //! ## Interpreting Results
 //!
@@ -19,6 +20,9 @@
 //! * **Operation cost**: `set` operations typically cost more than `get` operations
 //!   due to serialization overhead; `get_miss` is usually faster than `get_hit`
 //!   as no deserialization is needed.
+//! * **Expiration scaling**: `get_hit` latency on a memory backend holding
+//!   entries with a TTL should stay flat as the entry count grows, since
+//!   expiry is driven by a timer wheel rather than a full-map scan.
 //! * **Eviction policies**: LRU typically has better throughput than LFU but may
 //!   have worse cache hit rates for certain access patterns.
 //!
@@ -35,7 +39,7 @@
 //! cargo bench --bench core_benchmarks -- memory_backend
 //! ```
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, SamplingMode};
 use futures::executor::block_on;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -45,6 +49,7 @@ use std::time::Duration;
 
 use fncache::backends::memory::{MemoryBackend, MemoryBackendConfig};
 use fncache::backends::CacheBackend;
+use fncache::eviction::EvictionPolicyKind;
 
 const SMALL_DATA_SIZE: usize = 100;
 const MEDIUM_DATA_SIZE: usize = 1000;
@@ -57,6 +62,7 @@ const WARMUP_TIME_MS: u64 = 500;
 const MIN_SAMPLE_SIZE: usize = 10;
 
 const DEFAULT_TTL_SECONDS: u64 = 60;
+const EXPIRATION_SCALING_SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
 
 const RNG_SEED_SET: u64 = 42;
 const RNG_SEED_GET_MISS: u64 = 43;
@@ -305,7 +311,7 @@ fn bench_eviction_policies(c: &mut Criterion) {
     group.bench_function("lru_eviction", |b| {
         let mut backend = MemoryBackend::new();
         backend = backend.with_capacity(EVICTION_CACHE_CAPACITY);
-        backend = backend.with_eviction_policy("lru");
+        backend = backend.with_eviction_policy(EvictionPolicyKind::Lru);
 
         b.iter(|| {
             for key in &keys {
@@ -317,7 +323,7 @@ fn bench_eviction_policies(c: &mut Criterion) {
     group.bench_function("lfu_eviction", |b| {
         let mut backend = MemoryBackend::new();
         backend = backend.with_capacity(EVICTION_CACHE_CAPACITY);
-        backend = backend.with_eviction_policy("lfu");
+        backend = backend.with_eviction_policy(EvictionPolicyKind::Lfu);
 
         b.iter(|| {
             for key in &keys {
@@ -329,19 +335,66 @@ fn bench_eviction_policies(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark expiration overhead as the cache grows
+///
+/// Every entry is inserted with a TTL so that the backend has to track its
+/// deadline. Reads should cost roughly the same at 10k, 100k and 1M entries;
+/// a linear increase indicates that expiry is scanning the whole map.
+fn bench_expiration_scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory_expiration_scaling");
+    configure_benchmark_group(&mut group, true);
+    group.sampling_mode(SamplingMode::Flat);
+
+    let data = generate_data(SMALL_DATA_SIZE);
+    let ttl = Some(Duration::from_secs(DEFAULT_TTL_SECONDS));
+
+    for &entries in EXPIRATION_SCALING_SIZES.iter() {
+        let backend = Arc::new(MemoryBackend::new());
+        for i in 0..entries {
+            block_on(backend.set(format!("expiring_key_{}", i), data.clone(), ttl)).unwrap();
+        }
+
+        group.bench_with_input(BenchmarkId::new("get_hit", entries), &entries, |b, &n| {
+            let mut rng = StdRng::seed_from_u64(RNG_SEED_GET_MISS);
+            b.iter(|| {
+                let key = format!("expiring_key_{}", rng.gen_range(0..n));
+                block_on(backend.get(&key))
+            });
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("set_with_ttl", entries),
+            &entries,
+            |b, &n| {
+                let mut rng = StdRng::seed_from_u64(RNG_SEED_TTL);
+                b.iter(|| {
+                    let key = format!("expiring_key_{}", rng.gen_range(0..n));
+                    block_on(backend.set(key, data.clone(), ttl))
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn memory_backend_benchmarks(c: &mut Criterion) {
     let backend = MemoryBackend::new();
     bench_basic_operations(c, backend, "memory_backend");
 
-    let mut config = MemoryBackendConfig::default();
-    config.max_capacity = EVICTION_CACHE_CAPACITY;
-    config.eviction_policy = "lru".to_string();
+    let config = MemoryBackendConfig {
+        max_capacity: EVICTION_CACHE_CAPACITY,
+        eviction_policy: EvictionPolicyKind::Lru,
+        ..Default::default()
+    };
     let backend = MemoryBackend::with_config(config);
     bench_ttl_operations(c, backend, "memory_lru");
 
-    let mut config = MemoryBackendConfig::default();
-    config.max_capacity = EVICTION_CACHE_CAPACITY;
-    config.eviction_policy = "lfu".to_string();
+    let config = MemoryBackendConfig {
+        max_capacity: EVICTION_CACHE_CAPACITY,
+        eviction_policy: EvictionPolicyKind::Lfu,
+        ..Default::default()
+    };
     let backend = MemoryBackend::with_config(config);
     bench_ttl_operations(c, backend, "memory_lfu");
 }
@@ -350,7 +403,8 @@ criterion_group!(
     benches,
     memory_backend_benchmarks,
     bench_key_serialization,
-    bench_eviction_policies
+    bench_eviction_policies,
+    bench_expiration_scaling
 );
 
 criterion_main!(benches);
@@ -5,6 +5,7 @@
 //!
 //! Requires feature: `file-backend`
 
+#![cfg_attr(not(feature = "file-backend"), allow(dead_code, unused_imports))]
 #![allow(clippy::needless_return)]
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion, SamplingMode};
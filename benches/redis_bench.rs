@@ -8,6 +8,8 @@
 //!
 //! Requires feature: `redis-backend`
 
+#![cfg_attr(not(feature = "redis-backend"), allow(dead_code, unused_imports))]
+
 use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
 use std::env;
 use std::sync::Arc;
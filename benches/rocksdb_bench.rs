@@ -5,6 +5,8 @@
 //!
 //! Requires feature: `rocksdb-backend`
 
+#![cfg_attr(not(feature = "rocksdb-backend"), allow(dead_code, unused_imports))]
+
 use criterion::{criterion_group, criterion_main, Criterion};
 use futures::executor::block_on;
 use std::sync::Arc;
@@ -47,10 +47,8 @@ fn main() -> Result<()> {
 
     println!("\n--- Cache files in {} ---", cache_dir);
     if let Ok(entries) = std::fs::read_dir(cache_dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                println!("Cache file: {}", entry.file_name().to_string_lossy());
-            }
+        for entry in entries.flatten() {
+            println!("Cache file: {}", entry.file_name().to_string_lossy());
         }
     }
 
@@ -7,7 +7,7 @@
 //! - Prefix-based invalidation
 
 use fncache::{
-    backends::{memory::MemoryBackend, CacheBackend},
+    backends::memory::MemoryBackend,
     fncache, init_global_cache,
     invalidation::{CacheInvalidation, InvalidationCache, Tag},
     Result,
@@ -66,9 +66,8 @@ async fn main() -> Result<()> {
     println!("Config (cached): {}", config2);
 
     println!("Invalidating config:api_url...");
-    let cache = fncache::global_cache();
-    let cache_guard = cache.lock().unwrap();
-    cache_guard.remove(&"config:api_url".to_string()).await?;
+    let cache = fncache::global_cache().lock().unwrap().backend();
+    cache.remove(&"config:api_url".to_string()).await?;
 
     let config3 = get_config("api_url");
     println!("Config after invalidation: {}", config3);
@@ -86,10 +85,9 @@ async fn main() -> Result<()> {
     println!("Invalidating 'user_data' tag...");
     inv_cache.invalidate_tag(&Tag::new("user_data"))?;
 
-    let cache = fncache::global_cache();
-    let cache_guard = cache.lock().unwrap();
-    cache_guard.remove(&"user_data:101".to_string()).await?;
-    cache_guard.remove(&"user_data:102".to_string()).await?;
+    let cache = fncache::global_cache().lock().unwrap().backend();
+    cache.remove(&"user_data:101".to_string()).await?;
+    cache.remove(&"user_data:102".to_string()).await?;
 
     // User data should be recomputed, but product data should still be cached
     println!("After tag invalidation:");
@@ -104,10 +102,9 @@ async fn main() -> Result<()> {
 
     println!("Invalidating 'config' prefix...");
     inv_cache.invalidate_prefix("config")?;
-    let cache = fncache::global_cache();
-    let cache_guard = cache.lock().unwrap();
-    cache_guard.remove(&"config:db_url".to_string()).await?;
-    cache_guard.remove(&"config:api_key".to_string()).await?;
+    let cache = fncache::global_cache().lock().unwrap().backend();
+    cache.remove(&"config:db_url".to_string()).await?;
+    cache.remove(&"config:api_key".to_string()).await?;
 
     // All config items should be recomputed
     println!("After prefix invalidation:");
@@ -2,9 +2,6 @@
 
 use fncache::{backends::memory::MemoryBackend, init_global_cache, Result};
 
-use bincode;
-use futures;
-
 #[cfg(not(feature = "memory"))]
 compile_error!("This example requires the 'memory' feature to be enabled");
 
@@ -13,17 +13,36 @@ enum KeyDerivation {
 /// Parse the attributes passed to the fncache macro
 struct FncacheArgs {
     ttl: Option<u64>,
+    tti: Option<u64>,
+    pinned: bool,
+    single_flight: bool,
     key_derivation: KeyDerivation,
 }
 
 impl Parse for FncacheArgs {
     fn parse(input: ParseStream) -> Result<Self> {
-        let vars = Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let metas = Punctuated::<syn::Meta, Token![,]>::parse_terminated(input)?;
 
         let mut ttl = None;
+        let mut tti = None;
+        let mut pinned = false;
+        let mut single_flight = false;
         let mut key_derivation = KeyDerivation::Runtime;
 
-        for var in vars {
+        for meta in metas {
+            let var = match meta {
+                syn::Meta::NameValue(var) => var,
+                syn::Meta::Path(path) if path.is_ident("pinned") => {
+                    pinned = true;
+                    continue;
+                }
+                syn::Meta::Path(path) if path.is_ident("single_flight") => {
+                    single_flight = true;
+                    continue;
+                }
+                other => return Err(Error::new_spanned(other, "Unexpected argument")),
+            };
+
             let ident = var
                 .path
                 .get_ident()
@@ -36,6 +55,13 @@ impl Parse for FncacheArgs {
                     }
                     _ => return Err(Error::new_spanned(&var.lit, "ttl must be an integer")),
                 }
+            } else if ident == "tti" {
+                match &var.lit {
+                    Lit::Int(lit) => {
+                        tti = Some(lit.base10_parse()?);
+                    }
+                    _ => return Err(Error::new_spanned(&var.lit, "tti must be an integer")),
+                }
             } else if ident == "key_derivation" {
                 match &var.lit {
                     Lit::Str(lit_str) => {
@@ -63,6 +89,9 @@ impl Parse for FncacheArgs {
 
         Ok(FncacheArgs {
             ttl,
+            tti,
+            pinned,
+            single_flight,
             key_derivation,
         })
     }
@@ -70,11 +99,12 @@ impl Parse for FncacheArgs {
 
 #[proc_macro_attribute]
 pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let args = syn::parse_macro_input::parse::<FncacheArgs>(attr.clone()).unwrap_or_else(|_| {
-        FncacheArgs {
-            ttl: None,
-            key_derivation: KeyDerivation::Runtime,
-        }
+    let args = syn::parse_macro_input::parse::<FncacheArgs>(attr.clone()).unwrap_or(FncacheArgs {
+        ttl: None,
+        tti: None,
+        pinned: false,
+        single_flight: false,
+        key_derivation: KeyDerivation::Runtime,
     });
 
     let use_compile_time_keys = match args.key_derivation {
@@ -82,7 +112,22 @@ pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
         KeyDerivation::Runtime => false,
     };
 
-    let ttl_seconds = args.ttl.unwrap_or(60);
+    // A sliding `tti` without an explicit `ttl` has no absolute cap; otherwise
+    // fall back to the historical 60 second default.
+    let with_ttl = match (args.ttl, args.tti) {
+        (Some(ttl), _) => quote! { .with_ttl(Duration::from_secs(#ttl)) },
+        (None, Some(_)) => quote! {},
+        (None, None) => quote! { .with_ttl(Duration::from_secs(60u64)) },
+    };
+    let with_tti = match args.tti {
+        Some(tti) => quote! { .with_tti(Duration::from_secs(#tti)) },
+        None => quote! {},
+    };
+    let with_priority = if args.pinned {
+        quote! { .with_priority(fncache::backends::Priority::Pinned) }
+    } else {
+        quote! {}
+    };
 
     let input_fn = parse_macro_input!(item as ItemFn);
 
@@ -114,13 +159,24 @@ pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
     let arg_names1: Vec<_> = arg_names.clone().collect();
     let _arg_names2: Vec<_> = arg_names.collect();
 
-    let expanded = if is_async {
+    if args.single_flight && !is_async {
+        return Error::new_spanned(sig, "single_flight requires an async function")
+            .to_compile_error()
+            .into();
+    }
+
+    let expanded = if args.single_flight {
+        // The backend handle is cloned out of the global cache so that the
+        // guard is not held while computing: the computation may call other
+        // cached functions. The body is expanded once, into a future that is
+        // either handed to the backend or, if the backend never polled it,
+        // awaited directly; once it ran, its result is returned as is rather
+        // than computed again.
         quote! {
             #(#attrs)*
             #vis #sig {
                 use fncache::backends::CacheBackend;
                 use std::time::Duration;
-                use futures::TryFutureExt;
 
                 let key = if #use_compile_time_keys {
                     format!("{}-ct-{}", module_path!(), stringify!(#fn_name))
@@ -128,8 +184,65 @@ pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
                     format!("{}-{:?}", stringify!(#fn_name), (#(&(#arg_names1)),*))
                 };
 
-                if let Ok(cache_guard) = fncache::global_cache().lock() {
-                    if let Ok(Some(cached)) = cache_guard.get(&key).await {
+                let mut compute = Some(async move #block);
+                let backend = fncache::global_cache().lock().ok().map(|cache| cache.backend());
+                if let Some(backend) = backend {
+                    let options = fncache::backends::SetOptions::new()
+                        #with_ttl
+                        #with_tti
+                        #with_priority;
+                    let mut computed = None;
+                    let outcome = backend
+                        .get_or_compute(key, options, Box::pin(async {
+                            let result = compute
+                                .take()
+                                .expect("single-flight computation polled twice")
+                                .await;
+                            let serialized = bincode::serialize(&result)
+                                .map_err(|e| fncache::error::Error::Codec(e.to_string()));
+                            computed = Some(result);
+                            serialized
+                        }))
+                        .await;
+
+                    if let Some(result) = computed {
+                        return result;
+                    }
+                    if let Ok(cached) = outcome {
+                        if let Ok(deserialized) = bincode::deserialize::<_>(&cached) {
+                            return deserialized;
+                        }
+                    }
+                }
+
+                match compute {
+                    Some(compute) => compute.await,
+                    None => panic!(
+                        "cache backend dropped the single-flight computation of {} before it finished; \
+                         `get_or_compute` must drive a computation it polled to completion",
+                        stringify!(#fn_name)
+                    ),
+                }
+            }
+        }
+    } else if is_async {
+        // The guard is never held across an await, which would make the
+        // function's future `!Send`.
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                use fncache::backends::CacheBackend;
+                use std::time::Duration;
+
+                let key = if #use_compile_time_keys {
+                    format!("{}-ct-{}", module_path!(), stringify!(#fn_name))
+                } else {
+                    format!("{}-{:?}", stringify!(#fn_name), (#(&(#arg_names1)),*))
+                };
+
+                let backend = fncache::global_cache().lock().ok().map(|cache| cache.backend());
+                if let Some(backend) = &backend {
+                    if let Ok(Some(cached)) = backend.get(&key).await {
                         if let Ok(deserialized) = bincode::deserialize::<_>(&cached) {
                             return deserialized;
                         }
@@ -139,12 +252,12 @@ pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
                 let result = #block;
 
                 if let Ok(serialized) = bincode::serialize(&result) {
-                    if let Ok(mut cache_guard) = fncache::global_cache().lock() {
-                        let _ = cache_guard.set(
-                            key,
-                            serialized,
-                            Some(Duration::from_secs(#ttl_seconds))
-                        ).await;
+                    if let Some(backend) = &backend {
+                        let options = fncache::backends::SetOptions::new()
+                            #with_ttl
+                            #with_tti
+                            #with_priority;
+                        let _ = backend.set_with_options(key, serialized, options).await;
                     }
                 }
 
@@ -177,11 +290,13 @@ pub fn fncache(attr: TokenStream, item: TokenStream) -> TokenStream {
 
                 if let Ok(serialized) = bincode::serialize(&result) {
                     if let Ok(mut cache_guard) = fncache::global_cache().lock() {
-                        let _ = executor::block_on(cache_guard.set(
-                            key,
-                            serialized,
-                            Some(Duration::from_secs(#ttl_seconds))
-                        ));
+                        let options = fncache::backends::SetOptions::new()
+                            #with_ttl
+                            #with_tti
+                            #with_priority;
+                        let _ = executor::block_on(
+                            cache_guard.set_with_options(key, serialized, options)
+                        );
                     }
                 }
 
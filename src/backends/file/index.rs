@@ -0,0 +1,219 @@
+//! Compact index of the entry files of a [`FileBackend`](super::FileBackend).
+//!
+//! The index records the size, expiration and last access of every entry
+//! file, so that expiry sweeps and LRU eviction work from memory instead of
+//! opening every file. It is saved to the `.index` file in the base directory
+//! and reconciled with the directory listing when the backend is opened and
+//! on every janitor pass: files whose size or modification time changed,
+//! such as those written by another process, are read again.
+
+use crate::{error::Error, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, Metadata},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Magic bytes at the start of the index file.
+const INDEX_MAGIC: [u8; 3] = *b"fni";
+
+/// Version of the index format following the magic bytes.
+const INDEX_VERSION: u8 = 1;
+
+/// What the index knows about one entry file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct IndexEntry {
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Modification time of the file when it was indexed
+    modified: Option<SystemTime>,
+    /// When the entry expires (if ever)
+    pub expires_at: Option<SystemTime>,
+    /// Milliseconds since the Unix epoch of the last write or read
+    pub accessed: u64,
+}
+
+impl IndexEntry {
+    /// Describes a file from its metadata, as last accessed at `accessed`.
+    pub fn new(metadata: &Metadata, expires_at: Option<SystemTime>, accessed: SystemTime) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            expires_at,
+            accessed: millis(accessed),
+        }
+    }
+
+    /// Whether the file described by `metadata` is the one that was indexed.
+    fn matches(&self, metadata: &Metadata) -> bool {
+        self.size == metadata.len()
+            && self.modified.is_some()
+            && self.modified == metadata.modified().ok()
+    }
+
+    /// Whether the entry has expired at `now`.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+}
+
+/// Entries of the index keyed by the path of their file.
+#[derive(Debug, Default)]
+pub(super) struct Index {
+    entries: HashMap<PathBuf, IndexEntry>,
+    /// Sum of the sizes of all entries
+    total_bytes: u64,
+}
+
+impl Index {
+    /// Loads the index saved at `path` for the files under `base_dir`.
+    ///
+    /// A missing or unreadable index yields an empty one; reconciling it with
+    /// the directory rebuilds it.
+    pub fn load(path: &Path, base_dir: &Path) -> Self {
+        match Self::read(path, base_dir) {
+            Ok(index) => index,
+            Err(e) => {
+                if path.exists() {
+                    eprintln!("Warning: rebuilding file cache index: {}", e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    fn read(path: &Path, base_dir: &Path) -> Result<Self> {
+        let mut reader = io::BufReader::new(File::open(path)?);
+        let mut header = [0; 4];
+        reader.read_exact(&mut header)?;
+        if header[..3] != INDEX_MAGIC || header[3] != INDEX_VERSION {
+            return Err(Error::Codec("invalid index header".into()));
+        }
+        let saved: Vec<(PathBuf, IndexEntry)> = bincode::deserialize_from(reader)
+            .map_err(|e| Error::Codec(format!("Failed to deserialize index: {}", e)))?;
+
+        let mut index = Self::default();
+        for (relative, entry) in saved {
+            index.insert(base_dir.join(relative), entry);
+        }
+        Ok(index)
+    }
+
+    /// Saves the index to `path` through a temporary file in the same directory.
+    pub fn save(&self, path: &Path, base_dir: &Path, temp_prefix: &str) -> Result<()> {
+        let saved: Vec<(&Path, &IndexEntry)> = self
+            .entries
+            .iter()
+            .filter_map(|(path, entry)| Some((path.strip_prefix(base_dir).ok()?, entry)))
+            .collect();
+
+        let mut temp = tempfile::Builder::new()
+            .prefix(temp_prefix)
+            .tempfile_in(base_dir)?;
+        let mut writer = io::BufWriter::new(temp.as_file_mut());
+        writer.write_all(&INDEX_MAGIC)?;
+        writer.write_all(&[INDEX_VERSION])?;
+        bincode::serialize_into(&mut writer, &saved)
+            .map_err(|e| Error::Codec(format!("Failed to serialize index: {}", e)))?;
+        writer.flush()?;
+        drop(writer);
+
+        temp.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Number of indexed entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Total size of the indexed entry files in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// What the index knows about the file at `path`.
+    pub fn get(&self, path: &Path) -> Option<&IndexEntry> {
+        self.entries.get(path)
+    }
+
+    /// Whether the file at `path` is indexed and unchanged since.
+    pub fn is_current(&self, path: &Path, metadata: &Metadata) -> bool {
+        self.entries
+            .get(path)
+            .is_some_and(|entry| entry.matches(metadata))
+    }
+
+    /// Records the file at `path`, replacing what was known about it.
+    pub fn insert(&mut self, path: PathBuf, entry: IndexEntry) {
+        self.total_bytes += entry.size;
+        if let Some(old) = self.entries.insert(path, entry) {
+            self.total_bytes -= old.size;
+        }
+    }
+
+    /// Forgets the file at `path`.
+    pub fn remove(&mut self, path: &Path) -> Option<IndexEntry> {
+        let entry = self.entries.remove(path)?;
+        self.total_bytes -= entry.size;
+        Some(entry)
+    }
+
+    /// Records that the file at `from` was renamed to `to`.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        self.remove(to);
+        if let Some(entry) = self.remove(from) {
+            self.insert(to.to_path_buf(), entry);
+        }
+    }
+
+    /// Records a read of the entry at `path`.
+    pub fn touch(&mut self, path: &Path, now: SystemTime) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.accessed = millis(now);
+        }
+    }
+
+    /// Drops every entry whose path is not in `present`.
+    pub fn retain_present(&mut self, present: &HashSet<PathBuf>) {
+        let total_bytes = &mut self.total_bytes;
+        self.entries.retain(|path, entry| {
+            let keep = present.contains(path);
+            if !keep {
+                *total_bytes -= entry.size;
+            }
+            keep
+        });
+    }
+
+    /// Forgets every file.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.total_bytes = 0;
+    }
+
+    /// Paths of the entries that have expired at `now`.
+    pub fn expired(&self, now: SystemTime) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.is_expired(now))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Paths of all entries, least recently accessed first.
+    pub fn by_access(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = self.entries.iter().collect();
+        paths.sort_by_key(|(_, entry)| entry.accessed);
+        paths.into_iter().map(|(path, _)| path.clone()).collect()
+    }
+}
+
+/// Milliseconds since the Unix epoch, or 0 for earlier times.
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
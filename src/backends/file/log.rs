@@ -0,0 +1,1086 @@
+//! Log-structured storage engine for [`FileBackend`](super::FileBackend).
+//!
+//! Entries are appended as records to segment files in the `log` directory
+//! under the base directory, and an in-memory hash index maps each key to the
+//! position of its latest record. The active segment is sealed and a new one
+//! started once it reaches [`SEGMENT_BYTES`]. Removals append a tombstone.
+//!
+//! Streamed values are not copied into the active segment. A [`Spool`] writes
+//! them to a temporary file laid out as a segment with a single record, which
+//! [`LogStore::put_spooled`] then renames into place right after the active
+//! segment, sealing it.
+//!
+//! # Record Format
+//!
+//! Every segment starts with the magic bytes `fnl` and a format version. Each
+//! record then consists of:
+//!
+//! | Field        | Size     | Contents                                              |
+//! |--------------|----------|-------------------------------------------------------|
+//! | `crc`        | 4 bytes  | CRC-32 of the rest of the record, big-endian          |
+//! | `kind`       | 1 byte   | `0` for a value, `1` for a tombstone                  |
+//! | `expires_at` | 8 bytes  | Expiry in milliseconds since the Unix epoch, 0 = never |
+//! | `key_len`    | 4 bytes  | Length of the key, big-endian                         |
+//! | `value_len`  | 4 bytes  | Length of the value, big-endian                       |
+//! | `key`        | variable | UTF-8 key                                             |
+//! | `value`      | variable | Value bytes, empty for a tombstone                    |
+//!
+//! When the store is opened, the segments are replayed in order to rebuild the
+//! index. Replay of a segment stops at the first record that is truncated or
+//! fails its CRC; in the active segment that is a write torn by a crash, and
+//! the segment is truncated there.
+//!
+//! # Compaction
+//!
+//! Replaced, removed and expired records stay in their segments until a
+//! compaction seals the active segment and copies the live records of all
+//! sealed segments into one new segment that replaces them. Records are not
+//! synced to disk one by one, so a crash may lose the latest writes, but
+//! never resurrects a removed entry.
+//!
+//! A compaction runs in three steps, so that the copying does not hold up the
+//! store: [`LogStore::begin_compaction`] seals the active segment and takes a
+//! snapshot of the live records to copy, [`Compaction::run`] copies them into
+//! the merged segment on its own file handles, and
+//! [`LogStore::finish_compaction`] swaps the merged segment in. Keys written
+//! or removed in the meantime keep their newer records.
+
+use super::MappedValue;
+use crate::{error::Error, Result};
+use fs2::FileExt;
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Name of the directory under the base directory that holds the segments.
+pub(super) const LOG_DIR: &str = "log";
+
+/// Size at which the active segment is sealed.
+pub(super) const SEGMENT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Magic bytes at the start of every segment.
+const SEGMENT_MAGIC: [u8; 3] = *b"fnl";
+
+/// Version of the segment format following the magic bytes.
+const SEGMENT_VERSION: u8 = 1;
+
+/// Length of the segment header.
+const SEGMENT_HEADER_LEN: u64 = 4;
+
+/// Length of the fixed part of a record, before the key and value.
+const RECORD_HEADER_LEN: usize = 21;
+
+/// Record kind of a value.
+const KIND_VALUE: u8 = 0;
+
+/// Record kind of a tombstone.
+const KIND_TOMBSTONE: u8 = 1;
+
+/// Extension of the segment files.
+const SEGMENT_EXTENSION: &str = "seg";
+
+/// Extension of a segment being written by a compaction.
+const MERGE_EXTENSION: &str = "merge";
+
+/// Extension of a [`Spool`] before it is stored as a segment.
+const SPOOL_EXTENSION: &str = "spool";
+
+/// Where the latest record of a key is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    segment: u32,
+    offset: u64,
+    len: u64,
+    /// Expiry in milliseconds since the Unix epoch, 0 = never
+    expires_at: u64,
+}
+
+impl Location {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
+    }
+}
+
+/// An open segment file.
+#[derive(Debug)]
+struct Segment {
+    file: File,
+    /// Size of the file in bytes
+    size: u64,
+    /// Bytes of the records that are still the latest for their key
+    live: u64,
+}
+
+/// A record decoded from a segment.
+#[derive(Debug)]
+struct Record {
+    kind: u8,
+    expires_at: u64,
+    key: String,
+    value: Vec<u8>,
+}
+
+/// Result of looking a key up.
+#[derive(Debug)]
+pub(super) enum Lookup<T = Vec<u8>> {
+    /// The key's current value
+    Hit(T),
+    /// The key's value had expired and was dropped from the index
+    Expired(Vec<u8>),
+    /// The key is not stored
+    Miss,
+}
+
+/// Append-only segment store with an in-memory hash index.
+///
+/// See the [module documentation](self) for the on-disk format.
+#[derive(Debug)]
+pub(super) struct LogStore {
+    dir: PathBuf,
+    /// Locked exclusively while the store is open
+    _lock: File,
+    segments: BTreeMap<u32, Segment>,
+    active: u32,
+    index: HashMap<String, Location>,
+    /// Size at which the active segment is sealed
+    segment_bytes: u64,
+    /// Set while a [`Compaction`] of the store is alive
+    compacting: Arc<AtomicBool>,
+}
+
+/// A compaction started by [`LogStore::begin_compaction`].
+///
+/// See the [module documentation](self#compaction).
+#[derive(Debug)]
+pub(super) struct Compaction {
+    dir: PathBuf,
+    /// The sealed segments being merged
+    sealed: Vec<u32>,
+    /// Id of the newest sealed segment, which the merged segment replaces
+    target: u32,
+    /// The live records of the sealed segments, in the order they are copied
+    records: Vec<(String, Location)>,
+    /// Offsets of the records in the merged segment, once copied
+    offsets: Vec<u64>,
+    /// Size of the merged segment, once copied
+    size: u64,
+    compacting: Arc<AtomicBool>,
+}
+
+/// A value written to a segment of its own, for [`LogStore::put_spooled`].
+///
+/// The spool is a temporary file in the store directory and is written
+/// without the store, so that storing a large value does not hold it up.
+/// Dropping it before it is stored removes the file.
+#[derive(Debug)]
+pub(super) struct Spool {
+    file: tempfile::NamedTempFile,
+    key: String,
+    expires_at: u64,
+    /// Length of the record, once finished
+    len: u64,
+}
+
+impl LogStore {
+    /// Opens the store in `dir`, replaying its segments to rebuild the index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] if another process has the store open.
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let lock = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(".lock"))?;
+        FileExt::try_lock_exclusive(&lock).map_err(|_| {
+            Error::Config(format!(
+                "log store {} is open in another process",
+                dir.display()
+            ))
+        })?;
+
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            match path.extension().and_then(|e| e.to_str()) {
+                // Left behind by a compaction that crashed before replacing
+                // the segments it merged.
+                Some(MERGE_EXTENSION) => fs::remove_file(&path)?,
+                // Left behind by a streamed value that was never stored.
+                Some(SPOOL_EXTENSION) => fs::remove_file(&path)?,
+                Some(SEGMENT_EXTENSION) => {
+                    if let Some(id) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str()?.parse::<u32>().ok())
+                    {
+                        ids.push(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        ids.sort_unstable();
+
+        let mut store = Self {
+            dir: dir.to_path_buf(),
+            _lock: lock,
+            segments: BTreeMap::new(),
+            active: 0,
+            index: HashMap::new(),
+            segment_bytes: SEGMENT_BYTES,
+            compacting: Arc::new(AtomicBool::new(false)),
+        };
+        let now = now_millis();
+        for (position, &id) in ids.iter().enumerate() {
+            let is_last = position + 1 == ids.len();
+            store.replay(id, is_last, now)?;
+        }
+
+        match store.segments.keys().next_back() {
+            Some(&id) => store.active = id,
+            None => store.start_segment(ids.last().map_or(1, |id| id + 1))?,
+        }
+        Ok(store)
+    }
+
+    /// Reads segment `id` into the index.
+    fn replay(&mut self, id: u32, is_last: bool, now: u64) -> Result<()> {
+        let path = self.segment_path(id);
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        let mut reader = BufReader::new(&file);
+
+        let mut header = [0; SEGMENT_HEADER_LEN as usize];
+        if reader.read_exact(&mut header).is_err()
+            || header[..3] != SEGMENT_MAGIC
+            || header[3] != SEGMENT_VERSION
+        {
+            eprintln!(
+                "Warning: removing log segment with an invalid header: {}",
+                path.display()
+            );
+            fs::remove_file(&path)?;
+            return Ok(());
+        }
+        self.segments.insert(
+            id,
+            Segment {
+                file: file.try_clone()?,
+                size: SEGMENT_HEADER_LEN,
+                live: 0,
+            },
+        );
+
+        let mut offset = SEGMENT_HEADER_LEN;
+        loop {
+            match read_record(&mut reader, false) {
+                Ok(Some((record, len))) => {
+                    let location = Location {
+                        segment: id,
+                        offset,
+                        len,
+                        expires_at: record.expires_at,
+                    };
+                    self.segment_mut(id).size += len;
+                    if record.kind == KIND_VALUE && !location.is_expired(now) {
+                        self.insert(record.key, location);
+                    } else {
+                        self.forget(&record.key);
+                    }
+                    offset += len;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    if is_last {
+                        eprintln!(
+                            "Warning: truncating log segment {} at {}: {}",
+                            path.display(),
+                            offset,
+                            e
+                        );
+                        file.set_len(offset)?;
+                    } else {
+                        eprintln!(
+                            "Warning: skipping the rest of log segment {} from {}: {}",
+                            path.display(),
+                            offset,
+                            e
+                        );
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, id: u32) -> PathBuf {
+        segment_path(&self.dir, id)
+    }
+
+    fn segment_mut(&mut self, id: u32) -> &mut Segment {
+        self.segments.get_mut(&id).expect("segment is open")
+    }
+
+    /// Creates segment `id` and makes it the active segment.
+    fn start_segment(&mut self, id: u32) -> Result<()> {
+        let path = self.segment_path(id);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        file.write_all(&SEGMENT_MAGIC)?;
+        file.write_all(&[SEGMENT_VERSION])?;
+
+        self.segments.insert(
+            id,
+            Segment {
+                file,
+                size: SEGMENT_HEADER_LEN,
+                live: 0,
+            },
+        );
+        self.active = id;
+        Ok(())
+    }
+
+    /// Points `key` at `location`, releasing its previous record.
+    fn insert(&mut self, key: String, location: Location) {
+        self.segment_mut(location.segment).live += location.len;
+        if let Some(old) = self.index.insert(key, location) {
+            self.segment_mut(old.segment).live -= old.len;
+        }
+    }
+
+    /// Drops `key` from the index, releasing its record.
+    fn forget(&mut self, key: &str) -> Option<Location> {
+        let old = self.index.remove(key)?;
+        self.segment_mut(old.segment).live -= old.len;
+        Some(old)
+    }
+
+    /// Returns the active segment, sealing it and starting a new one first
+    /// if it is full.
+    fn writable_segment(&mut self) -> Result<u32> {
+        if self.segments[&self.active].size >= self.segment_bytes {
+            self.segment_mut(self.active).file.sync_all()?;
+            self.start_segment(self.active + 1)?;
+        }
+        Ok(self.active)
+    }
+
+    /// Appends a record to the active segment, sealing it first if full.
+    fn append(&mut self, kind: u8, key: &str, value: &[u8], expires_at: u64) -> Result<Location> {
+        let bytes = encode_record(kind, key, value, expires_at)?;
+        let active = self.writable_segment()?;
+        let segment = self.segment_mut(active);
+        segment.file.write_all(&bytes)?;
+        let location = Location {
+            segment: active,
+            offset: segment.size,
+            len: bytes.len() as u64,
+            expires_at,
+        };
+        segment.size += location.len;
+        Ok(location)
+    }
+
+    /// Reads the record at `location`.
+    fn read_at(&self, location: Location) -> Result<Record> {
+        let mut file = &self.segments[&location.segment].file;
+        file.seek(SeekFrom::Start(location.offset))?;
+        match read_record(&mut file.take(location.len), true)? {
+            Some((record, _)) => Ok(record),
+            None => Err(Error::Codec("log record is missing".into())),
+        }
+    }
+
+    /// Looks `key` up, dropping it from the index if it has expired.
+    pub fn get(&mut self, key: &str) -> Result<Lookup> {
+        let Some(&location) = self.index.get(key) else {
+            return Ok(Lookup::Miss);
+        };
+        let record = self.read_at(location)?;
+        if location.is_expired(now_millis()) {
+            self.forget(key);
+            return Ok(Lookup::Expired(record.value));
+        }
+        Ok(Lookup::Hit(record.value))
+    }
+
+    /// Maps the value of `key` from its segment, dropping the key from the
+    /// index if it has expired.
+    ///
+    /// Unlike [`LogStore::get`], this does not check the record's CRC, which
+    /// would mean reading the whole value.
+    pub fn get_mapped(&mut self, key: &str) -> Result<Lookup<MappedValue>> {
+        let Some(&location) = self.index.get(key) else {
+            return Ok(Lookup::Miss);
+        };
+        if location.is_expired(now_millis()) {
+            let record = self.read_at(location)?;
+            self.forget(key);
+            return Ok(Lookup::Expired(record.value));
+        }
+
+        let value_offset = (RECORD_HEADER_LEN + key.len()) as u64;
+        let value = MappedValue::map(
+            &self.segments[&location.segment].file,
+            location.offset + value_offset,
+            location.len - value_offset,
+        )?;
+        Ok(Lookup::Hit(value))
+    }
+
+    /// Reads the current value of `key` whether or not it has expired.
+    pub fn peek(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.index.get(key) {
+            Some(&location) => Ok(Some(self.read_at(location)?.value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether `key` has a value that has not expired.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index
+            .get(key)
+            .is_some_and(|location| !location.is_expired(now_millis()))
+    }
+
+    /// Stores `value` under `key`.
+    pub fn put(&mut self, key: String, value: &[u8], expires_at: Option<SystemTime>) -> Result<()> {
+        let expires_at = expires_at.map_or(0, |time| to_millis(time).max(1));
+        let location = self.append(KIND_VALUE, &key, value, expires_at)?;
+        self.insert(key, location);
+        Ok(())
+    }
+
+    /// Stores the record of `spool` as a segment of its own.
+    ///
+    /// The segment goes right after the active segment, which is sealed, or
+    /// replaces the active segment if that is still empty. A new active
+    /// segment follows it, so later records are replayed after this one.
+    pub fn put_spooled(&mut self, spool: Spool) -> Result<()> {
+        let active = self.active;
+        let id = if self.segments[&active].size == SEGMENT_HEADER_LEN {
+            active
+        } else {
+            self.segment_mut(active).file.sync_all()?;
+            active + 1
+        };
+        let path = self.segment_path(id);
+        spool.file.persist(&path).map_err(|e| e.error)?;
+
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        self.segments.insert(
+            id,
+            Segment {
+                file,
+                size: SEGMENT_HEADER_LEN + spool.len,
+                live: 0,
+            },
+        );
+        let location = Location {
+            segment: id,
+            offset: SEGMENT_HEADER_LEN,
+            len: spool.len,
+            expires_at: spool.expires_at,
+        };
+        self.insert(spool.key, location);
+        self.start_segment(id + 1)
+    }
+
+    /// Removes `key` by appending a tombstone, returning whether it was stored.
+    pub fn remove(&mut self, key: &str) -> Result<bool> {
+        if self.forget(key).is_none() {
+            return Ok(false);
+        }
+        self.append(KIND_TOMBSTONE, key, &[], 0)?;
+        Ok(true)
+    }
+
+    /// Drops the expired keys from the index and returns them.
+    ///
+    /// Their records already carry the expiry, so no tombstone is needed.
+    pub fn remove_expired(&mut self) -> Vec<String> {
+        let now = now_millis();
+        let expired: Vec<String> = self
+            .index
+            .iter()
+            .filter(|(_, location)| location.is_expired(now))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &expired {
+            self.forget(key);
+        }
+        expired
+    }
+
+    /// Removes every segment and starts an empty one.
+    pub fn clear(&mut self) -> Result<()> {
+        let next = self.active + 1;
+        for id in std::mem::take(&mut self.segments).into_keys() {
+            fs::remove_file(self.segment_path(id))?;
+        }
+        self.index.clear();
+        self.start_segment(next)
+    }
+
+    /// Number of stored keys.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Total size of the segments in bytes.
+    pub fn disk_bytes(&self) -> u64 {
+        self.segments.values().map(|segment| segment.size).sum()
+    }
+
+    /// Share of the segment bytes taken by records that are no longer live.
+    pub fn garbage_ratio(&self) -> f64 {
+        let (size, live) = self
+            .segments
+            .values()
+            .fold((0, 0), |(size, live), segment| {
+                (
+                    size + segment.size - SEGMENT_HEADER_LEN,
+                    live + segment.live,
+                )
+            });
+        if size == 0 {
+            0.0
+        } else {
+            (size - live) as f64 / size as f64
+        }
+    }
+
+    /// Seals the active segment and returns the compaction of the sealed
+    /// segments, for [`Compaction::run`] to copy without the store.
+    ///
+    /// Returns `None` if there is nothing to merge or another compaction is
+    /// still alive.
+    pub fn begin_compaction(&mut self) -> Result<Option<Compaction>> {
+        if self.compacting.swap(true, Ordering::AcqRel) {
+            return Ok(None);
+        }
+        // From here on, dropping the compaction clears the flag again.
+        let mut compaction = Compaction {
+            dir: self.dir.clone(),
+            sealed: Vec::new(),
+            target: 0,
+            records: Vec::new(),
+            offsets: Vec::new(),
+            size: SEGMENT_HEADER_LEN,
+            compacting: self.compacting.clone(),
+        };
+
+        if self.segments[&self.active].size > SEGMENT_HEADER_LEN {
+            self.start_segment(self.active + 1)?;
+        }
+        compaction.sealed = self
+            .segments
+            .range(..self.active)
+            .map(|(&id, _)| id)
+            .collect();
+        let Some(&target) = compaction.sealed.last() else {
+            return Ok(None);
+        };
+        compaction.target = target;
+
+        self.remove_expired();
+        compaction.records = self
+            .index
+            .iter()
+            .filter(|(_, location)| location.segment != self.active)
+            .map(|(key, location)| (key.clone(), *location))
+            .collect();
+        compaction
+            .records
+            .sort_unstable_by_key(|(_, location)| (location.segment, location.offset));
+        Ok(Some(compaction))
+    }
+
+    /// Swaps the segment merged by `compaction` in for the segments it
+    /// merged.
+    ///
+    /// Keys written, removed or expired since the compaction began keep their
+    /// current records. If the store was cleared in the meantime, the merged
+    /// segment is dropped instead.
+    pub fn finish_compaction(&mut self, compaction: Compaction) -> Result<()> {
+        let merge_path = compaction.merge_path();
+        if !compaction
+            .sealed
+            .iter()
+            .all(|id| self.segments.contains_key(id))
+        {
+            fs::remove_file(&merge_path)?;
+            return Ok(());
+        }
+
+        // The older segments go first: should this crash before the rename,
+        // their entries are lost, but no removed entry comes back.
+        for &id in &compaction.sealed {
+            self.segments.remove(&id);
+            if id != compaction.target {
+                fs::remove_file(self.segment_path(id))?;
+            }
+        }
+        let path = self.segment_path(compaction.target);
+        fs::rename(&merge_path, &path)?;
+
+        let mut live = 0;
+        for ((key, old), &offset) in compaction.records.iter().zip(&compaction.offsets) {
+            if self.index.get(key) == Some(old) {
+                let location = Location {
+                    segment: compaction.target,
+                    offset,
+                    ..*old
+                };
+                self.index.insert(key.clone(), location);
+                live += location.len;
+            }
+        }
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        self.segments.insert(
+            compaction.target,
+            Segment {
+                file,
+                size: compaction.size,
+                live,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Spool {
+    /// Starts spooling the value of `key` to a temporary file in `dir`.
+    pub fn create(dir: &Path, key: String, expires_at: Option<SystemTime>) -> Result<Self> {
+        let expires_at = expires_at.map_or(0, |time| to_millis(time).max(1));
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{}", SPOOL_EXTENSION))
+            .tempfile_in(dir)?;
+        file.write_all(&SEGMENT_MAGIC)?;
+        file.write_all(&[SEGMENT_VERSION])?;
+        // The value length is only known at the end, so the header is
+        // written again by `finish`.
+        file.write_all(&record_header(KIND_VALUE, &key, 0, expires_at)?)?;
+        Ok(Self {
+            file,
+            key,
+            expires_at,
+            len: 0,
+        })
+    }
+
+    /// The file to write the value to, after the record header.
+    pub fn file_mut(&mut self) -> &mut File {
+        self.file.as_file_mut()
+    }
+
+    /// Completes the record once the `value_len` bytes of the value, with
+    /// CRC-32 `value_crc`, are written, and flushes the spool to disk.
+    pub fn finish(mut self, value_len: u64, value_crc: crc32fast::Hasher) -> Result<Self> {
+        let mut header = record_header(KIND_VALUE, &self.key, value_len, self.expires_at)?;
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(&header[4..]);
+        crc.combine(&value_crc);
+        header[..4].copy_from_slice(&crc.finalize().to_be_bytes());
+
+        let file = self.file.as_file_mut();
+        file.seek(SeekFrom::Start(SEGMENT_HEADER_LEN))?;
+        file.write_all(&header)?;
+        file.sync_all()?;
+        self.len = header.len() as u64 + value_len;
+        Ok(self)
+    }
+}
+
+impl Compaction {
+    /// Copies the records of the compaction into the merged segment.
+    ///
+    /// Does blocking I/O on file handles of its own, without the store.
+    pub fn run(mut self) -> Result<Self> {
+        // Sealed by `begin_compaction` without waiting for the disk.
+        File::open(segment_path(&self.dir, self.target))?.sync_all()?;
+
+        let mut merged = io::BufWriter::new(File::create(self.merge_path())?);
+        merged.write_all(&SEGMENT_MAGIC)?;
+        merged.write_all(&[SEGMENT_VERSION])?;
+
+        let mut sources: HashMap<u32, File> = HashMap::new();
+        let mut offset = SEGMENT_HEADER_LEN;
+        for (_, location) in &self.records {
+            let file = match sources.entry(location.segment) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    entry.insert(File::open(segment_path(&self.dir, location.segment))?)
+                }
+            };
+            file.seek(SeekFrom::Start(location.offset))?;
+            if io::copy(&mut file.take(location.len), &mut merged)? < location.len {
+                return Err(Error::Codec("log record is missing".into()));
+            }
+            self.offsets.push(offset);
+            offset += location.len;
+        }
+        let merged = merged.into_inner().map_err(|e| e.into_error())?;
+        merged.sync_all()?;
+
+        self.size = offset;
+        Ok(self)
+    }
+
+    fn merge_path(&self) -> PathBuf {
+        segment_path(&self.dir, self.target).with_extension(MERGE_EXTENSION)
+    }
+}
+
+impl Drop for Compaction {
+    fn drop(&mut self) {
+        self.compacting.store(false, Ordering::Release);
+    }
+}
+
+/// Path of segment `id` in the store directory `dir`.
+fn segment_path(dir: &Path, id: u32) -> PathBuf {
+    dir.join(format!("{:08}.{}", id, SEGMENT_EXTENSION))
+}
+
+/// Encodes a record as described in the [module documentation](self).
+fn encode_record(kind: u8, key: &str, value: &[u8], expires_at: u64) -> Result<Vec<u8>> {
+    let mut bytes = record_header(kind, key, value.len() as u64, expires_at)?;
+    bytes.extend_from_slice(value);
+
+    let crc = crc32fast::hash(&bytes[4..]);
+    bytes[..4].copy_from_slice(&crc.to_be_bytes());
+    Ok(bytes)
+}
+
+/// Encodes the fixed part and the key of a record, leaving the CRC zeroed.
+fn record_header(kind: u8, key: &str, value_len: u64, expires_at: u64) -> Result<Vec<u8>> {
+    let key_len = u32::try_from(key.len())
+        .map_err(|_| Error::Backend("key is too large for the log engine".into()))?;
+    let value_len = u32::try_from(value_len)
+        .map_err(|_| Error::Backend("value is too large for the log engine".into()))?;
+
+    let mut bytes = Vec::with_capacity(RECORD_HEADER_LEN + key.len());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.push(kind);
+    bytes.extend_from_slice(&expires_at.to_be_bytes());
+    bytes.extend_from_slice(&key_len.to_be_bytes());
+    bytes.extend_from_slice(&value_len.to_be_bytes());
+    bytes.extend_from_slice(key.as_bytes());
+    Ok(bytes)
+}
+
+/// Reads the next record and its length, or `None` at the end of the segment.
+///
+/// Without `with_value`, the value is checked against the CRC a piece at a
+/// time and left out of the returned record, so that replaying a segment
+/// never holds a whole value in memory.
+fn read_record(reader: &mut impl Read, with_value: bool) -> Result<Option<(Record, u64)>> {
+    let mut header = [0; RECORD_HEADER_LEN];
+    let read = read_full(reader, &mut header)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if read < RECORD_HEADER_LEN {
+        return Err(Error::Codec("truncated log record header".into()));
+    }
+
+    let crc = u32::from_be_bytes(header[0..4].try_into().expect("4 bytes"));
+    let kind = header[4];
+    let expires_at = u64::from_be_bytes(header[5..13].try_into().expect("8 bytes"));
+    let key_len = u32::from_be_bytes(header[13..17].try_into().expect("4 bytes")) as usize;
+    let value_len = u32::from_be_bytes(header[17..21].try_into().expect("4 bytes")) as usize;
+
+    let truncated = || Error::Codec("truncated log record".into());
+    let mut key = vec![0; key_len];
+    if read_full(reader, &mut key)? < key_len {
+        return Err(truncated());
+    }
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[4..]);
+    hasher.update(&key);
+
+    let mut value = Vec::new();
+    if with_value {
+        value.resize(value_len, 0);
+        if read_full(reader, &mut value)? < value_len {
+            return Err(truncated());
+        }
+        hasher.update(&value);
+    } else {
+        let mut buf = [0; 8192];
+        let mut remaining = value_len;
+        while remaining > 0 {
+            let want = remaining.min(buf.len());
+            let read = read_full(reader, &mut buf[..want])?;
+            if read == 0 {
+                return Err(truncated());
+            }
+            hasher.update(&buf[..read]);
+            remaining -= read;
+        }
+    }
+    if hasher.finalize() != crc || kind > KIND_TOMBSTONE {
+        return Err(Error::Codec("log record failed its CRC check".into()));
+    }
+
+    let key = String::from_utf8(key)
+        .map_err(|e| Error::Codec(format!("invalid log record key: {}", e)))?;
+    let len = (RECORD_HEADER_LEN + key_len + value_len) as u64;
+    Ok(Some((
+        Record {
+            kind,
+            expires_at,
+            key,
+            value,
+        },
+        len,
+    )))
+}
+
+/// Reads until `buf` is full or the reader is exhausted, returning the count.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64
+}
+
+fn now_millis() -> u64 {
+    to_millis(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn compact(store: &mut LogStore) {
+        if let Some(compaction) = store.begin_compaction().unwrap() {
+            let compaction = compaction.run().unwrap();
+            store.finish_compaction(compaction).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let bytes = encode_record(KIND_VALUE, "key", b"value", 42).unwrap();
+        let (record, len) = read_record(&mut bytes.as_slice(), true).unwrap().unwrap();
+        assert_eq!(len, bytes.len() as u64);
+        assert_eq!(
+            (record.kind, record.expires_at, record.key.as_str()),
+            (KIND_VALUE, 42, "key")
+        );
+        assert_eq!(record.value, b"value");
+
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(read_record(&mut corrupted.as_slice(), true).is_err());
+        assert!(read_record(&mut corrupted.as_slice(), false).is_err());
+        assert!(read_record(&mut &bytes[..10], true).is_err());
+        assert!(read_record(&mut &[][..], true).unwrap().is_none());
+
+        let (record, skipped_len) = read_record(&mut bytes.as_slice(), false).unwrap().unwrap();
+        assert_eq!(record.key, "key");
+        assert!(record.value.is_empty());
+        assert_eq!(skipped_len, len);
+    }
+
+    #[test]
+    fn test_replay_restores_the_latest_records() {
+        let dir = tempdir().unwrap();
+        {
+            let mut store = LogStore::open(dir.path()).unwrap();
+            store.put("a".into(), b"1", None).unwrap();
+            store.put("a".into(), b"2", None).unwrap();
+            store.put("b".into(), b"3", None).unwrap();
+            store.remove("b").unwrap();
+            store
+                .put(
+                    "c".into(),
+                    b"4",
+                    Some(SystemTime::now() - Duration::from_secs(1)),
+                )
+                .unwrap();
+        }
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(matches!(store.get("a").unwrap(), Lookup::Hit(v) if v == b"2"));
+        assert!(matches!(store.get("b").unwrap(), Lookup::Miss));
+    }
+
+    #[test]
+    fn test_torn_write_is_truncated() {
+        let dir = tempdir().unwrap();
+        let path = {
+            let mut store = LogStore::open(dir.path()).unwrap();
+            store.put("a".into(), b"1", None).unwrap();
+            store.put("b".into(), b"2", None).unwrap();
+            store.segment_path(store.active)
+        };
+        let size = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(size - 3)
+            .unwrap();
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert!(matches!(store.get("a").unwrap(), Lookup::Hit(_)));
+        assert!(matches!(store.get("b").unwrap(), Lookup::Miss));
+        store.put("c".into(), b"3", None).unwrap();
+        drop(store);
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert!(matches!(store.get("c").unwrap(), Lookup::Hit(v) if v == b"3"));
+    }
+
+    #[test]
+    fn test_compaction_keeps_only_live_records() {
+        let dir = tempdir().unwrap();
+        let mut store = LogStore::open(dir.path()).unwrap();
+        store.segment_bytes = 64;
+        for i in 0..20 {
+            store.put(format!("key{}", i % 5), &[i; 16], None).unwrap();
+        }
+        store.remove("key0").unwrap();
+        assert!(store.segments.len() > 2);
+        assert!(store.garbage_ratio() > 0.5);
+
+        compact(&mut store);
+        assert_eq!(store.segments.len(), 2);
+        assert_eq!(store.garbage_ratio(), 0.0);
+        assert!(matches!(store.get("key4").unwrap(), Lookup::Hit(v) if v == [19; 16]));
+        drop(store);
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert_eq!(store.len(), 4);
+        assert!(matches!(store.get("key0").unwrap(), Lookup::Miss));
+        assert!(matches!(store.get("key1").unwrap(), Lookup::Hit(v) if v == [16; 16]));
+    }
+
+    #[test]
+    fn test_writes_during_compaction_keep_their_records() {
+        let dir = tempdir().unwrap();
+        let mut store = LogStore::open(dir.path()).unwrap();
+        for key in ["kept", "replaced", "removed"] {
+            store.put(key.to_string(), b"old", None).unwrap();
+        }
+
+        let compaction = store.begin_compaction().unwrap().unwrap();
+        assert!(store.begin_compaction().unwrap().is_none());
+        store.put("replaced".to_string(), b"new", None).unwrap();
+        store.remove("removed").unwrap();
+        let compaction = compaction.run().unwrap();
+        store.finish_compaction(compaction).unwrap();
+
+        assert!(matches!(store.get("kept").unwrap(), Lookup::Hit(v) if v == b"old"));
+        assert!(matches!(store.get("replaced").unwrap(), Lookup::Hit(v) if v == b"new"));
+        assert!(matches!(store.get("removed").unwrap(), Lookup::Miss));
+        drop(store);
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(matches!(store.get("replaced").unwrap(), Lookup::Hit(v) if v == b"new"));
+        assert!(matches!(store.get("removed").unwrap(), Lookup::Miss));
+        assert!(store.begin_compaction().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_get_mapped_reads_the_value_region() {
+        let dir = tempdir().unwrap();
+        let mut store = LogStore::open(dir.path()).unwrap();
+        store.put("a".into(), b"first", None).unwrap();
+        store.put("bb".into(), b"second", None).unwrap();
+        store.put("empty".into(), b"", None).unwrap();
+
+        assert!(matches!(store.get_mapped("bb").unwrap(), Lookup::Hit(v) if &*v == b"second"));
+        assert!(matches!(store.get_mapped("empty").unwrap(), Lookup::Hit(v) if v.is_empty()));
+        assert!(matches!(store.get_mapped("c").unwrap(), Lookup::Miss));
+    }
+
+    fn spool(dir: &Path, key: &str, value: &[u8]) -> Spool {
+        let mut spool = Spool::create(dir, key.into(), None).unwrap();
+        spool.file_mut().write_all(value).unwrap();
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(value);
+        spool.finish(value.len() as u64, crc).unwrap()
+    }
+
+    #[test]
+    fn test_put_spooled_stores_the_value_as_a_segment() {
+        let dir = tempdir().unwrap();
+        let mut store = LogStore::open(dir.path()).unwrap();
+        let value = vec![7; 100_000];
+
+        // Into the empty active segment, then after a non-empty one.
+        store.put_spooled(spool(dir.path(), "big", &value)).unwrap();
+        store.put("key".into(), b"old", None).unwrap();
+        store
+            .put_spooled(spool(dir.path(), "key", b"spooled"))
+            .unwrap();
+        assert!(matches!(store.get("big").unwrap(), Lookup::Hit(v) if v == value));
+
+        // A spool that is never stored leaves nothing behind.
+        drop(spool(dir.path(), "abandoned", b"value"));
+        store.put("after".into(), b"value", None).unwrap();
+        drop(store);
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert!(matches!(store.get("big").unwrap(), Lookup::Hit(v) if v == value));
+        assert!(matches!(store.get("key").unwrap(), Lookup::Hit(v) if v == b"spooled"));
+        assert!(matches!(store.get("abandoned").unwrap(), Lookup::Miss));
+        assert!(matches!(store.get("after").unwrap(), Lookup::Hit(v) if v == b"value"));
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_leftover_spool_is_removed_on_open() {
+        let dir = tempdir().unwrap();
+        drop(LogStore::open(dir.path()).unwrap());
+        let spool = spool(dir.path(), "key", b"value");
+        let (_, path) = spool.file.keep().unwrap();
+
+        let mut store = LogStore::open(dir.path()).unwrap();
+        assert!(!path.exists());
+        assert!(matches!(store.get("key").unwrap(), Lookup::Miss));
+    }
+
+    #[test]
+    fn test_store_is_locked_while_open() {
+        let dir = tempdir().unwrap();
+        let store = LogStore::open(dir.path()).unwrap();
+        assert!(matches!(LogStore::open(dir.path()), Err(Error::Config(_))));
+        drop(store);
+        assert!(LogStore::open(dir.path()).is_ok());
+    }
+}
@@ -0,0 +1,65 @@
+//! Memory-mapped values returned by [`FileBackend::get_mapped`](super::FileBackend::get_mapped).
+
+use memmap2::{Mmap, MmapOptions};
+use std::{fmt, fs::File, io, ops::Deref};
+
+/// A cached value mapped into memory from the file that stores it.
+///
+/// Dereferences to the value bytes. The bytes are paged in by the operating
+/// system as they are read instead of being copied into a buffer up front, so
+/// large values cost no more memory than the pages actually touched.
+///
+/// The mapping stays valid after the entry is replaced, removed or cleared:
+/// `FileBackend` never modifies the bytes of an entry in place, and an
+/// unlinked file stays readable for as long as it is mapped. Modifying the
+/// cache files with other tools while a value is mapped is not supported.
+pub struct MappedValue {
+    /// `None` for an empty value, which cannot be mapped
+    mmap: Option<Mmap>,
+}
+
+impl MappedValue {
+    /// Maps `len` bytes of `file` starting at `offset`.
+    ///
+    /// The caller checks that the range lies within the file, since touching a
+    /// mapped page past its end raises `SIGBUS`.
+    pub(super) fn map(file: &File, offset: u64, len: u64) -> io::Result<Self> {
+        if len == 0 {
+            return Ok(Self { mmap: None });
+        }
+        let len = usize::try_from(len)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "value is too large to map"))?;
+        // SAFETY: the cache never writes to an entry or record after it is
+        // complete; new values are renamed over old files or appended to a
+        // segment, so the mapped range is not modified while it is mapped.
+        let mmap = unsafe { MmapOptions::new().offset(offset).len(len).map(file)? };
+        Ok(Self { mmap: Some(mmap) })
+    }
+
+    /// Returns the value bytes.
+    pub fn as_slice(&self) -> &[u8] {
+        self.mmap.as_deref().unwrap_or_default()
+    }
+}
+
+impl Deref for MappedValue {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for MappedValue {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for MappedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedValue")
+            .field("len", &self.len())
+            .finish()
+    }
+}
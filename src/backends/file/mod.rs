@@ -0,0 +1,2219 @@
+//! File-based cache backend implementation.
+//!
+//! This backend stores cache entries as individual files in the local filesystem,
+//! providing persistent storage between application restarts. It uses a simple directory
+//! structure with content-addressed storage based on key hashing to ensure proper
+//! filesystem compatibility.
+//!
+//! # Features
+//!
+//! * Persistent storage that survives application restarts
+//! * TTL (time-to-live) support for expiring entries
+//! * Disk quota with LRU eviction and an optional background janitor
+//! * Thread-safe access using async locks
+//! * Built-in metrics for hits, misses, and insertions
+//! * Efficient storage with binary serialization
+//!
+//! # Usage
+//!
+//! The file backend requires a base directory path where it will store all cached data.
+//! It will automatically create the directory structure as needed.
+//!
+//! ```rust,no_run
+//! use fncache::{backends::file::FileBackend, init_global_cache, fncache};
+//! use std::time::Duration;
+//!
+//! // Initialize the file backend with a directory path
+//! let cache_dir = "/tmp/fncache";
+//! let backend = FileBackend::new(cache_dir).unwrap();
+//! init_global_cache(backend).unwrap();
+//!
+//! // Define a cached function with TTL of 60 seconds
+//! #[fncache(ttl = 60)]
+//! fn compute_value(input: u32) -> String {
+//!     println!("Computing value for {}", input);
+//!     format!("value_{}", input)
+//! }
+//!
+//! // Call the function - first time will execute and store result
+//! let result1 = compute_value(42);
+//! // Second call with same input will use the cached value
+//! let result2 = compute_value(42);
+//! ```
+//!
+//! # Storage Format
+//!
+//! The file backend stores each cache entry in its own file using a path derived from the key:
+//!
+//! - Keys are hashed with 64-bit FNV-1a, a fixed and documented hash, so the same key maps to
+//!   the same file across Rust releases and platforms
+//! - Files are organized in a two-level directory structure (first two characters of hash as directory)
+//! - Each file starts with a fixed-width header, followed by the full key and then the value:
+//!
+//! | Offset | Size     | Contents                                                      |
+//! |--------|----------|---------------------------------------------------------------|
+//! | 0      | 3 bytes  | Magic bytes `fnc`                                              |
+//! | 3      | 1 byte   | Format version, currently 2                                    |
+//! | 4      | 8 bytes  | Expiry in milliseconds since the Unix epoch, 0 = never, big-endian |
+//! | 12     | 4 bytes  | Key length, big-endian                                         |
+//! | 16     | 8 bytes  | Value length, big-endian                                       |
+//! | 24     | variable | UTF-8 key                                                      |
+//! | ...    | variable | Value bytes                                                    |
+//!
+//! Lookups read only the header and the key until they find the right entry, and the value
+//! can be memory-mapped in place with [`FileBackend::get_mapped`].
+//!
+//! Since the key is stored with the value, a read verifies it and never returns the value of a
+//! different key. Keys whose hashes collide are chained: the first takes the file named after
+//! the hash, the next ones take `<hash>.1`, `<hash>.2` and so on, and removing an entry moves the
+//! last one of its chain into the freed slot. Files without a valid header, such as those written
+//! by earlier releases, are removed by the expiry sweep.
+//!
+//! # Memory-Mapped Reads
+//!
+//! For large values, [`FileBackend::get_mapped`] returns a [`MappedValue`] that maps the value
+//! bytes of the entry file (or of the log segment) into memory instead of reading them into a
+//! `Vec<u8>`. Nothing is copied up front; the operating system pages the value in from its page
+//! cache as it is read.
+//!
+//! # Streaming
+//!
+//! `FileBackend` implements [`StreamingBackend`]. `put_stream` copies the value into a temporary
+//! file a chunk at a time, before taking any lock, and only locks the directory to rename the
+//! finished file into place, so a slow upload does not hold up other operations. Its temporary
+//! files start with `.stream-` and are removed by the sweep once an hour has passed without
+//! writes to them. Under the log engine, the value is spooled to a segment of its own, which is
+//! renamed in after the active segment; log records are limited to 4 GiB. `get_stream` reads through
+//! [`FileBackend::get_mapped`].
+//!
+//! # Disk Quota and Janitor
+//!
+//! [`FileBackend::with_max_disk_bytes`] and [`FileBackend::with_max_entries`] bound the cache.
+//! Once a write takes it over a limit, expired entries are removed first, then the least
+//! recently read or written entries, until the cache is down to 90% of the limit, so that the
+//! following writes do not each have to evict.
+//!
+//! Sizes, expiration times and access times are kept in an index that is saved to the `.index`
+//! file in the base directory. Expired entries are removed when they are read, and
+//! [`FileBackend::spawn_janitor`] removes the others on an interval from the index alone,
+//! without opening every entry file. The janitor also brings the index up to date with entries
+//! written or removed by other processes sharing the directory; until it does, the limits only
+//! account for this process's writes.
+//!
+//! # Crash Safety and Sharing
+//!
+//! Entries are written to a temporary file in the target directory, flushed to disk and then
+//! renamed over the entry file, so a crash never leaves a truncated entry behind: readers see
+//! either the old entry or the new one. Temporary files orphaned by a crash are removed when a
+//! backend is opened on the directory and by the expiry sweep.
+//!
+//! Several processes can share one cache directory. Besides the in-process lock, every
+//! operation takes an advisory lock (`flock` on Unix, `LockFileEx` on Windows) on the `.lock`
+//! file in the base directory: shared for lookups, exclusive for anything that writes or removes
+//! files. Waiting for another process to release it happens on tokio's blocking thread pool. The
+//! lock is advisory, so tools other than `FileBackend` that modify the directory are not
+//! excluded.
+//!
+//! # Log Engine
+//!
+//! One file per entry is simple, but slow for millions of small values and costly in inodes.
+//! [`FileBackend::with_engine`] with [`Engine::Log`] switches to a log-structured engine in the
+//! style of Bitcask instead: entries are appended as CRC-checked records to segment files in the
+//! `log` directory, with the TTL stored in the record and tombstones for removals, and an
+//! in-memory hash index points at each key's latest record. The index is rebuilt by replaying the
+//! segments when the engine is opened.
+//!
+//! Old records are reclaimed by [`FileBackend::compact`], which merges the live records of all
+//! sealed segments into one; the janitor runs it once more than half of the segment bytes are
+//! garbage. The records are copied on tokio's blocking thread pool while the engine keeps serving
+//! reads and writes, and the merged segment is swapped in at the end. The log engine is used by
+//! one process at a time, and the disk quota does not apply to it.
+//!
+//! # Removal Listeners
+//!
+//! Listeners registered with [`FileBackend::on_removal`] are told about
+//! explicit removals, replaced values, evicted entries and expired entries,
+//! whether they are found expired when read or by the janitor. Entries dropped
+//! by `clear`, and entries the janitor expires under the log engine, are not
+//! reported.
+
+mod index;
+mod log;
+mod mapped;
+
+use crate::{
+    backends::{
+        read_chunk, CacheBackend, EvictionReason, RemovalCause, RemovalListener, RemovalListeners,
+        StreamingBackend, ValueReader, STREAM_CHUNK_SIZE,
+    },
+    error::Error,
+    metrics::Metrics,
+    Result,
+};
+use fs2::FileExt;
+use index::{Index, IndexEntry};
+use log::{LogStore, Lookup};
+pub use mapped::MappedValue;
+use std::{
+    collections::HashSet,
+    fs::{self, create_dir_all, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{io::AsyncRead, sync::RwLock};
+
+/// Magic bytes at the start of every entry file.
+const ENTRY_MAGIC: [u8; 3] = *b"fnc";
+
+/// Version of the entry format following the magic bytes.
+const ENTRY_VERSION: u8 = 2;
+
+/// Length of the fixed-width entry header, before the key.
+const ENTRY_HEADER_LEN: usize = 24;
+
+/// Name of the file in the base directory that processes lock to share it.
+const LOCK_FILE: &str = ".lock";
+
+/// Name of the file in the base directory that the index is saved to.
+const INDEX_FILE: &str = ".index";
+
+/// Share of garbage in the log segments above which the janitor compacts them.
+const COMPACTION_GARBAGE_RATIO: f64 = 0.5;
+
+/// Prefix of the temporary files entries are written to before being
+/// renamed into place.
+const TEMP_PREFIX: &str = ".tmp-";
+
+/// Prefix of the temporary files that streamed values are written to.
+///
+/// Unlike other temporary files, these are written before the directory is
+/// locked, so a sweep only removes them once they are [`STALE_STREAM_AGE`] old.
+const STREAM_PREFIX: &str = ".stream-";
+
+/// How long a streamed write may go without writing to its temporary file
+/// before the sweep considers it abandoned.
+const STALE_STREAM_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// An open entry file and its decoded header.
+///
+/// The value is read or mapped from the same open file, so it belongs to the
+/// header even if the entry is replaced in the meantime.
+#[derive(Debug)]
+struct EntryFile {
+    file: File,
+    /// The key the entry was stored under
+    key: String,
+    /// When the entry expires (if ever)
+    /// If None, the entry never expires
+    expires_at: Option<SystemTime>,
+    /// Length of the value in bytes
+    value_len: u64,
+}
+
+impl EntryFile {
+    /// Opens the entry at `path`, if it exists and has a valid header.
+    fn open(path: &Path) -> Option<Self> {
+        Self::decode(File::open(path).ok()?).ok()
+    }
+
+    /// Decodes the header and key of an entry written by
+    /// [`FileBackend::write_entry`].
+    fn decode(file: File) -> Result<Self> {
+        let mut reader = io::BufReader::new(&file);
+        let mut header = [0; ENTRY_HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| Error::Codec(format!("Failed to read cache entry header: {}", e)))?;
+        if header[..3] != ENTRY_MAGIC || header[3] != ENTRY_VERSION {
+            return Err(Error::Codec("invalid cache entry header".into()));
+        }
+        let expires_at = u64::from_be_bytes(header[4..12].try_into().expect("8 bytes"));
+        let key_len = u32::from_be_bytes(header[12..16].try_into().expect("4 bytes"));
+        let value_len = u64::from_be_bytes(header[16..24].try_into().expect("8 bytes"));
+
+        let mut key = vec![0; key_len as usize];
+        reader
+            .read_exact(&mut key)
+            .map_err(|e| Error::Codec(format!("Failed to read cache entry key: {}", e)))?;
+        let key = String::from_utf8(key)
+            .map_err(|e| Error::Codec(format!("invalid cache entry key: {}", e)))?;
+        drop(reader);
+
+        let entry = Self {
+            file,
+            key,
+            expires_at: (expires_at != 0).then(|| UNIX_EPOCH + Duration::from_millis(expires_at)),
+            value_len,
+        };
+        // A mapped read past the end of the file would fault.
+        if entry.file.metadata()?.len() < entry.value_offset() + value_len {
+            return Err(Error::Codec("truncated cache entry".into()));
+        }
+        Ok(entry)
+    }
+
+    fn value_offset(&self) -> u64 {
+        (ENTRY_HEADER_LEN + self.key.len()) as u64
+    }
+
+    /// Whether the entry has expired at `now`.
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    /// Reads the value into memory.
+    fn read_value(&self) -> Result<Vec<u8>> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(self.value_offset()))?;
+        let mut value = vec![0; self.value_len as usize];
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    /// Maps the value into memory.
+    fn map_value(&self) -> Result<MappedValue> {
+        Ok(MappedValue::map(
+            &self.file,
+            self.value_offset(),
+            self.value_len,
+        )?)
+    }
+}
+
+/// Storage engine of a [`FileBackend`].
+///
+/// See the [module documentation](self#log-engine) for how the engines differ.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// One file per entry, in directories named after the key hash.
+    #[default]
+    Files,
+    /// Append-only segment files with an in-memory index.
+    Log,
+}
+
+/// File-based cache backend for persistent storage
+///
+/// This backend stores cache entries as individual files in a directory structure,
+/// providing persistent cache storage that survives application restarts.
+/// Each key is hashed to create a safe file path, ensuring filesystem compatibility
+/// regardless of the characters in the original cache keys.
+///
+/// # Features
+///
+/// * Disk-based persistent storage
+/// * TTL (time-to-live) support
+/// * Disk quota with LRU eviction and a background janitor
+/// * Thread-safe file access using async locks
+/// * Metrics collection
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use fncache::backends::file::FileBackend;
+/// use fncache::backends::CacheBackend;
+/// use std::time::Duration;
+///
+/// # async fn run() -> fncache::Result<()> {
+/// // Create a file backend with a specific storage directory
+/// let backend = FileBackend::new("/path/to/cache")?;
+///
+/// // Store a value with 5-minute TTL
+/// let key = "user:profile:123".to_string();
+/// let value = b"{\"name\": \"John Doe\"}".to_vec();
+/// backend.set(key.clone(), value, Some(Duration::from_secs(300))).await?;
+///
+/// // Retrieve the value later
+/// if let Some(data) = backend.get(&key).await? {
+///     println!("Retrieved {} bytes from cache", data.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FileBackend {
+    /// Base directory for cache files
+    base_dir: PathBuf,
+    /// Cache metrics
+    metrics: Arc<Metrics>,
+    /// Lock to ensure thread-safety for file operations; the lock file
+    /// provides the same between processes
+    file_lock: RwLock<()>,
+    /// Callbacks notified when entries are removed
+    listeners: RemovalListeners,
+    /// Sizes, expiration and access times of the entry files
+    index: Mutex<Index>,
+    /// Largest total size of the entry files, if bounded
+    max_disk_bytes: Option<u64>,
+    /// Largest number of entries, if bounded
+    max_entries: Option<usize>,
+    /// The log store, when the log engine is selected
+    log: Option<Mutex<LogStore>>,
+}
+
+impl FileBackend {
+    /// Creates a new FileBackend with the specified base directory.
+    ///
+    /// # Arguments
+    /// * `base_dir` - The base directory where cache files will be stored
+    ///
+    /// # Returns
+    /// A new FileBackend instance
+    ///
+    /// Temporary files left behind by a writer that crashed are removed. This
+    /// waits for the writes of other processes sharing the directory to finish.
+    ///
+    /// # Errors
+    /// Returns an error if the base directory could not be created or locked
+    pub fn new<P: AsRef<Path>>(base_dir: P) -> Result<Self> {
+        let path = base_dir.as_ref().to_path_buf();
+        create_dir_all(&path)?;
+
+        let backend = Self {
+            base_dir: path,
+            metrics: Arc::new(Metrics::new()),
+            file_lock: RwLock::new(()),
+            listeners: RemovalListeners::default(),
+            index: Mutex::new(Index::default()),
+            max_disk_bytes: None,
+            max_entries: None,
+            log: None,
+        };
+        backend.recover()?;
+        Ok(backend)
+    }
+
+    /// Bounds the total size of the entry files.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// See the [module documentation](self#disk-quota-and-janitor) for how
+    /// entries are evicted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::file::FileBackend;
+    ///
+    /// let backend = FileBackend::new("/tmp/fncache")
+    ///     .unwrap()
+    ///     .with_max_disk_bytes(512 * 1024 * 1024)
+    ///     .with_max_entries(100_000);
+    /// ```
+    pub fn with_max_disk_bytes(mut self, max_disk_bytes: u64) -> Self {
+        self.max_disk_bytes = Some(max_disk_bytes);
+        self
+    }
+
+    /// Bounds the number of entries.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Selects the storage engine.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// Selecting [`Engine::Log`] opens the log store in the `log` directory
+    /// and replays its segments. Entries stored by the other engine are not
+    /// visible through this one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log store could not be opened, including
+    /// [`Error::Config`] if another process has it open.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::file::{Engine, FileBackend};
+    ///
+    /// let backend = FileBackend::new("/tmp/fncache")
+    ///     .unwrap()
+    ///     .with_engine(Engine::Log)
+    ///     .unwrap();
+    /// ```
+    pub fn with_engine(mut self, engine: Engine) -> Result<Self> {
+        self.log = match engine {
+            Engine::Files => None,
+            Engine::Log => Some(Mutex::new(LogStore::open(
+                &self.base_dir.join(log::LOG_DIR),
+            )?)),
+        };
+        Ok(self)
+    }
+
+    /// Returns the storage engine in use.
+    pub fn engine(&self) -> Engine {
+        if self.log.is_some() {
+            Engine::Log
+        } else {
+            Engine::Files
+        }
+    }
+
+    /// Returns the total size of the entry files known to this backend.
+    pub fn disk_bytes(&self) -> u64 {
+        match self.log() {
+            Some(log) => log.disk_bytes(),
+            None => self.index().total_bytes(),
+        }
+    }
+
+    /// Returns the number of entries known to this backend.
+    pub fn entry_count(&self) -> usize {
+        match self.log() {
+            Some(log) => log.len(),
+            None => self.index().len(),
+        }
+    }
+
+    /// Retrieves a value by memory-mapping it from the file that stores it.
+    ///
+    /// Behaves like [`CacheBackend::get`], but the returned [`MappedValue`]
+    /// reads the value in place instead of copying it into a `Vec<u8>`, which
+    /// pays off for values of many megabytes. With the log engine, the value
+    /// is not checked against its record's CRC. See the
+    /// [module documentation](self#memory-mapped-reads).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::file::FileBackend;
+    /// use fncache::backends::CacheBackend;
+    ///
+    /// # async fn run() -> fncache::Result<()> {
+    /// let backend = FileBackend::new("/path/to/cache")?;
+    /// backend
+    ///     .set("features".to_string(), vec![0; 100 * 1024 * 1024], None)
+    ///     .await?;
+    ///
+    /// if let Some(features) = backend.get_mapped("features").await? {
+    ///     println!("First byte of {}: {}", features.len(), features[0]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_mapped(&self, key: &str) -> Result<Option<MappedValue>> {
+        if let Some(mut log) = self.log() {
+            return Ok(match log.get_mapped(key)? {
+                Lookup::Hit(value) => {
+                    self.metrics.record_hit();
+                    Some(value)
+                }
+                Lookup::Expired(value) => {
+                    self.metrics.record_miss();
+                    self.listeners
+                        .notify(&key.to_string(), &value, RemovalCause::Expired);
+                    None
+                }
+                Lookup::Miss => {
+                    self.metrics.record_miss();
+                    None
+                }
+            });
+        }
+
+        {
+            let _guard = self.file_lock.read().await;
+            let _lock = self.lock_shared().await?;
+
+            match self.find_entry(key) {
+                Found::Live(entry) => {
+                    let value = entry.map_value()?;
+                    self.metrics.record_hit();
+                    return Ok(Some(value));
+                }
+                Found::Expired => {}
+                Found::Missing => {
+                    self.metrics.record_miss();
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.remove_expired_entry(key).await?;
+        self.metrics.record_miss();
+        Ok(None)
+    }
+
+    /// Merges the live records of the sealed log segments into one and
+    /// removes the rest.
+    ///
+    /// Does nothing with the files engine, or while another compaction is
+    /// running. See the
+    /// [module documentation](self#log-engine).
+    pub async fn compact(&self) -> Result<()> {
+        let compaction = match self.log() {
+            Some(mut log) => log.begin_compaction()?,
+            None => None,
+        };
+        let Some(compaction) = compaction else {
+            return Ok(());
+        };
+
+        // The records are copied without the store, which keeps serving
+        // reads and writes until the merged segment is swapped in.
+        let compaction = run_blocking(move || compaction.run()).await?;
+        self.log()
+            .expect("the log engine has a log")
+            .finish_compaction(compaction)
+    }
+
+    /// Spawns a background task that periodically runs [`FileBackend::sweep`].
+    ///
+    /// The janitor holds a weak reference to the backend and stops on its own
+    /// once the backend is dropped. Must be called from within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How often to sweep
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::file::FileBackend;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let backend = Arc::new(FileBackend::new("/tmp/fncache").unwrap());
+    /// let janitor = backend.spawn_janitor(Duration::from_secs(60));
+    ///
+    /// // ... use the backend ...
+    ///
+    /// janitor.abort();
+    /// # }
+    /// ```
+    pub fn spawn_janitor(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let backend = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match backend.upgrade() {
+                    Some(backend) => {
+                        if let Err(e) = backend.sweep().await {
+                            eprintln!("Warning: file cache sweep failed: {}", e);
+                        }
+                    }
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Brings the index up to date with the directory, removes expired
+    /// entries, evicts down to the limits and saves the index.
+    ///
+    /// Only files that changed since they were indexed are opened. Orphaned
+    /// temporary files and files that are not valid entries are removed.
+    ///
+    /// With the log engine, expired keys are dropped from the index and the
+    /// segments are compacted once enough of them is garbage.
+    pub async fn sweep(&self) -> Result<()> {
+        let garbage_ratio = self.log().map(|mut log| {
+            log.remove_expired();
+            log.garbage_ratio()
+        });
+        if let Some(garbage_ratio) = garbage_ratio {
+            if garbage_ratio > COMPACTION_GARBAGE_RATIO {
+                self.compact().await?;
+            }
+            return Ok(());
+        }
+
+        // Removing a slot may move another one, so the sweep needs exclusive access.
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+        let mut index = self.index();
+
+        self.sync_index(&mut index)?;
+        self.remove_expired(&mut index)?;
+        self.enforce_limits(&mut index)?;
+        index.save(&self.index_path(), &self.base_dir, TEMP_PREFIX)
+    }
+
+    /// Registers a listener that is called when an entry is removed.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// See the [module documentation](self#removal-listeners) for which
+    /// removals can be reported by this backend.
+    pub fn on_removal(mut self, listener: impl RemovalListener + 'static) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Loads the index and removes the temporary files of writes that never
+    /// completed.
+    ///
+    /// Runs from the constructor, which blocks until the lock is free.
+    fn recover(&self) -> Result<()> {
+        let lock = self.open_lock_file()?;
+        FileExt::lock_exclusive(&lock)
+            .map_err(|e| Error::Backend(format!("Failed to lock cache directory: {}", e)))?;
+        let mut index = self.index();
+        *index = Index::load(&self.index_path(), &self.base_dir);
+
+        for entry in fs::read_dir(&self.base_dir)? {
+            let path = entry?.path();
+            if Self::is_temp_file(&path) {
+                fs::remove_file(&path)?;
+            }
+        }
+        self.sync_index(&mut index)
+    }
+
+    fn index(&self) -> MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn log(&self) -> Option<MutexGuard<'_, LogStore>> {
+        self.log
+            .as_ref()
+            .map(|log| log.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.base_dir.join(INDEX_FILE)
+    }
+
+    /// Reconciles the index with the entry files in the directory.
+    ///
+    /// Must be called with the exclusive lock held, so that no write is in
+    /// progress and every temporary file is an orphan.
+    fn sync_index(&self, index: &mut Index) -> Result<()> {
+        let mut present = HashSet::new();
+        for path in self.cache_files()? {
+            if Self::is_stream_file(&path) {
+                if Self::is_stale(&path) {
+                    if let Err(e) = fs::remove_file(&path) {
+                        eprintln!("Failed to remove abandoned temporary file: {}", e);
+                    }
+                }
+                continue;
+            }
+            if Self::is_temp_file(&path) {
+                if let Err(e) = fs::remove_file(&path) {
+                    eprintln!("Failed to remove orphaned temporary file: {}", e);
+                }
+                continue;
+            }
+            let Ok(metadata) = fs::metadata(&path) else {
+                // Moved into an earlier slot by a removal during this sync.
+                continue;
+            };
+            if index.is_current(&path, &metadata) {
+                present.insert(path);
+                continue;
+            }
+
+            match EntryFile::open(&path) {
+                Some(entry) => {
+                    let accessed = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+                    index.insert(
+                        path.clone(),
+                        IndexEntry::new(&metadata, entry.expires_at, accessed),
+                    );
+                    present.insert(path);
+                }
+                None => {
+                    index.remove(&path);
+                    if let Some(moved) = Self::remove_slot(&path)? {
+                        // The moved entry is indexed again by the next sync.
+                        index.remove(&moved);
+                    }
+                }
+            }
+        }
+
+        index.retain_present(&present);
+        Ok(())
+    }
+
+    /// Removes the entries of the index that have expired.
+    fn remove_expired(&self, index: &mut Index) -> Result<()> {
+        for path in index.expired(SystemTime::now()) {
+            let expired = if self.listeners.is_empty() {
+                None
+            } else {
+                Self::read_entry(&path)
+            };
+            self.remove_indexed(index, &path)?;
+            if let Some((key, value)) = expired {
+                self.listeners.notify(&key, &value, RemovalCause::Expired);
+            }
+        }
+        Ok(())
+    }
+
+    /// Evicts entries once the cache is over one of its limits.
+    ///
+    /// Expired entries go first, then the least recently accessed ones, until
+    /// the cache is down to 90% of the limits.
+    fn enforce_limits(&self, index: &mut Index) -> Result<()> {
+        let exceeds = |index: &Index, max_bytes: Option<u64>, max_entries: Option<usize>| {
+            max_bytes.is_some_and(|max| index.total_bytes() > max)
+                || max_entries.is_some_and(|max| index.len() > max)
+        };
+        if !exceeds(index, self.max_disk_bytes, self.max_entries) {
+            return Ok(());
+        }
+        self.remove_expired(index)?;
+
+        let target_bytes = self.max_disk_bytes.map(|max| max - max / 10);
+        let target_entries = self.max_entries.map(|max| max - max / 10);
+        let mut candidates = index.by_access().into_iter();
+        while exceeds(index, target_bytes, target_entries) {
+            let Some(path) = candidates.next() else {
+                break;
+            };
+            let Some(size) = index.get(&path).map(|entry| entry.size) else {
+                continue;
+            };
+
+            let reason = if target_entries.is_some_and(|max| index.len() > max) {
+                EvictionReason::Capacity
+            } else {
+                EvictionReason::Weight
+            };
+            let evicted = if self.listeners.is_empty() {
+                None
+            } else {
+                Self::read_entry(&path)
+            };
+            if let Some(moved) = self.remove_indexed(index, &path)? {
+                // The entry moved into the freed slot keeps its place in line
+                // under its new path.
+                candidates = candidates
+                    .map(|candidate| {
+                        if candidate == moved {
+                            path.clone()
+                        } else {
+                            candidate
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter();
+            }
+
+            self.metrics.record_eviction();
+            self.metrics.record_entry_removal(size as usize);
+            if let Some((key, value)) = evicted {
+                self.listeners
+                    .notify(&key, &value, RemovalCause::Evicted(reason));
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the slot at `path` and updates the index, returning the path
+    /// of the slot that was moved into it, if any.
+    fn remove_indexed(&self, index: &mut Index, path: &Path) -> Result<Option<PathBuf>> {
+        let moved = Self::remove_slot(path)?;
+        index.remove(path);
+        if let Some(from) = &moved {
+            index.rename(from, path);
+        }
+        Ok(moved)
+    }
+
+    /// Takes the cross-process lock on the cache directory for reading.
+    ///
+    /// The lock is released when the returned file is dropped.
+    async fn lock_shared(&self) -> Result<File> {
+        lock_file(
+            self.open_lock_file()?,
+            FileExt::try_lock_shared,
+            FileExt::lock_shared,
+        )
+        .await
+    }
+
+    /// Takes the cross-process lock on the cache directory for writing.
+    ///
+    /// The lock is released when the returned file is dropped.
+    async fn lock_exclusive(&self) -> Result<File> {
+        lock_file(
+            self.open_lock_file()?,
+            FileExt::try_lock_exclusive,
+            FileExt::lock_exclusive,
+        )
+        .await
+    }
+
+    /// Opens the lock file, recreating the base directory if it was removed.
+    fn open_lock_file(&self) -> Result<File> {
+        create_dir_all(&self.base_dir)?;
+        Ok(OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.base_dir.join(LOCK_FILE))?)
+    }
+
+    /// Lists the files in the hash directories, including temporary files.
+    fn cache_files(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.base_dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() && !Self::is_log_dir(&path) {
+                if let Ok(subentries) = fs::read_dir(&path) {
+                    for subentry in subentries {
+                        let subpath = subentry?.path();
+                        if subpath.is_file() {
+                            files.push(subpath);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Whether `path` is the directory of the log engine, which the files
+    /// engine leaves alone.
+    fn is_log_dir(path: &Path) -> bool {
+        path.file_name() == Some(log::LOG_DIR.as_ref())
+    }
+
+    /// Whether `path` is a temporary file written by [`FileBackend::write_entry`].
+    fn is_temp_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(TEMP_PREFIX))
+    }
+
+    /// Whether `path` is a temporary file of a streamed value.
+    fn is_stream_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(STREAM_PREFIX))
+    }
+
+    /// Whether the file at `path` was last modified more than
+    /// [`STALE_STREAM_AGE`] ago.
+    fn is_stale(path: &Path) -> bool {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > STALE_STREAM_AGE)
+    }
+
+    /// Reads the key and value stored at `path`, if it exists and can be
+    /// decoded.
+    fn read_entry(path: &Path) -> Option<(String, Vec<u8>)> {
+        let entry = EntryFile::open(path)?;
+        let value = entry.read_value().ok()?;
+        Some((entry.key, value))
+    }
+
+    /// Writes an entry to `path`, atomically replacing any file already there.
+    ///
+    /// The entry is written to a temporary file in the same directory, synced
+    /// and renamed over `path`, so a crash leaves either the old entry or the
+    /// new one.
+    fn write_entry(
+        path: &Path,
+        key: &str,
+        value: &[u8],
+        expires_at: Option<SystemTime>,
+    ) -> Result<()> {
+        let header = Self::entry_header(key, value.len() as u64, expires_at)?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut temp = tempfile::Builder::new()
+            .prefix(TEMP_PREFIX)
+            .tempfile_in(dir)?;
+
+        let mut writer = io::BufWriter::new(temp.as_file_mut());
+        writer.write_all(&header)?;
+        writer.write_all(value)?;
+        writer.flush()?;
+        drop(writer);
+
+        temp.as_file().sync_all()?;
+        temp.persist(path).map_err(|e| e.error)?;
+        sync_dir(dir)?;
+        Ok(())
+    }
+
+    /// Encodes the header and key of an entry, up to its value.
+    fn entry_header(key: &str, value_len: u64, expires_at: Option<SystemTime>) -> Result<Vec<u8>> {
+        let key_len = u32::try_from(key.len())
+            .map_err(|_| Error::Backend("key is too large for the file backend".into()))?;
+        let expires_at = expires_at.map_or(0, |time| {
+            time.duration_since(UNIX_EPOCH)
+                .map_or(1, |elapsed| (elapsed.as_millis() as u64).max(1))
+        });
+
+        let mut header = Vec::with_capacity(ENTRY_HEADER_LEN + key.len());
+        header.extend_from_slice(&ENTRY_MAGIC);
+        header.push(ENTRY_VERSION);
+        header.extend_from_slice(&expires_at.to_be_bytes());
+        header.extend_from_slice(&key_len.to_be_bytes());
+        header.extend_from_slice(&value_len.to_be_bytes());
+        header.extend_from_slice(key.as_bytes());
+        Ok(header)
+    }
+
+    /// Stores the entry of `key` in its slot, writing the file with `write`,
+    /// then indexes it, evicts down to the limits and reports the value it
+    /// replaced.
+    ///
+    /// Must be called with the exclusive lock held.
+    fn store_entry(
+        &self,
+        key: &String,
+        expires_at: Option<SystemTime>,
+        write: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<()> {
+        let (path, replaced) = match self.find_slot(key) {
+            Slot::Found(path, old) if !self.listeners.is_empty() => (path, old.read_value().ok()),
+            Slot::Found(path, _) | Slot::Free(path) => (path, None),
+        };
+        self.ensure_dir_exists(&path)?;
+
+        write(&path)?;
+        let metadata = fs::metadata(&path)?;
+        let mut index = self.index();
+        index.insert(
+            path,
+            IndexEntry::new(&metadata, expires_at, SystemTime::now()),
+        );
+        self.enforce_limits(&mut index)?;
+        drop(index);
+
+        self.metrics.record_insertion();
+        if let Some(old) = replaced {
+            self.listeners.notify(key, &old, RemovalCause::Replaced);
+        }
+        Ok(())
+    }
+
+    /// Returns when an entry written now with `ttl` expires.
+    fn expires_at(ttl: Option<Duration>) -> Option<SystemTime> {
+        ttl.map(|duration| {
+            SystemTime::now()
+                .checked_add(duration)
+                .unwrap_or_else(|| SystemTime::now() + duration)
+        })
+    }
+
+    /// Convert a cache key to the path of the first slot of its chain
+    fn key_to_path(&self, key: &str) -> PathBuf {
+        let hash = Self::hash_key(key);
+        let dir_name = &hash[0..2];
+        let file_name = &hash[2..];
+
+        let mut path = self.base_dir.clone();
+        path.push(dir_name);
+        path.push(file_name);
+
+        path
+    }
+
+    /// Hashes a key into a valid filename.
+    ///
+    /// This is 64-bit FNV-1a over the UTF-8 bytes of the key, formatted as 16
+    /// lowercase hex digits. It is part of the on-disk format and must not
+    /// change, or existing cache directories would no longer be found.
+    fn hash_key(key: &str) -> String {
+        let hash = key.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Returns the path of slot `index` in the chain whose first slot is `base`.
+    fn slot_path(base: &Path, index: usize) -> PathBuf {
+        if index == 0 {
+            base.to_path_buf()
+        } else {
+            base.with_extension(index.to_string())
+        }
+    }
+
+    /// Splits a slot path into the first slot of its chain and its index.
+    fn split_slot(path: &Path) -> (PathBuf, usize) {
+        match path.extension().and_then(|e| e.to_str()?.parse().ok()) {
+            Some(index) => (path.with_extension(""), index),
+            None => (path.to_path_buf(), 0),
+        }
+    }
+
+    /// Walks the chain of `key` until it finds the entry stored under `key`
+    /// or the first free slot.
+    fn find_slot(&self, key: &str) -> Slot {
+        let base = self.key_to_path(key);
+        for index in 0.. {
+            let path = Self::slot_path(&base, index);
+            if !path.exists() {
+                return Slot::Free(path);
+            }
+            // A slot that cannot be read belongs to no key; the expiry sweep
+            // removes it and compacts the chain.
+            if let Some(entry) = EntryFile::open(&path) {
+                if entry.key == key {
+                    return Slot::Found(path, entry);
+                }
+            }
+        }
+        unreachable!("collision chains are finite")
+    }
+
+    /// Looks up the entry of `key` for a read, recording the access if it is
+    /// live.
+    ///
+    /// Must be called with at least the shared lock held. An expired entry is
+    /// left in place for [`FileBackend::remove_expired_entry`].
+    fn find_entry(&self, key: &str) -> Found {
+        let Slot::Found(path, entry) = self.find_slot(key) else {
+            return Found::Missing;
+        };
+
+        let now = SystemTime::now();
+        if entry.is_expired(now) {
+            return Found::Expired;
+        }
+        self.index().touch(&path, now);
+        Found::Live(entry)
+    }
+
+    /// Removes the entry of `key` if it is still expired once the exclusive
+    /// lock is taken; it may have been removed or rewritten in the meantime.
+    async fn remove_expired_entry(&self, key: &str) -> Result<()> {
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        let Slot::Found(path, entry) = self.find_slot(key) else {
+            return Ok(());
+        };
+        if !entry.is_expired(SystemTime::now()) {
+            return Ok(());
+        }
+        let expired = if self.listeners.is_empty() {
+            None
+        } else {
+            entry.read_value().ok()
+        };
+        self.remove_indexed(&mut self.index(), &path)?;
+        if let Some(value) = expired {
+            self.listeners
+                .notify(&entry.key, &value, RemovalCause::Expired);
+        }
+        Ok(())
+    }
+
+    /// Removes the file at `path` and moves the last slot of its chain into
+    /// the gap, so that chains never have holes.
+    ///
+    /// Returns the path of the slot that was moved, if any.
+    fn remove_slot(path: &Path) -> io::Result<Option<PathBuf>> {
+        let (base, index) = Self::split_slot(path);
+        let mut last = index;
+        while Self::slot_path(&base, last + 1).exists() {
+            last += 1;
+        }
+
+        let moved = if last == index {
+            fs::remove_file(path)?;
+            None
+        } else {
+            let from = Self::slot_path(&base, last);
+            fs::rename(&from, path)?;
+            Some(from)
+        };
+        if let Some(dir) = path.parent() {
+            sync_dir(dir)?;
+        }
+        Ok(moved)
+    }
+
+    /// Ensure the parent directory exists for a given file path
+    fn ensure_dir_exists(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Takes a lock on the lock file `file` with `try_lock`, or else waits for
+/// it with `lock`, and returns the locked file.
+///
+/// Waiting for other processes may take as long as their operations do, so
+/// it happens on the blocking thread pool.
+async fn lock_file(
+    file: File,
+    try_lock: fn(&File) -> io::Result<()>,
+    lock: fn(&File) -> io::Result<()>,
+) -> Result<File> {
+    if try_lock(&file).is_ok() {
+        return Ok(file);
+    }
+    run_blocking(move || {
+        lock(&file)
+            .map_err(|e| Error::Backend(format!("Failed to lock cache directory: {}", e)))?;
+        Ok(file)
+    })
+    .await
+}
+
+/// Runs `operation` on tokio's blocking thread pool, so that it does not
+/// hold up an executor thread. Without a tokio runtime there is no pool and
+/// it runs in place.
+async fn run_blocking<T, F>(operation: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime
+            .spawn_blocking(operation)
+            .await
+            .map_err(|e| Error::Backend(format!("File cache task failed: {}", e)))?,
+        Err(_) => operation(),
+    }
+}
+
+/// Flushes changes to the entries of `dir`, such as a rename, to disk.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing outside Unix; renames are
+/// flushed by the file system on its own schedule.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Result of looking a key up in its collision chain.
+#[derive(Debug)]
+enum Slot {
+    /// The entry stored under the key, and the file holding it
+    Found(PathBuf, EntryFile),
+    /// The first free slot of the chain
+    Free(PathBuf),
+}
+
+/// Result of looking a key up for a read.
+enum Found {
+    /// The key's live entry
+    Live(EntryFile),
+    /// The key's entry has expired and is still on disk
+    Expired,
+    /// The key is not stored
+    Missing,
+}
+
+/// Implementation of the CacheBackend trait for FileBackend
+///
+/// This implementation provides:
+/// * Thread-safe file operations using async locks
+/// * TTL support with automatic cleanup of expired entries
+/// * Metrics collection for hits, misses, and insertions
+/// * Bincode-based serialization for efficient storage
+#[async_trait::async_trait]
+impl CacheBackend for FileBackend {
+    async fn get(&self, key: &String) -> Result<Option<Vec<u8>>> {
+        if let Some(mut log) = self.log() {
+            return Ok(match log.get(key)? {
+                Lookup::Hit(value) => {
+                    self.metrics.record_hit();
+                    Some(value)
+                }
+                Lookup::Expired(value) => {
+                    self.metrics.record_miss();
+                    self.listeners.notify(key, &value, RemovalCause::Expired);
+                    None
+                }
+                Lookup::Miss => {
+                    self.metrics.record_miss();
+                    None
+                }
+            });
+        }
+
+        {
+            let _guard = self.file_lock.read().await;
+            let _lock = self.lock_shared().await?;
+
+            match self.find_entry(key) {
+                Found::Live(entry) => {
+                    let value = entry.read_value()?;
+                    self.metrics.record_hit();
+                    return Ok(Some(value));
+                }
+                Found::Expired => {}
+                Found::Missing => {
+                    self.metrics.record_miss();
+                    return Ok(None);
+                }
+            }
+        }
+
+        self.remove_expired_entry(key).await?;
+        self.metrics.record_miss();
+        Ok(None)
+    }
+
+    async fn set(&self, key: String, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let expires_at = Self::expires_at(ttl);
+
+        if let Some(mut log) = self.log() {
+            let replaced = if self.listeners.is_empty() {
+                None
+            } else {
+                log.peek(&key)?
+            };
+            log.put(key.clone(), &value, expires_at)?;
+            drop(log);
+
+            self.metrics.record_insertion();
+            if let Some(old) = replaced {
+                self.listeners.notify(&key, &old, RemovalCause::Replaced);
+            }
+            return Ok(());
+        }
+
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        self.store_entry(&key, expires_at, |path| {
+            Self::write_entry(path, &key, &value, expires_at)
+        })
+    }
+
+    async fn remove(&self, key: &String) -> Result<()> {
+        if let Some(mut log) = self.log() {
+            let removed = if self.listeners.is_empty() {
+                None
+            } else {
+                log.peek(key)?
+            };
+            log.remove(key)?;
+            drop(log);
+
+            if let Some(value) = removed {
+                self.listeners.notify(key, &value, RemovalCause::Explicit);
+            }
+            return Ok(());
+        }
+
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        if let Slot::Found(path, entry) = self.find_slot(key) {
+            let removed = if self.listeners.is_empty() {
+                None
+            } else {
+                entry.read_value().ok()
+            };
+            self.remove_indexed(&mut self.index(), &path)?;
+            if let Some(value) = removed {
+                self.listeners.notify(key, &value, RemovalCause::Explicit);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn contains_key(&self, key: &String) -> Result<bool> {
+        if let Some(log) = self.log() {
+            return Ok(log.contains_key(key));
+        }
+
+        let _guard = self.file_lock.read().await;
+        let _lock = self.lock_shared().await?;
+
+        Ok(match self.find_slot(key) {
+            Slot::Found(_, entry) => !entry.is_expired(SystemTime::now()),
+            Slot::Free(_) => false,
+        })
+    }
+
+    async fn clear(&self) -> Result<()> {
+        if let Some(mut log) = self.log() {
+            return log.clear();
+        }
+
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        // The lock file stays: other processes may be waiting on it. So does
+        // the log engine's directory.
+        for entry in fs::read_dir(&self.base_dir)? {
+            let path = entry?.path();
+            if Self::is_log_dir(&path) {
+                continue;
+            } else if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else if path.file_name() != Some(LOCK_FILE.as_ref()) {
+                fs::remove_file(&path)?;
+            }
+        }
+        self.index().clear();
+
+        Ok(())
+    }
+}
+
+/// Streams values to and from the engine's files.
+///
+/// Under the files engine, `put_stream` writes the value to a temporary file
+/// next to its entry file before taking any lock, and renames it into place
+/// once complete. Under the log engine, it spools the value to a segment of
+/// its own in the `log` directory, which is then renamed in after the active
+/// segment. `get_stream` reads the value through [`FileBackend::get_mapped`].
+#[async_trait::async_trait]
+impl StreamingBackend for FileBackend {
+    async fn put_stream<R>(&self, key: String, mut reader: R, ttl: Option<Duration>) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let expires_at = Self::expires_at(ttl);
+
+        if self.engine() == Engine::Log {
+            let log_dir = self.base_dir.join(log::LOG_DIR);
+            let mut spool = log::Spool::create(&log_dir, key.clone(), expires_at)?;
+            let mut crc = crc32fast::Hasher::new();
+            let len = spool_value(&mut reader, spool.file_mut(), Some(&mut crc)).await?;
+            let spool = run_blocking(move || spool.finish(len, crc)).await?;
+
+            let mut log = self.log().expect("the log engine has a log");
+            let replaced = if self.listeners.is_empty() {
+                None
+            } else {
+                log.peek(&key)?
+            };
+            log.put_spooled(spool)?;
+            drop(log);
+
+            self.metrics.record_insertion();
+            if let Some(old) = replaced {
+                self.listeners.notify(&key, &old, RemovalCause::Replaced);
+            }
+            return Ok(len);
+        }
+
+        // The temporary file goes in the directory of the entry, so that it
+        // can be renamed over whichever slot of the chain the key ends up in.
+        let base = self.key_to_path(&key);
+        self.ensure_dir_exists(&base)?;
+        let dir = base.parent().unwrap_or_else(|| Path::new("."));
+        let mut temp = tempfile::Builder::new()
+            .prefix(STREAM_PREFIX)
+            .tempfile_in(dir)?;
+
+        // The value length in the header is only known at the end, so the
+        // header is written again once the value is.
+        let header = Self::entry_header(&key, 0, expires_at)?;
+        temp.as_file_mut().write_all(&header)?;
+        let len = spool_value(&mut reader, temp.as_file_mut(), None).await?;
+        temp.as_file_mut().rewind()?;
+        temp.as_file_mut()
+            .write_all(&Self::entry_header(&key, len, expires_at)?)?;
+        temp.as_file().sync_all()?;
+
+        let _guard = self.file_lock.write().await;
+        let _lock = self.lock_exclusive().await?;
+
+        self.store_entry(&key, expires_at, |path| {
+            temp.persist(path).map_err(|e| e.error)?;
+            sync_dir(dir)?;
+            Ok(())
+        })?;
+        Ok(len)
+    }
+
+    async fn get_stream(&self, key: &String) -> Result<Option<ValueReader>> {
+        Ok(self
+            .get_mapped(key)
+            .await?
+            .map(|value| Box::pin(io::Cursor::new(value)) as ValueReader))
+    }
+}
+
+/// Copies `reader` to `file` a chunk at a time, feeding the bytes to `crc`
+/// if given, and returns how many were copied.
+async fn spool_value<R>(
+    reader: &mut R,
+    file: &mut File,
+    mut crc: Option<&mut crc32fast::Hasher>,
+) -> Result<u64>
+where
+    R: AsyncRead + Send + Unpin,
+{
+    let mut chunk = Vec::with_capacity(STREAM_CHUNK_SIZE);
+    let mut len = 0;
+    while read_chunk(reader, &mut chunk).await? > 0 {
+        file.write_all(&chunk)?;
+        if let Some(crc) = crc.as_deref_mut() {
+            crc.update(&chunk);
+        }
+        len += chunk.len() as u64;
+    }
+    Ok(len)
+}
+
+impl Drop for FileBackend {
+    fn drop(&mut self) {
+        if self.log.is_some() {
+            return;
+        }
+        // The index is only a hint, so it is saved without waiting for the lock.
+        if let Err(e) = self
+            .index()
+            .save(&self.index_path(), &self.base_dir, TEMP_PREFIX)
+        {
+            eprintln!("Warning: failed to save file cache index: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use tempfile::tempdir;
+    use tokio::{io::AsyncReadExt, time::sleep};
+
+    #[tokio::test]
+    #[serial]
+    async fn test_get_set() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+
+        let key = "test_key".to_string();
+        let value = b"test_value".to_vec();
+
+        backend.set(key.clone(), value.clone(), None).await.unwrap();
+
+        let result = backend.get(&key).await.unwrap();
+        assert_eq!(result, Some(value));
+
+        assert!(backend.contains_key(&key).await.unwrap());
+
+        backend.remove(&key).await.unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), None);
+        assert!(!backend.contains_key(&key).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_ttl() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+
+        let key = "test_ttl".to_string();
+        let value = b"test_value".to_vec();
+
+        backend
+            .set(key.clone(), value, Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+
+        assert!(backend.get(&key).await.unwrap().is_some());
+
+        sleep(Duration::from_millis(150)).await;
+
+        assert!(backend.get(&key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_clear() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+
+        let key1 = "test_key1".to_string();
+        let key2 = "test_key2".to_string();
+        let value = b"test_value".to_vec();
+
+        backend
+            .set(key1.clone(), value.clone(), None)
+            .await
+            .unwrap();
+        backend
+            .set(key2.clone(), value.clone(), None)
+            .await
+            .unwrap();
+
+        assert!(backend.contains_key(&key1).await.unwrap());
+        assert!(backend.contains_key(&key2).await.unwrap());
+
+        backend.clear().await.unwrap();
+
+        assert!(!backend.contains_key(&key1).await.unwrap());
+        assert!(!backend.contains_key(&key2).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_metrics() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+
+        let key = "test_metrics".to_string();
+        let value = b"test_value".to_vec();
+
+        assert_eq!(backend.metrics.hits(), 0);
+        assert_eq!(backend.metrics.misses(), 0);
+
+        assert!(backend.get(&key).await.unwrap().is_none());
+        assert_eq!(backend.metrics.misses(), 1);
+
+        backend.set(key.clone(), value, None).await.unwrap();
+
+        assert!(backend.get(&key).await.unwrap().is_some());
+        assert_eq!(backend.metrics.hits(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_removal_listener() {
+        use std::sync::Mutex;
+
+        let temp_dir = tempdir().unwrap();
+        let removals = Arc::new(Mutex::new(Vec::new()));
+        let sink = removals.clone();
+        let backend = FileBackend::new(temp_dir.path()).unwrap().on_removal(
+            move |key: &String, value: &Vec<u8>, cause| {
+                sink.lock()
+                    .unwrap()
+                    .push((key.clone(), value.clone(), cause));
+            },
+        );
+
+        let key = "listened".to_string();
+        backend
+            .set(key.clone(), b"v1".to_vec(), None)
+            .await
+            .unwrap();
+        backend
+            .set(key.clone(), b"v2".to_vec(), None)
+            .await
+            .unwrap();
+        backend.remove(&key).await.unwrap();
+        backend.remove(&key).await.unwrap();
+
+        assert_eq!(
+            *removals.lock().unwrap(),
+            vec![
+                (key.clone(), b"v1".to_vec(), RemovalCause::Replaced),
+                (key, b"v2".to_vec(), RemovalCause::Explicit),
+            ]
+        );
+    }
+    #[test]
+    fn test_hash_key_is_fnv1a() {
+        assert_eq!(FileBackend::hash_key(""), "cbf29ce484222325");
+        assert_eq!(FileBackend::hash_key("a"), "af63dc4c8601ec8c");
+        assert_eq!(FileBackend::hash_key("foobar"), "85944171f73967e8");
+    }
+
+    /// Writes an entry for `stored_key` into the first slot of `key`'s chain,
+    /// as if the two keys hashed the same.
+    fn plant_collision(backend: &FileBackend, key: &str, stored_key: &str) -> PathBuf {
+        let path = backend.key_to_path(key);
+        backend.ensure_dir_exists(&path).unwrap();
+        FileBackend::write_entry(&path, stored_key, b"collider", None).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_colliding_keys_are_chained() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "chained".to_string();
+        let first = plant_collision(&backend, &key, "other");
+
+        assert_eq!(backend.get(&key).await.unwrap(), None);
+        assert!(!backend.contains_key(&key).await.unwrap());
+
+        backend
+            .set(key.clone(), b"mine".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"mine".to_vec()));
+        assert!(FileBackend::slot_path(&first, 1).exists());
+
+        // Removing the head of the chain moves the last slot into its place.
+        FileBackend::remove_slot(&first).unwrap();
+        assert!(!FileBackend::slot_path(&first, 1).exists());
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"mine".to_vec()));
+
+        backend.remove(&key).await.unwrap();
+        assert!(!first.exists());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_files_without_header_are_swept() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "legacy".to_string();
+        let path = backend.key_to_path(&key);
+        backend.ensure_dir_exists(&path).unwrap();
+        fs::write(&path, b"not an entry").unwrap();
+
+        assert_eq!(backend.get(&key).await.unwrap(), None);
+        backend.sweep().await.unwrap();
+        assert!(!path.exists());
+    }
+    #[tokio::test]
+    #[serial]
+    async fn test_open_removes_orphaned_temp_files() {
+        let temp_dir = tempdir().unwrap();
+        let key = "survivor".to_string();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        backend
+            .set(key.clone(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+
+        let path = backend.key_to_path(&key);
+        let orphan = path.with_file_name(format!("{}crashed", TEMP_PREFIX));
+        fs::write(&orphan, b"half an ent").unwrap();
+        drop(backend);
+
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        assert!(!orphan.exists());
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"value".to_vec()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_writes_hold_the_directory_lock() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+
+        let lock = backend.lock_exclusive().await.unwrap();
+        let other = backend.open_lock_file().unwrap();
+        assert!(FileExt::try_lock_shared(&other).is_err());
+        drop(lock);
+        assert!(FileExt::try_lock_exclusive(&other).is_ok());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_waiting_for_the_directory_lock_does_not_block_the_executor() {
+        let temp_dir = tempdir().unwrap();
+        let backend = Arc::new(FileBackend::new(temp_dir.path()).unwrap());
+        let key = "waiting".to_string();
+
+        // Another process writing.
+        let writer = backend.open_lock_file().unwrap();
+        FileExt::lock_exclusive(&writer).unwrap();
+
+        let reader = {
+            let backend = backend.clone();
+            let key = key.clone();
+            tokio::spawn(async move { backend.get(&key).await })
+        };
+        // The single executor thread keeps running other tasks meanwhile.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!reader.is_finished());
+
+        drop(writer);
+        assert_eq!(reader.await.unwrap().unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_lookups_share_the_directory_lock() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "shared".to_string();
+        backend
+            .set(key.clone(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        backend
+            .set(
+                "expired".to_string(),
+                b"old".to_vec(),
+                Some(Duration::from_millis(10)),
+            )
+            .await
+            .unwrap();
+
+        // Another reader holding the lock does not block lookups.
+        let reader = backend.open_lock_file().unwrap();
+        FileExt::lock_shared(&reader).unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"value".to_vec()));
+        assert!(backend.get_mapped(&key).await.unwrap().is_some());
+        drop(reader);
+
+        // Removing an expired entry still takes the exclusive lock.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(backend.get(&"expired".to_string()).await.unwrap(), None);
+        assert!(!backend.key_to_path("expired").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[serial]
+    async fn test_backends_share_a_directory() {
+        let temp_dir = tempdir().unwrap();
+        let a = Arc::new(FileBackend::new(temp_dir.path()).unwrap());
+        let b = Arc::new(FileBackend::new(temp_dir.path()).unwrap());
+
+        let writers = [a.clone(), b.clone()].map(|backend| {
+            tokio::spawn(async move {
+                for i in 0..50 {
+                    let value = format!("value{}", i).into_bytes();
+                    backend.set(format!("key{}", i), value, None).await.unwrap();
+                }
+            })
+        });
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        for i in 0..50 {
+            let expected = Some(format!("value{}", i).into_bytes());
+            assert_eq!(a.get(&format!("key{}", i)).await.unwrap(), expected);
+            assert_eq!(b.get(&format!("key{}", i)).await.unwrap(), expected);
+        }
+
+        b.clear().await.unwrap();
+        assert!(!a.contains_key(&"key0".to_string()).await.unwrap());
+        assert!(temp_dir.path().join(LOCK_FILE).exists());
+    }
+    #[tokio::test]
+    #[serial]
+    async fn test_max_entries_evicts_least_recently_used() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path())
+            .unwrap()
+            .with_max_entries(3);
+
+        for key in ["a", "b", "c"] {
+            backend
+                .set(key.to_string(), b"value".to_vec(), None)
+                .await
+                .unwrap();
+            sleep(Duration::from_millis(5)).await;
+        }
+        assert!(backend.get(&"a".to_string()).await.unwrap().is_some());
+        sleep(Duration::from_millis(5)).await;
+
+        backend
+            .set("d".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(backend.entry_count(), 3);
+        assert!(!backend.contains_key(&"b".to_string()).await.unwrap());
+        for key in ["a", "c", "d"] {
+            assert!(backend.contains_key(&key.to_string()).await.unwrap());
+        }
+        assert_eq!(backend.metrics.evictions(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_max_disk_bytes_evicts_down_to_low_water() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        backend
+            .set("probe".to_string(), vec![0; 1000], None)
+            .await
+            .unwrap();
+        let entry_size = backend.disk_bytes();
+        backend.clear().await.unwrap();
+
+        let backend = backend.with_max_disk_bytes(entry_size * 10);
+        for i in 0..11 {
+            backend
+                .set(format!("key{}", i), vec![0; 1000], None)
+                .await
+                .unwrap();
+        }
+
+        assert!(backend.disk_bytes() <= entry_size * 9);
+        assert!(backend.contains_key(&"key10".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_janitor_removes_expired_entries() {
+        let temp_dir = tempdir().unwrap();
+        let backend = Arc::new(FileBackend::new(temp_dir.path()).unwrap());
+        backend
+            .set(
+                "short".to_string(),
+                b"value".to_vec(),
+                Some(Duration::from_millis(20)),
+            )
+            .await
+            .unwrap();
+        backend
+            .set("long".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        let short_path = backend.key_to_path("short");
+
+        let janitor = backend.spawn_janitor(Duration::from_millis(20));
+        sleep(Duration::from_millis(100)).await;
+        janitor.abort();
+
+        assert!(!short_path.exists());
+        assert_eq!(backend.entry_count(), 1);
+        assert!(temp_dir.path().join(INDEX_FILE).exists());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_index_follows_other_backends() {
+        let temp_dir = tempdir().unwrap();
+        let a = FileBackend::new(temp_dir.path()).unwrap();
+        let b = FileBackend::new(temp_dir.path()).unwrap();
+
+        a.set("shared".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(b.entry_count(), 0);
+        b.sweep().await.unwrap();
+        assert_eq!(b.entry_count(), 1);
+        assert_eq!(b.disk_bytes(), a.disk_bytes());
+
+        drop(b);
+        a.remove(&"shared".to_string()).await.unwrap();
+        let c = FileBackend::new(temp_dir.path()).unwrap();
+        assert_eq!(c.entry_count(), 0);
+    }
+    #[tokio::test]
+    #[serial]
+    async fn test_log_engine() {
+        use std::sync::Mutex;
+
+        let temp_dir = tempdir().unwrap();
+        let removals = Arc::new(Mutex::new(Vec::new()));
+        let sink = removals.clone();
+        let backend = FileBackend::new(temp_dir.path())
+            .unwrap()
+            .with_engine(Engine::Log)
+            .unwrap()
+            .on_removal(move |key: &String, value: &Vec<u8>, cause| {
+                sink.lock()
+                    .unwrap()
+                    .push((key.clone(), value.clone(), cause));
+            });
+        assert_eq!(backend.engine(), Engine::Log);
+
+        let key = "logged".to_string();
+        backend
+            .set(key.clone(), b"v1".to_vec(), None)
+            .await
+            .unwrap();
+        backend
+            .set(key.clone(), b"v2".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"v2".to_vec()));
+        backend.remove(&key).await.unwrap();
+        assert!(!backend.contains_key(&key).await.unwrap());
+
+        backend
+            .set(
+                "short".to_string(),
+                b"v3".to_vec(),
+                Some(Duration::from_millis(20)),
+            )
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(backend.get(&"short".to_string()).await.unwrap(), None);
+
+        assert_eq!(
+            *removals.lock().unwrap(),
+            vec![
+                (key.clone(), b"v1".to_vec(), RemovalCause::Replaced),
+                (key, b"v2".to_vec(), RemovalCause::Explicit),
+                ("short".to_string(), b"v3".to_vec(), RemovalCause::Expired),
+            ]
+        );
+        assert_eq!(backend.metrics.hits(), 1);
+        assert_eq!(backend.metrics.misses(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_log_engine_persists_and_compacts() {
+        let temp_dir = tempdir().unwrap();
+        let open = || {
+            FileBackend::new(temp_dir.path())
+                .unwrap()
+                .with_engine(Engine::Log)
+                .unwrap()
+        };
+
+        let backend = open();
+        for i in 0..10 {
+            backend
+                .set("hot".to_string(), vec![i; 100], None)
+                .await
+                .unwrap();
+        }
+        backend
+            .set("cold".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        let before = backend.disk_bytes();
+        backend.sweep().await.unwrap();
+        assert!(backend.disk_bytes() < before);
+        drop(backend);
+
+        // The files engine leaves the log alone.
+        let files = FileBackend::new(temp_dir.path()).unwrap();
+        files.clear().await.unwrap();
+        drop(files);
+
+        let backend = open();
+        assert_eq!(backend.entry_count(), 2);
+        assert_eq!(
+            backend.get(&"hot".to_string()).await.unwrap(),
+            Some(vec![9; 100])
+        );
+        backend.clear().await.unwrap();
+        assert_eq!(backend.get(&"cold".to_string()).await.unwrap(), None);
+    }
+    #[tokio::test]
+    #[serial]
+    async fn test_get_mapped() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "large".to_string();
+        let value: Vec<u8> = (0..1_000_000u32).map(|i| i as u8).collect();
+        backend.set(key.clone(), value.clone(), None).await.unwrap();
+
+        let mapped = backend.get_mapped(&key).await.unwrap().unwrap();
+        assert_eq!(&*mapped, value.as_slice());
+
+        // The mapping keeps the value it was created with.
+        backend
+            .set(key.clone(), b"replacement".to_vec(), None)
+            .await
+            .unwrap();
+        backend.remove(&key).await.unwrap();
+        assert_eq!(mapped.len(), value.len());
+        assert_eq!(mapped[999_999], value[999_999]);
+
+        backend
+            .set("empty".to_string(), Vec::new(), None)
+            .await
+            .unwrap();
+        assert!(backend
+            .get_mapped("empty")
+            .await
+            .unwrap()
+            .unwrap()
+            .is_empty());
+        assert!(backend.get_mapped("missing").await.unwrap().is_none());
+
+        backend
+            .set(
+                "short".to_string(),
+                b"value".to_vec(),
+                Some(Duration::from_millis(10)),
+            )
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+        assert!(backend.get_mapped("short").await.unwrap().is_none());
+        assert_eq!(backend.metrics.hits(), 2);
+        assert_eq!(backend.metrics.misses(), 2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_get_mapped_with_log_engine() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path())
+            .unwrap()
+            .with_engine(Engine::Log)
+            .unwrap();
+        backend
+            .set("key".to_string(), b"logged value".to_vec(), None)
+            .await
+            .unwrap();
+
+        let mapped = backend.get_mapped("key").await.unwrap().unwrap();
+        assert_eq!(mapped.as_ref(), b"logged value");
+        backend.compact().await.unwrap();
+        assert_eq!(mapped.as_slice(), b"logged value");
+    }
+
+    #[test]
+    fn test_entry_header_layout() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("entry");
+        let expires_at = UNIX_EPOCH + Duration::from_millis(0x0102_0304);
+        FileBackend::write_entry(&path, "key", b"value", Some(expires_at)).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], b"fnc\x02");
+        assert_eq!(&bytes[4..12], &0x0102_0304u64.to_be_bytes());
+        assert_eq!(&bytes[12..16], &3u32.to_be_bytes());
+        assert_eq!(&bytes[16..24], &5u64.to_be_bytes());
+        assert_eq!(&bytes[24..], b"keyvalue");
+
+        let entry = EntryFile::open(&path).unwrap();
+        assert_eq!(entry.expires_at, Some(expires_at));
+        assert_eq!(entry.read_value().unwrap(), b"value");
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(EntryFile::open(&path).is_none());
+    }
+
+    /// A reader that fails after yielding `data`.
+    struct FailingReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl AsyncRead for FailingReader<'_> {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<io::Result<()>> {
+            if self.data.is_empty() {
+                return std::task::Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::ConnectionReset,
+                    "upload interrupted",
+                )));
+            }
+            let len = buf.remaining().min(self.data.len());
+            buf.put_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn read_stream(backend: &FileBackend, key: &str) -> Option<Vec<u8>> {
+        let mut reader = backend.get_stream(&key.to_string()).await.unwrap()?;
+        let mut value = Vec::new();
+        reader.read_to_end(&mut value).await.unwrap();
+        Some(value)
+    }
+
+    #[tokio::test]
+    async fn test_put_stream_and_get_stream() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "blob".to_string();
+        let value: Vec<u8> = (0..STREAM_CHUNK_SIZE * 5 / 2).map(|i| i as u8).collect();
+
+        let len = backend
+            .put_stream(key.clone(), value.as_slice(), None)
+            .await
+            .unwrap();
+        assert_eq!(len, value.len() as u64);
+        assert_eq!(read_stream(&backend, "blob").await.unwrap(), value);
+        assert_eq!(backend.get(&key).await.unwrap().unwrap(), value);
+        assert_eq!(backend.entry_count(), 1);
+
+        backend
+            .set("small".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(read_stream(&backend, "small").await.unwrap(), b"value");
+        assert!(read_stream(&backend, "missing").await.is_none());
+
+        backend
+            .put_stream(
+                "short".to_string(),
+                &b"value"[..],
+                Some(Duration::from_millis(10)),
+            )
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(30)).await;
+        assert!(read_stream(&backend, "short").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_put_stream_keeps_the_old_value() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let key = "blob".to_string();
+        backend
+            .set(key.clone(), b"old".to_vec(), None)
+            .await
+            .unwrap();
+
+        let reader = FailingReader { data: b"new" };
+        assert!(backend.put_stream(key.clone(), reader, None).await.is_err());
+        assert_eq!(backend.get(&key).await.unwrap().unwrap(), b"old");
+
+        // Nothing is left behind for the sweep to find.
+        backend.sweep().await.unwrap();
+        assert_eq!(backend.entry_count(), 1);
+        let leftovers = backend
+            .cache_files()
+            .unwrap()
+            .into_iter()
+            .filter(|path| FileBackend::is_stream_file(path))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_keeps_streams_in_progress() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path()).unwrap();
+        let dir = temp_dir.path().join("ab");
+        fs::create_dir_all(&dir).unwrap();
+        let upload = dir.join(format!("{}upload", STREAM_PREFIX));
+        fs::write(&upload, b"partial").unwrap();
+
+        backend.sweep().await.unwrap();
+        assert!(upload.exists());
+        assert_eq!(backend.entry_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_put_stream_with_log_engine() {
+        let temp_dir = tempdir().unwrap();
+        let backend = FileBackend::new(temp_dir.path())
+            .unwrap()
+            .with_engine(Engine::Log)
+            .unwrap();
+        let key = "blob".to_string();
+        let value: Vec<u8> = (0..STREAM_CHUNK_SIZE * 3 / 2).map(|i| i as u8).collect();
+
+        backend
+            .put_stream(key.clone(), value.as_slice(), None)
+            .await
+            .unwrap();
+        assert_eq!(read_stream(&backend, "blob").await.unwrap(), value);
+
+        let reader = FailingReader { data: b"new" };
+        assert!(backend.put_stream(key.clone(), reader, None).await.is_err());
+        drop(backend);
+
+        let backend = FileBackend::new(temp_dir.path())
+            .unwrap()
+            .with_engine(Engine::Log)
+            .unwrap();
+        assert_eq!(backend.get(&key).await.unwrap().unwrap(), value);
+    }
+}
@@ -3,9 +3,14 @@
 //! This module provides a high-performance, thread-safe in-memory cache implementation
 //! that uses `dashmap` for concurrent map access. The `MemoryBackend` supports:
 //!
-//! * Configurable maximum capacity
+//! * Configurable maximum capacity, by entry count or by weight
 //! * TTL-based entry expiration
-//! * Pluggable eviction policies (LRU, LFU)
+//! * Sliding time-to-idle expiration capped by an absolute TTL
+//! * Timer-wheel based expiry that avoids scanning the whole map on reads
+//! * An optional background janitor task for proactive expiry
+//! * Pluggable eviction policies (LRU, LFU, W-TinyLFU, ARC, 2Q, SIEVE, FIFO, random, or custom)
+//! * Entry priorities, including pinned entries that are never evicted
+//! * Removal listeners notified with the cause of every removal
 //! * Performance metrics collection
 //!
 //! # Examples
@@ -41,11 +46,13 @@
 //!
 //! ```
 //! use fncache::backends::memory::{MemoryBackend, MemoryBackendConfig};
+//! use fncache::eviction::EvictionPolicyKind;
 //!
 //! // Create a backend with 1000 item capacity and LFU eviction
 //! let config = MemoryBackendConfig {
 //!     max_capacity: 1000,
-//!     eviction_policy: "lfu".to_string(),
+//!     eviction_policy: EvictionPolicyKind::Lfu,
+//!     ..Default::default()
 //! };
 //!
 //! let backend = MemoryBackend::with_config(config);
@@ -55,30 +62,141 @@
 //!
 //! ```
 //! use fncache::backends::memory::MemoryBackend;
+//! use fncache::eviction::EvictionPolicyKind;
 //!
 //! // Create a backend with 500 item capacity and LRU eviction
 //! let backend = MemoryBackend::new()
 //!     .with_capacity(500)
-//!     .with_eviction_policy("lru");
+//!     .with_eviction_policy(EvictionPolicyKind::Lru);
+//!
+//! // Or bound the cache by size: evict once keys and values exceed 64 MiB
+//! let backend = MemoryBackend::new().with_max_weight(64 * 1024 * 1024);
+//! ```
+//!
+//! Observing removals:
+//!
+//! ```
+//! use fncache::backends::memory::MemoryBackend;
+//! use fncache::backends::RemovalCause;
+//!
+//! let backend = MemoryBackend::new()
+//!     .with_capacity(100)
+//!     .on_removal(|key: &String, _value: &Vec<u8>, cause: RemovalCause| {
+//!         if cause.was_evicted() {
+//!             println!("{} left the cache: {:?}", key, cause);
+//!         }
+//!     });
 //! ```
 
+use super::timer_wheel::TimerWheel;
 use super::*;
-use crate::eviction::EvictionPolicy;
+use crate::eviction::{EvictionPolicy, EvictionPolicyKind};
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError};
 use std::time::{Duration, Instant};
 
 /// An entry in the in-memory cache.
 ///
-/// Each cache entry stores the serialized value and an optional expiration time.
-/// When the expiration time is reached, the entry is considered invalid and will
-/// be removed on the next access or during cleanup operations.
+/// Each cache entry stores the serialized value, an optional absolute expiration
+/// time and an optional idle deadline. When either deadline is reached, the entry
+/// is considered invalid and will be removed on the next access or during cleanup
+/// operations.
 #[derive(Debug)]
 struct CacheEntry {
     /// The actual cached value (serialized as bytes)
     value: Value,
     /// Optional expiration timestamp, after which the entry is considered invalid
     expires_at: Option<Instant>,
+    /// Optional time-to-idle, used to push `idle_expires_at` forward on access
+    tti: Option<Duration>,
+    /// Optional idle deadline, refreshed on every successful read
+    idle_expires_at: Option<Instant>,
+    /// Weight of the entry as computed by the configured `Weigher`
+    weight: u64,
+    /// Eviction priority the entry was stored with
+    priority: Priority,
+}
+
+impl CacheEntry {
+    /// Creates a new entry, computing its deadlines from `options`.
+    fn new(value: Value, weight: u64, options: &SetOptions) -> Self {
+        let now = Instant::now();
+        Self {
+            value,
+            weight,
+            priority: options.priority,
+            expires_at: options.ttl.map(|ttl| now + ttl),
+            tti: options.tti,
+            idle_expires_at: options.tti.map(|tti| now + tti),
+        }
+    }
+
+    /// Returns `true` if either the absolute or the idle deadline has passed.
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| now >= at)
+            || self.idle_expires_at.is_some_and(|at| now >= at)
+    }
+
+    /// Returns the earliest of the absolute and idle deadlines, if any.
+    fn deadline(&self) -> Option<Instant> {
+        match (self.expires_at, self.idle_expires_at) {
+            (Some(absolute), Some(idle)) => Some(absolute.min(idle)),
+            (absolute, idle) => absolute.or(idle),
+        }
+    }
+
+    /// Pushes the idle deadline forward after a successful read.
+    fn touch(&mut self, now: Instant) {
+        if let Some(tti) = self.tti {
+            self.idle_expires_at = Some(now + tti);
+        }
+    }
+}
+
+/// Computes the weight of a cache entry for [`MemoryBackendConfig::max_weight`].
+///
+/// Any `Fn(&Key, &Value) -> u64` closure is a weigher.
+///
+/// # Examples
+///
+/// ```
+/// use fncache::backends::memory::MemoryBackend;
+///
+/// // Count only the value bytes and ignore the key
+/// let backend = MemoryBackend::new()
+///     .with_max_weight(64 * 1024 * 1024)
+///     .with_weigher(|_key: &String, value: &Vec<u8>| value.len() as u64);
+/// ```
+pub trait Weigher: Send + Sync {
+    /// Returns the weight of the entry.
+    fn weigh(&self, key: &Key, value: &Value) -> u64;
+}
+
+impl<F> Weigher for F
+where
+    F: Fn(&Key, &Value) -> u64 + Send + Sync,
+{
+    fn weigh(&self, key: &Key, value: &Value) -> u64 {
+        self(key, value)
+    }
+}
+
+impl fmt::Debug for dyn Weigher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Weigher")
+    }
+}
+
+/// The default weigher: the length of the value plus the length of the key, in bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultWeigher;
+
+impl Weigher for DefaultWeigher {
+    fn weigh(&self, key: &Key, value: &Value) -> u64 {
+        (key.len() + value.len()) as u64
+    }
 }
 
 /// Configuration options for the memory backend.
@@ -90,11 +208,13 @@ struct CacheEntry {
 ///
 /// ```
 /// use fncache::backends::memory::{MemoryBackend, MemoryBackendConfig};
+/// use fncache::eviction::EvictionPolicyKind;
 ///
 /// // Create a configuration with 10,000 item limit and LFU eviction
 /// let config = MemoryBackendConfig {
 ///     max_capacity: 10_000,
-///     eviction_policy: "lfu".to_string(),
+///     eviction_policy: EvictionPolicyKind::Lfu,
+///     ..Default::default()
 /// };
 ///
 /// // Use the config to create a memory backend
@@ -106,22 +226,48 @@ pub struct MemoryBackendConfig {
     ///
     /// When this limit is reached, the configured eviction policy will
     /// be used to determine which items to remove. Setting this to 0
-    /// disables the capacity limit.
+    /// disables the capacity limit. Unlimited by default.
     pub max_capacity: usize,
 
-    /// Eviction policy name ("lru", "lfu").
+    /// Eviction policy used once the capacity limit is reached.
+    ///
+    /// Defaults to [`EvictionPolicyKind::Lru`]. Use
+    /// [`EvictionPolicyKind::Custom`] to plug in your own
+    /// [`EvictionPolicy`] implementation.
+    pub eviction_policy: EvictionPolicyKind,
+
+    /// Maximum total weight of all items in the cache (0 = unlimited).
+    ///
+    /// Each entry is weighed by [`weigher`](Self::weigher) when it is
+    /// written, and the eviction policy removes items until the new entry
+    /// fits. An entry heavier than the limit on its own is not stored.
+    /// Applies in addition to `max_capacity`. Unlimited by default.
+    pub max_weight: u64,
+
+    /// Computes entry weights for `max_weight`.
+    ///
+    /// Defaults to [`DefaultWeigher`], which makes `max_weight` a bound on
+    /// the key and value bytes held by the cache.
+    pub weigher: Arc<dyn Weigher>,
+
+    /// Largest share of `max_capacity` and `max_weight` that pinned entries
+    /// may take, between 0.0 and 1.0.
     ///
-    /// Supported values:
-    /// - "lru": Least Recently Used - removes least recently accessed items first
-    /// - "lfu": Least Frequently Used - removes least frequently accessed items first
-    pub eviction_policy: String,
+    /// A pinned entry that would exceed either share is stored with
+    /// [`Priority::High`] instead, so pinning cannot leave the cache without
+    /// room for regular entries. Has no effect on unbounded caches.
+    /// Defaults to 0.5.
+    pub max_pinned_fraction: f64,
 }
 
 impl Default for MemoryBackendConfig {
     fn default() -> Self {
         Self {
-            max_capacity: 0, // Unlimited by default
-            eviction_policy: "lru".to_string(),
+            max_capacity: 0,
+            eviction_policy: EvictionPolicyKind::default(),
+            max_weight: 0,
+            weigher: Arc::new(DefaultWeigher),
+            max_pinned_fraction: 0.5,
         }
     }
 }
@@ -148,13 +294,14 @@ impl Default for MemoryBackendConfig {
 /// ```
 /// use fncache::backends::memory::MemoryBackend;
 /// use fncache::backends::CacheBackend;
+/// use fncache::eviction::EvictionPolicyKind;
 /// use std::time::Duration;
 ///
 /// # async fn example() -> fncache::Result<()> {
 /// // Create a new memory backend with LRU eviction and 1000 item capacity
 /// let backend = MemoryBackend::new()
 ///     .with_capacity(1000)
-///     .with_eviction_policy("lru");
+///     .with_eviction_policy(EvictionPolicyKind::Lru);
 ///
 /// // Store an item with 30-second TTL
 /// let key = "session:user123".to_string();
@@ -177,6 +324,16 @@ pub struct MemoryBackend {
     config: MemoryBackendConfig,
     /// The active eviction policy implementation
     eviction_policy: Arc<dyn EvictionPolicy<Key, Value>>,
+    /// Deadlines of entries with a TTL or TTI, used for incremental expiry
+    expirations: Mutex<TimerWheel<Key>>,
+    /// Sum of the weights of all stored entries
+    total_weight: AtomicU64,
+    /// Number of stored entries with `Priority::Pinned`
+    pinned_count: AtomicUsize,
+    /// Sum of the weights of stored entries with `Priority::Pinned`
+    pinned_weight: AtomicU64,
+    /// Callbacks notified when entries leave the cache
+    listeners: RemovalListeners,
 }
 
 impl Default for MemoryBackend {
@@ -238,22 +395,31 @@ impl MemoryBackend {
     ///
     /// ```
     /// use fncache::backends::memory::{MemoryBackend, MemoryBackendConfig};
+    /// use fncache::eviction::EvictionPolicyKind;
     ///
     /// let config = MemoryBackendConfig {
     ///     max_capacity: 5000,
-    ///     eviction_policy: "lfu".to_string(),
+    ///     eviction_policy: EvictionPolicyKind::Lfu,
+    ///     ..Default::default()
     /// };
     ///
     /// let backend = MemoryBackend::with_config(config);
     /// ```
     pub fn with_config(config: MemoryBackendConfig) -> Self {
-        let eviction_policy = crate::eviction::create_policy(&config.eviction_policy);
+        let eviction_policy = config
+            .eviction_policy
+            .build_prioritized(config.max_capacity);
 
         Self {
             store: DashMap::new(),
             metrics: crate::metrics::Metrics::default(),
             config,
             eviction_policy,
+            expirations: Mutex::new(TimerWheel::new()),
+            total_weight: AtomicU64::new(0),
+            pinned_count: AtomicUsize::new(0),
+            pinned_weight: AtomicU64::new(0),
+            listeners: RemovalListeners::default(),
         }
     }
 
@@ -282,9 +448,68 @@ impl MemoryBackend {
     /// ```
     pub fn with_capacity(mut self, max_capacity: usize) -> Self {
         self.config.max_capacity = max_capacity;
+        // Rebuild the policy so capacity-dependent state is sized to match.
+        self.eviction_policy = self.config.eviction_policy.build_prioritized(max_capacity);
+        self
+    }
+
+    /// Sets the maximum total weight of the cache.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// With the default weigher the weight of an entry is its key and value
+    /// length in bytes, so this bounds the memory held by cached data.
+    /// Setting the weight to 0 means unlimited.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_weight` - The maximum total weight of all items (0 = unlimited)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fncache::backends::memory::MemoryBackend;
+    ///
+    /// let backend = MemoryBackend::new()
+    ///     .with_max_weight(256 * 1024 * 1024); // About 256 MiB of keys and values
+    /// ```
+    pub fn with_max_weight(mut self, max_weight: u64) -> Self {
+        self.config.max_weight = max_weight;
+        self
+    }
+
+    /// Sets the weigher used to compute entry weights for `max_weight`.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// Entries that are already stored keep the weight they were given
+    /// when they were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `weigher` - A [`Weigher`], such as a `Fn(&Key, &Value) -> u64` closure
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fncache::backends::memory::MemoryBackend;
+    ///
+    /// // Account for per-entry bookkeeping overhead
+    /// let backend = MemoryBackend::new()
+    ///     .with_max_weight(1 << 20)
+    ///     .with_weigher(|key: &String, value: &Vec<u8>| (key.len() + value.len() + 64) as u64);
+    /// ```
+    pub fn with_weigher(mut self, weigher: impl Weigher + 'static) -> Self {
+        self.config.weigher = Arc::new(weigher);
         self
     }
 
+    /// Returns the total weight of all entries currently stored.
+    ///
+    /// Like [`get_store_len`](Self::get_store_len), this may include expired
+    /// entries that haven't been cleaned up yet.
+    pub fn weighted_size(&self) -> u64 {
+        self.total_weight.load(Ordering::Relaxed)
+    }
+
     /// Sets the eviction policy for the cache.
     ///
     /// This is a builder method that returns `self` for method chaining.
@@ -293,7 +518,7 @@ impl MemoryBackend {
     ///
     /// # Arguments
     ///
-    /// * `policy_name` - The name of the eviction policy to use ("lru", "lfu")
+    /// * `policy` - The eviction policy to use
     ///
     /// # Returns
     ///
@@ -303,37 +528,186 @@ impl MemoryBackend {
     ///
     /// ```
     /// use fncache::backends::memory::MemoryBackend;
+    /// use fncache::eviction::EvictionPolicyKind;
     ///
     /// // Create a backend with Least Frequently Used eviction policy
     /// let backend = MemoryBackend::new()
-    ///     .with_eviction_policy("lfu");
+    ///     .with_eviction_policy(EvictionPolicyKind::Lfu);
+    ///
+    /// // Policies can also be selected by name, e.g. from a configuration file
+    /// let policy: EvictionPolicyKind = "sieve".parse()?;
+    /// let backend = MemoryBackend::new().with_eviction_policy(policy);
+    /// # Ok::<(), fncache::FncacheError>(())
     /// ```
-    pub fn with_eviction_policy(mut self, policy_name: &str) -> Self {
-        self.config.eviction_policy = policy_name.to_string();
-        self.eviction_policy = crate::eviction::create_policy(policy_name);
+    pub fn with_eviction_policy(mut self, policy: EvictionPolicyKind) -> Self {
+        self.eviction_policy = policy.build_prioritized(self.config.max_capacity);
+        self.config.eviction_policy = policy;
         self
     }
 
+    /// Spawns a background task that periodically removes expired entries.
+    ///
+    /// Expired entries are otherwise only removed lazily when the cache is
+    /// accessed. The janitor holds a weak reference to the backend and stops
+    /// on its own once the backend is dropped. Must be called from within a
+    /// tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How often to sweep for expired entries
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fncache::backends::memory::MemoryBackend;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let backend = Arc::new(MemoryBackend::new());
+    /// let janitor = backend.spawn_janitor(Duration::from_secs(1));
+    ///
+    /// // ... use the backend ...
+    ///
+    /// janitor.abort();
+    /// # }
+    /// ```
+    pub fn spawn_janitor(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let backend = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match backend.upgrade() {
+                    Some(backend) => backend.cleanup_expired(),
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Sets the largest share of the capacity that pinned entries may take.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// Values outside `0.0..=1.0` are clamped. See
+    /// [`MemoryBackendConfig::max_pinned_fraction`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fncache::backends::memory::MemoryBackend;
+    /// use fncache::backends::{CacheBackend, Priority, SetOptions};
+    ///
+    /// # async fn example() -> fncache::Result<()> {
+    /// // At most 10 of the 100 entries can be pinned
+    /// let backend = MemoryBackend::new()
+    ///     .with_capacity(100)
+    ///     .with_max_pinned_fraction(0.1);
+    ///
+    /// let options = SetOptions::new().with_priority(Priority::Pinned);
+    /// backend
+    ///     .set_with_options("config".to_string(), b"{}".to_vec(), options)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_max_pinned_fraction(mut self, fraction: f64) -> Self {
+        self.config.max_pinned_fraction = if fraction.is_nan() {
+            0.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Registers a listener that is called for every entry leaving the cache.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// The listener receives the key, the value and the [`RemovalCause`]:
+    /// expiration, eviction by the capacity or weight limit, an explicit
+    /// `remove`, replacement by a newer value, or `clear`. It runs after the
+    /// entry has been removed and without any internal lock held, so it may
+    /// call back into the cache. Listeners are called in registration order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use fncache::backends::memory::MemoryBackend;
+    /// use fncache::backends::{CacheBackend, RemovalCause};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// # async fn example() -> fncache::Result<()> {
+    /// let evicted = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = evicted.clone();
+    /// let backend = MemoryBackend::new()
+    ///     .with_capacity(1)
+    ///     .on_removal(move |key: &String, _: &Vec<u8>, cause: RemovalCause| {
+    ///         sink.lock().unwrap().push((key.clone(), cause));
+    ///     });
+    ///
+    /// backend.set("a".to_string(), vec![1], None).await?;
+    /// backend.set("b".to_string(), vec![2], None).await?;
+    /// assert_eq!(evicted.lock().unwrap().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_removal(mut self, listener: impl RemovalListener + 'static) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Locks the expiration wheel, recovering from a poisoned lock.
+    fn expirations(&self) -> MutexGuard<'_, TimerWheel<Key>> {
+        self.expirations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Removes expired entries from the cache.
     ///
-    /// This method scans the cache for entries whose TTL has expired and removes them.
-    /// It's called automatically during operations like `get` to ensure expired
-    /// entries are not returned to clients.
+    /// This method advances the expiration timer wheel and removes only the
+    /// entries whose deadline has passed, so its cost is proportional to the
+    /// number of expiring entries rather than the size of the cache. It's
+    /// called automatically during operations like `get` to ensure expired
+    /// entries are not returned to clients. If another thread is already
+    /// advancing the wheel, the call returns immediately.
     ///
-    /// When an entry is removed due to expiration, the eviction policy is notified
-    /// and metrics are updated.
+    /// When an entry is removed due to expiration, the eviction policy and the
+    /// removal listeners are notified and metrics are updated. Entries whose idle deadline was pushed forward
+    /// since they were scheduled are rescheduled instead.
     fn cleanup_expired(&self) {
         let now = Instant::now();
-        self.store.retain(|key, entry| {
-            if let Some(expires_at) = entry.expires_at {
-                if now >= expires_at {
+        let candidates = match self.expirations.try_lock() {
+            Ok(mut wheel) => wheel.advance(now),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().advance(now),
+            Err(TryLockError::WouldBlock) => return,
+        };
+
+        let mut reschedule = Vec::new();
+        for key in candidates {
+            match self.store.remove_if(&key, |_, entry| entry.is_expired(now)) {
+                Some((key, entry)) => {
+                    self.account_removal(&entry);
                     self.metrics.record_eviction();
-                    self.eviction_policy.on_remove(key);
-                    return false;
+                    self.eviction_policy.on_remove(&key);
+                    self.listeners
+                        .notify(&key, &entry.value, RemovalCause::Expired);
+                }
+                None => {
+                    if let Some(deadline) = self.store.get(&key).and_then(|e| e.deadline()) {
+                        reschedule.push((key, deadline));
+                    }
                 }
             }
-            true
-        });
+        }
+
+        if !reschedule.is_empty() {
+            let mut wheel = self.expirations();
+            for (key, deadline) in reschedule {
+                wheel.schedule(key, deadline);
+            }
+        }
     }
 
     /// Enforces the capacity limit by evicting items if necessary.
@@ -351,16 +725,111 @@ impl MemoryBackend {
 
         let to_evict = self.store.len() - self.config.max_capacity;
 
-        let eviction_result = self.eviction_policy.evict(to_evict);
-
-        if eviction_result.keys_to_evict.is_empty() && to_evict > 0 {
+        if self.evict(to_evict, EvictionReason::Capacity) == 0 && to_evict > 0 {
             eprintln!("Warning: Eviction policy returned no keys to evict when {} items needed to be evicted", to_evict);
         }
+    }
+
+    /// Evicts entries until an entry of `weight` can be stored under `key`
+    /// without exceeding `max_weight`.
+    ///
+    /// The current weight of `key`, if present, is not counted because the
+    /// new entry replaces it. Stops early if the policy runs out of keys.
+    fn enforce_weight_limit(&self, key: &Key, weight: u64) {
+        loop {
+            let replaced = self.store.get(key).map_or(0, |entry| entry.weight);
+            let projected = self.weighted_size().saturating_sub(replaced) + weight;
+            if projected <= self.config.max_weight || self.evict(1, EvictionReason::Weight) == 0 {
+                return;
+            }
+        }
+    }
 
-        for key in eviction_result.keys_to_evict {
-            self.store.remove(&key);
+    /// Asks the eviction policy for up to `count` keys and removes them,
+    /// reporting them to the removal listeners as evicted for `reason`.
+    ///
+    /// Returns the number of keys the policy selected.
+    fn evict(&self, count: usize, reason: EvictionReason) -> usize {
+        let keys_to_evict = self.eviction_policy.evict(count).keys_to_evict;
+        let selected = keys_to_evict.len();
+        for key in keys_to_evict {
+            if let Some(entry) = self.discard(&key) {
+                self.listeners
+                    .notify(&key, &entry.value, RemovalCause::Evicted(reason));
+            }
             self.metrics.record_eviction();
         }
+        selected
+    }
+
+    /// Removes `key` from the store and the expiration wheel.
+    ///
+    /// Keeps the weight and size accounting in sync but does not notify the
+    /// eviction policy. The wheel is only locked if the entry had a deadline.
+    fn discard(&self, key: &Key) -> Option<CacheEntry> {
+        let (_, entry) = self.store.remove(key)?;
+        if entry.deadline().is_some() {
+            self.expirations().cancel(key);
+        }
+        self.account_removal(&entry);
+        Some(entry)
+    }
+
+    /// Removes `key` from the store if its entry is still expired at `now`.
+    ///
+    /// A concurrent `set` may have replaced the entry since it was found to be
+    /// expired, in which case nothing is removed. The wheel is left alone: its
+    /// slot entry is already due and is dropped on the next advance.
+    fn discard_expired(&self, key: &Key, now: Instant) -> Option<CacheEntry> {
+        let (_, entry) = self
+            .store
+            .remove_if(key, |_, entry| entry.is_expired(now))?;
+        self.account_removal(&entry);
+        Some(entry)
+    }
+
+    /// Returns the priority to store an entry of `weight` under `key` with.
+    ///
+    /// Pinned entries that would take more than `max_pinned_fraction` of
+    /// either limit are demoted to `Priority::High`.
+    fn admit_priority(&self, key: &Key, weight: u64, priority: Priority) -> Priority {
+        if priority != Priority::Pinned {
+            return priority;
+        }
+
+        let (count, pinned_weight) = match self.store.get(key) {
+            Some(entry) if entry.priority == Priority::Pinned => (0, 0),
+            _ => (1, weight),
+        };
+        let fraction = self.config.max_pinned_fraction;
+        let fits = |used: f64, limit: f64| limit == 0.0 || used <= fraction * limit;
+
+        let count = (self.pinned_count.load(Ordering::Relaxed) + count) as f64;
+        let pinned_weight = (self.pinned_weight.load(Ordering::Relaxed) + pinned_weight) as f64;
+        if fits(count, self.config.max_capacity as f64)
+            && fits(pinned_weight, self.config.max_weight as f64)
+        {
+            Priority::Pinned
+        } else {
+            Priority::High
+        }
+    }
+
+    /// Updates the pinned entry accounting for an entry that left the store.
+    fn release_pin(&self, entry: &CacheEntry) {
+        if entry.priority == Priority::Pinned {
+            self.pinned_count.fetch_sub(1, Ordering::Relaxed);
+            self.pinned_weight
+                .fetch_sub(entry.weight, Ordering::Relaxed);
+        }
+    }
+
+    /// Updates the weight and size accounting for an entry that left the store.
+    fn account_removal(&self, entry: &CacheEntry) {
+        self.release_pin(entry);
+        self.total_weight.fetch_sub(entry.weight, Ordering::Relaxed);
+        let size = bincode::serialized_size(&entry.value).unwrap_or(0) as usize;
+        self.metrics.record_entry_removal(size);
     }
 
     /// Returns the current number of items in the cache.
@@ -405,27 +874,36 @@ impl CacheBackend for MemoryBackend {
 
         self.cleanup_expired();
 
-        let result = if let Some(entry) = self.store.get(key) {
-            if let Some(expires_at) = entry.expires_at {
-                if Instant::now() > expires_at {
-                    self.metrics.record_miss();
-                    self.store.remove(key);
-                    Ok(None)
-                } else {
-                    self.eviction_policy.on_access(key);
-
-                    self.metrics.record_hit();
-                    Ok(Some(entry.value.clone()))
-                }
+        let now = Instant::now();
+        let lookup = self.store.get_mut(key).map(|mut entry| {
+            if entry.is_expired(now) {
+                None
             } else {
+                entry.touch(now);
+                Some(entry.value.clone())
+            }
+        });
+
+        let result = match lookup {
+            Some(Some(value)) => {
                 self.eviction_policy.on_access(key);
 
                 self.metrics.record_hit();
-                Ok(Some(entry.value.clone()))
+                Ok(Some(value))
+            }
+            Some(None) => {
+                self.metrics.record_miss();
+                if let Some(entry) = self.discard_expired(key, now) {
+                    self.eviction_policy.on_remove(key);
+                    self.listeners
+                        .notify(key, &entry.value, RemovalCause::Expired);
+                }
+                Ok(None)
+            }
+            None => {
+                self.metrics.record_miss();
+                Ok(None)
             }
-        } else {
-            self.metrics.record_miss();
-            Ok(None)
         };
 
         self.metrics.record_get_latency(timing);
@@ -434,44 +912,88 @@ impl CacheBackend for MemoryBackend {
     }
 
     async fn set(&self, key: Key, value: Value, ttl: Option<Duration>) -> crate::Result<()> {
-        let timing = self.metrics.begin_set_timing();
+        let options = SetOptions {
+            ttl,
+            ..SetOptions::default()
+        };
+        self.set_with_options(key, value, options).await
+    }
 
-        let new_size = bincode::serialized_size(&value).unwrap_or(0) as usize;
+    async fn set_with_options(
+        &self,
+        key: Key,
+        value: Value,
+        options: SetOptions,
+    ) -> crate::Result<()> {
+        let timing = self.metrics.begin_set_timing();
 
-        let old_size = if let Some(old_entry) = self.store.get(&key) {
-            bincode::serialized_size(&old_entry.value).unwrap_or(0) as usize
-        } else {
-            0
-        };
+        let weight = self.config.weigher.weigh(&key, &value);
+        if self.config.max_weight > 0 && weight > self.config.max_weight {
+            // The entry can never fit. Drop any previous value instead of
+            // leaving a stale one behind.
+            if let Some(entry) = self.discard(&key) {
+                self.eviction_policy.on_remove(&key);
+                self.listeners.notify(
+                    &key,
+                    &entry.value,
+                    RemovalCause::Evicted(EvictionReason::Weight),
+                );
+            }
+            self.metrics.record_eviction();
+            self.metrics.record_set_latency(timing);
+            return Ok(());
+        }
 
         let is_existing_key = self.store.contains_key(&key);
         if !is_existing_key
             && self.config.max_capacity > 0
             && self.store.len() >= self.config.max_capacity
         {
-            let to_evict = 1;
-            let eviction_result = self.eviction_policy.evict(to_evict);
-
-            for key_to_evict in eviction_result.keys_to_evict {
-                if let Some(evicted_entry) = self.store.get(&key_to_evict) {
-                    let evicted_size =
-                        bincode::serialized_size(&evicted_entry.value).unwrap_or(0) as usize;
-                    self.metrics.record_entry_removal(evicted_size);
-                }
-                self.store.remove(&key_to_evict);
-                self.metrics.record_eviction();
-            }
+            self.evict(1, EvictionReason::Capacity);
         }
+        if self.config.max_weight > 0 {
+            self.enforce_weight_limit(&key, weight);
+        }
+
+        let new_size = bincode::serialized_size(&value).unwrap_or(0) as usize;
+        let old_size = if let Some(old_entry) = self.store.get(&key) {
+            bincode::serialized_size(&old_entry.value).unwrap_or(0) as usize
+        } else {
+            0
+        };
 
-        let entry = CacheEntry {
-            value: value.clone(),
-            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        let priority = self.admit_priority(&key, weight, options.priority);
+        let options = SetOptions {
+            priority,
+            ..options
         };
+        let entry = CacheEntry::new(value.clone(), weight, &options);
+        let deadline = entry.deadline();
 
         self.metrics.record_entry_size(old_size, new_size);
 
-        self.eviction_policy.on_insert(&key, &value);
-        self.store.insert(key, entry);
+        self.eviction_policy
+            .on_insert_with_priority(&key, &value, priority);
+        if let Some(deadline) = deadline {
+            self.expirations().schedule(key.clone(), deadline);
+        }
+        self.total_weight.fetch_add(weight, Ordering::Relaxed);
+        if priority == Priority::Pinned {
+            self.pinned_count.fetch_add(1, Ordering::Relaxed);
+            self.pinned_weight.fetch_add(weight, Ordering::Relaxed);
+        }
+        if let Some(replaced) = self.store.insert(key.clone(), entry) {
+            // Entries without a TTL never touch the wheel lock unless they
+            // replace one that was scheduled.
+            if deadline.is_none() && replaced.deadline().is_some() {
+                self.expirations().cancel(&key);
+            }
+            self.release_pin(&replaced);
+            self.total_weight
+                .fetch_sub(replaced.weight, Ordering::Relaxed);
+            self.listeners
+                .notify(&key, &replaced.value, RemovalCause::Replaced);
+        }
         self.metrics.record_insertion();
 
         if self.config.max_capacity > 0 && self.store.len() > self.config.max_capacity {
@@ -484,29 +1006,44 @@ impl CacheBackend for MemoryBackend {
     }
 
     async fn remove(&self, key: &Key) -> crate::Result<()> {
-        let size = if let Some(entry) = self.store.get(key) {
-            bincode::serialized_size(&entry.value).unwrap_or(0) as usize
-        } else {
-            0
-        };
-
         self.eviction_policy.on_remove(key);
-
-        let removed = self.store.remove(key).is_some();
-        if removed && size > 0 {
-            self.metrics.record_entry_removal(size);
+        if let Some(entry) = self.discard(key) {
+            self.listeners
+                .notify(key, &entry.value, RemovalCause::Explicit);
         }
-
         Ok(())
     }
 
     async fn contains_key(&self, key: &Key) -> crate::Result<bool> {
         self.cleanup_expired();
-        Ok(self.store.contains_key(key))
+        let now = Instant::now();
+        Ok(self
+            .store
+            .get(key)
+            .is_some_and(|entry| !entry.is_expired(now)))
     }
 
     async fn clear(&self) -> crate::Result<()> {
-        self.store.clear();
+        self.expirations().clear();
+        self.eviction_policy.reset();
+        if self.listeners.is_empty() {
+            self.store.clear();
+            self.total_weight.store(0, Ordering::Relaxed);
+            self.pinned_count.store(0, Ordering::Relaxed);
+            self.pinned_weight.store(0, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        // Remove entries one at a time so each can be reported without
+        // holding a shard lock while a listener runs.
+        let keys: Vec<Key> = self.store.iter().map(|entry| entry.key().clone()).collect();
+        for key in keys {
+            if let Some((key, entry)) = self.store.remove(&key) {
+                self.account_removal(&entry);
+                self.listeners
+                    .notify(&key, &entry.value, RemovalCause::Cleared);
+            }
+        }
         Ok(())
     }
 }
@@ -547,6 +1084,130 @@ mod tests {
         assert!(backend.get(&key).await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_ttl_beyond_timer_wheel_span() {
+        let backend = Arc::new(MemoryBackend::new());
+        let key = "test_long_ttl".to_string();
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        assert!(backend.get(&key).await.unwrap().is_none());
+        backend
+            .set(
+                key.clone(),
+                b"test_value".to_vec(),
+                Some(Duration::from_secs(1000 * 24 * 60 * 60)),
+            )
+            .await
+            .unwrap();
+
+        // Look the key up on a detached thread so a stuck wheel fails the test
+        // instead of hanging it.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(futures::executor::block_on(backend.get(&key)).unwrap());
+        });
+        let result = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("lookup after scheduling a far-future deadline hung");
+        assert_eq!(result, Some(b"test_value".to_vec()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_tti_sliding_expiration() {
+        let backend = MemoryBackend::new();
+        let key = "test_tti".to_string();
+        let value = b"test_value".to_vec();
+
+        let options = SetOptions::new().with_tti(Duration::from_millis(100));
+        backend
+            .set_with_options(key.clone(), value, options)
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            assert!(backend.get(&key).await.unwrap().is_some());
+        }
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert!(backend.get(&key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_tti_capped_by_ttl() {
+        let backend = MemoryBackend::new();
+        let key = "test_tti_cap".to_string();
+        let value = b"test_value".to_vec();
+
+        let options = SetOptions::new()
+            .with_ttl(Duration::from_millis(150))
+            .with_tti(Duration::from_millis(100));
+        backend
+            .set_with_options(key.clone(), value, options)
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(backend.get(&key).await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert!(backend.get(&key).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_expired_entries_removed_without_access() {
+        let backend = MemoryBackend::new();
+        let value = b"test_value".to_vec();
+
+        for i in 0..100 {
+            backend
+                .set(
+                    format!("short_{}", i),
+                    value.clone(),
+                    Some(Duration::from_millis(50)),
+                )
+                .await
+                .unwrap();
+        }
+        backend
+            .set("long".to_string(), value.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(backend.get_store_len().await, 101);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(backend.get(&"long".to_string()).await.unwrap().is_some());
+        assert_eq!(backend.get_store_len().await, 1);
+        assert_eq!(backend.metrics.evictions(), 100);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_janitor_removes_expired_entries() {
+        let backend = Arc::new(MemoryBackend::new());
+        let janitor = backend.spawn_janitor(Duration::from_millis(20));
+
+        backend
+            .set(
+                "janitor_key".to_string(),
+                b"test_value".to_vec(),
+                Some(Duration::from_millis(30)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(backend.get_store_len().await, 1);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(backend.get_store_len().await, 0);
+
+        janitor.abort();
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_metrics() {
@@ -564,4 +1225,238 @@ mod tests {
         assert!(backend.get(&key).await.unwrap().is_some());
         assert_eq!(backend.metrics.hits(), 1);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_max_weight_evicts_until_entry_fits() {
+        let backend = MemoryBackend::new().with_max_weight(100);
+
+        for key in ["k1", "k2", "k3"] {
+            backend
+                .set(key.to_string(), vec![0; 28], None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.weighted_size(), 90);
+
+        // A 60-byte entry needs two of the 30-byte entries gone.
+        backend
+            .set("big".to_string(), vec![0; 57], None)
+            .await
+            .unwrap();
+        assert_eq!(backend.weighted_size(), 90);
+        assert!(!backend.contains_key(&"k1".to_string()).await.unwrap());
+        assert!(!backend.contains_key(&"k2".to_string()).await.unwrap());
+        assert!(backend.contains_key(&"k3".to_string()).await.unwrap());
+
+        // Replacing an entry only counts the difference in weight.
+        backend
+            .set("k3".to_string(), vec![0; 38], None)
+            .await
+            .unwrap();
+        assert_eq!(backend.weighted_size(), 100);
+        assert_eq!(backend.get_store_len().await, 2);
+
+        backend.remove(&"big".to_string()).await.unwrap();
+        assert_eq!(backend.weighted_size(), 40);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_entry_heavier_than_max_weight_is_not_stored() {
+        let backend = MemoryBackend::new().with_max_weight(10);
+        let key = "key".to_string();
+
+        backend.set(key.clone(), vec![1], None).await.unwrap();
+        backend.set(key.clone(), vec![0; 100], None).await.unwrap();
+
+        assert!(backend.get(&key).await.unwrap().is_none());
+        assert_eq!(backend.weighted_size(), 0);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_custom_weigher() {
+        let backend = MemoryBackend::new()
+            .with_max_weight(3)
+            .with_weigher(|_key: &Key, _value: &Value| 1);
+
+        for i in 0..5 {
+            backend
+                .set(format!("key{}", i), vec![0; 1000], None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(backend.get_store_len().await, 3);
+        assert_eq!(backend.weighted_size(), 3);
+    }
+
+    type Removals = Arc<Mutex<Vec<(Key, Value, RemovalCause)>>>;
+
+    fn recording(backend: MemoryBackend) -> (MemoryBackend, Removals) {
+        let removals = Removals::default();
+        let sink = removals.clone();
+        let backend = backend.on_removal(move |key: &Key, value: &Value, cause| {
+            sink.lock()
+                .unwrap()
+                .push((key.clone(), value.clone(), cause));
+        });
+        (backend, removals)
+    }
+
+    fn causes(removals: &Removals) -> Vec<(Key, RemovalCause)> {
+        removals
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, _, cause)| (key.clone(), *cause))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_removal_listener_reports_each_cause() {
+        let (backend, removals) = recording(MemoryBackend::new().with_capacity(2));
+
+        backend.set("a".to_string(), vec![1], None).await.unwrap();
+        backend.set("a".to_string(), vec![2], None).await.unwrap();
+        backend.set("b".to_string(), vec![3], None).await.unwrap();
+        backend.set("c".to_string(), vec![4], None).await.unwrap();
+        backend.remove(&"b".to_string()).await.unwrap();
+        backend.remove(&"missing".to_string()).await.unwrap();
+        backend.clear().await.unwrap();
+
+        assert_eq!(
+            causes(&removals),
+            vec![
+                ("a".to_string(), RemovalCause::Replaced),
+                (
+                    "a".to_string(),
+                    RemovalCause::Evicted(EvictionReason::Capacity)
+                ),
+                ("b".to_string(), RemovalCause::Explicit),
+                ("c".to_string(), RemovalCause::Cleared),
+            ]
+        );
+        // Listeners see the value that left the cache, not the new one.
+        assert_eq!(removals.lock().unwrap()[0].1, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_removal_listener_reports_expiry_and_weight_evictions() {
+        let (backend, removals) = recording(MemoryBackend::new().with_max_weight(20));
+
+        backend
+            .set(
+                "short".to_string(),
+                vec![0; 4],
+                Some(Duration::from_millis(20)),
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(backend.get(&"short".to_string()).await.unwrap(), None);
+
+        backend
+            .set("k1".to_string(), vec![0; 8], None)
+            .await
+            .unwrap();
+        backend
+            .set("k2".to_string(), vec![0; 8], None)
+            .await
+            .unwrap();
+        backend
+            .set("k3".to_string(), vec![0; 5], None)
+            .await
+            .unwrap();
+        // A value that can never fit pushes out the one it replaces.
+        backend
+            .set("k2".to_string(), vec![0; 100], None)
+            .await
+            .unwrap();
+
+        let weight = RemovalCause::Evicted(EvictionReason::Weight);
+        assert_eq!(
+            causes(&removals),
+            vec![
+                ("short".to_string(), RemovalCause::Expired),
+                ("k1".to_string(), weight),
+                ("k2".to_string(), weight),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_panicking_removal_listener_does_not_fail_the_operation() {
+        let backend = MemoryBackend::new()
+            .on_removal(|_: &Key, _: &Value, _: RemovalCause| panic!("listener failure"));
+
+        backend.set("key".to_string(), vec![1], None).await.unwrap();
+        backend.remove(&"key".to_string()).await.unwrap();
+
+        assert!(!backend.contains_key(&"key".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_entries_are_never_evicted() {
+        let backend = MemoryBackend::new()
+            .with_capacity(4)
+            .with_max_pinned_fraction(0.5);
+        let pinned = SetOptions::new().with_priority(Priority::Pinned);
+        let high = SetOptions::new().with_priority(Priority::High);
+
+        backend
+            .set_with_options("config".to_string(), vec![1], pinned)
+            .await
+            .unwrap();
+        backend
+            .set_with_options("tenant".to_string(), vec![2], high)
+            .await
+            .unwrap();
+        for i in 0..20 {
+            backend
+                .set(format!("key{}", i), vec![0], None)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(backend.get_store_len().await, 4);
+        assert!(backend.contains_key(&"config".to_string()).await.unwrap());
+        assert!(backend.contains_key(&"tenant".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_entries_beyond_the_cap_are_demoted() {
+        let backend = MemoryBackend::new()
+            .with_capacity(4)
+            .with_max_pinned_fraction(0.5);
+        let pinned = SetOptions::new().with_priority(Priority::Pinned);
+
+        for i in 0..4 {
+            backend
+                .set_with_options(format!("pinned{}", i), vec![0], pinned.clone())
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.pinned_count.load(Ordering::Relaxed), 2);
+
+        // Regular entries push out the demoted ones but not the pinned ones.
+        for i in 0..4 {
+            backend
+                .set(format!("key{}", i), vec![0], None)
+                .await
+                .unwrap();
+        }
+        assert!(backend.contains_key(&"pinned0".to_string()).await.unwrap());
+        assert!(backend.contains_key(&"pinned1".to_string()).await.unwrap());
+        assert!(!backend.contains_key(&"pinned2".to_string()).await.unwrap());
+
+        // Removing a pinned entry frees its slot.
+        backend.remove(&"pinned0".to_string()).await.unwrap();
+        backend
+            .set_with_options("pinned4".to_string(), vec![0], pinned)
+            .await
+            .unwrap();
+        assert_eq!(backend.pinned_count.load(Ordering::Relaxed), 2);
+    }
 }
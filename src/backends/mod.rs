@@ -8,7 +8,7 @@
 //! # Available Backends
 //!
 //! * **Memory Backend** (always available): In-memory cache using `dashmap` with support
-//!   for configurable eviction policies (LRU, LFU).
+//!   for configurable eviction policies (see [`crate::eviction`]).
 //!
 //! * **File Backend** (with `file-backend` feature): Persistent cache stored on disk with
 //!   optional compression.
@@ -20,6 +20,13 @@
 //!
 //! * **WASM Backend** (with `wasm` feature): Backend optimized for WebAssembly environments.
 //!
+//! * **Tiered Backend** (always available): A local L1 backend in front of any L2 backend,
+//!   such as a memory cache in front of Redis.
+//!
+//! The file, Redis and RocksDB backends also implement [`StreamingBackend`],
+//! which stores and returns values through [`AsyncRead`] for values too large
+//! to hold in memory.
+//!
 //! # Example: Using the Memory Backend
 //!
 //! ```
@@ -41,7 +48,27 @@
 //! ```
 
 use async_trait::async_trait;
-use std::{fmt::Debug, time::Duration};
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+use futures::Stream;
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+use std::task::{ready, Context, Poll};
+use std::{
+    fmt::{self, Debug},
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::io::AsyncRead;
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+use tokio::io::ReadBuf;
+#[cfg(any(
+    feature = "file-backend",
+    feature = "redis-backend",
+    feature = "rocksdb-backend"
+))]
+use {std::io, tokio::io::AsyncReadExt};
 
 #[cfg(feature = "file-backend")]
 pub mod file;
@@ -50,6 +77,8 @@ pub mod memory;
 pub mod redis;
 #[cfg(feature = "rocksdb-backend")]
 pub mod rocksdb;
+pub mod tiered;
+mod timer_wheel;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
@@ -70,6 +99,200 @@ pub type Key = String;
 /// for cached functions.
 pub type Value = Vec<u8>;
 
+/// A computation of a missing value for [`CacheBackend::get_or_compute`].
+///
+/// The future only runs if it is polled, so a backend that finds the value
+/// already cached simply drops it. Once polled, however, it must be driven to
+/// completion: the caller may not be able to run the computation again, and
+/// `#[fncache(single_flight)]` functions panic if it is dropped half-way.
+pub type Compute<'a> = Pin<Box<dyn Future<Output = crate::Result<Value>> + Send + 'a>>;
+
+/// Per-entry options for [`CacheBackend::set_with_options`].
+///
+/// `ttl` is an absolute lifetime measured from insertion, while `tti`
+/// (time-to-idle) is a sliding window that is pushed forward every time the
+/// entry is read. When both are set the entry expires at whichever deadline
+/// comes first, so `ttl` acts as a hard cap on how long a frequently used
+/// entry may live.
+///
+/// # Examples
+///
+/// ```
+/// use fncache::backends::SetOptions;
+/// use std::time::Duration;
+///
+/// // Live for as long as the entry is read at least every 5 minutes,
+/// // but never longer than one hour.
+/// let options = SetOptions::new()
+///     .with_ttl(Duration::from_secs(3600))
+///     .with_tti(Duration::from_secs(300));
+///
+/// assert_eq!(options.tti, Some(Duration::from_secs(300)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetOptions {
+    /// Absolute time-to-live measured from insertion.
+    pub ttl: Option<Duration>,
+    /// Time-to-idle, refreshed on every successful read.
+    pub tti: Option<Duration>,
+    /// How strongly the entry resists eviction.
+    pub priority: Priority,
+}
+
+/// How strongly an entry resists eviction.
+///
+/// Priorities only affect which entries the eviction policy selects to make
+/// room. Expiration and explicit removal apply to every entry alike, and
+/// backends without an eviction policy ignore them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Evicted according to the eviction policy.
+    #[default]
+    Normal,
+    /// Evicted only when no `Normal` entry is left to evict.
+    High,
+    /// Never evicted. Backends may cap the share of their capacity that
+    /// pinned entries take; see
+    /// [`MemoryBackendConfig::max_pinned_fraction`](memory::MemoryBackendConfig::max_pinned_fraction).
+    Pinned,
+}
+
+impl SetOptions {
+    /// Creates options with no expiration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the absolute time-to-live.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the sliding time-to-idle.
+    pub fn with_tti(mut self, tti: Duration) -> Self {
+        self.tti = Some(tti);
+        self
+    }
+
+    /// Sets the eviction priority.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the fixed TTL to use on backends without sliding expiration.
+    ///
+    /// This is the shorter of `ttl` and `tti`, so an entry never outlives
+    /// either deadline even though reads do not extend it.
+    pub fn fixed_ttl(&self) -> Option<Duration> {
+        match (self.ttl, self.tti) {
+            (Some(ttl), Some(tti)) => Some(ttl.min(tti)),
+            (ttl, tti) => ttl.or(tti),
+        }
+    }
+}
+
+/// Why an entry left the cache, as reported to a [`RemovalListener`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemovalCause {
+    /// The entry's TTL or TTI deadline passed.
+    Expired,
+    /// The eviction policy removed the entry to make room.
+    Evicted(EvictionReason),
+    /// The entry was removed with [`CacheBackend::remove`].
+    Explicit,
+    /// The entry was overwritten by a new value for the same key.
+    Replaced,
+    /// The entry was removed by [`CacheBackend::clear`].
+    Cleared,
+}
+
+impl RemovalCause {
+    /// Returns `true` if the cache removed the entry on its own, through
+    /// expiration or eviction, rather than because the caller asked for it.
+    pub fn was_evicted(&self) -> bool {
+        matches!(self, Self::Expired | Self::Evicted(_))
+    }
+}
+
+/// The limit that forced an eviction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionReason {
+    /// The cache reached its maximum number of entries.
+    Capacity,
+    /// The cache reached its maximum total weight.
+    Weight,
+}
+
+/// Receives entries as they leave a cache.
+///
+/// Listeners run synchronously on the task that caused the removal, after the
+/// entry is gone from the cache, so they should be quick. Hand long-running
+/// work such as writing to a slower tier off to another task. A panicking
+/// listener is caught and reported without affecting the cache operation.
+///
+/// Any `Fn(&Key, &Value, RemovalCause)` closure is a listener.
+///
+/// [`memory::MemoryBackend`] reports every removal. The file backend reports
+/// the removals it can attribute to a key. The Redis and RocksDB backends
+/// expire entries on the server or during compaction and do not support
+/// listeners.
+pub trait RemovalListener: Send + Sync {
+    /// Called once for every entry that leaves the cache.
+    fn on_removal(&self, key: &Key, value: &Value, cause: RemovalCause);
+}
+
+impl<F> RemovalListener for F
+where
+    F: Fn(&Key, &Value, RemovalCause) + Send + Sync,
+{
+    fn on_removal(&self, key: &Key, value: &Value, cause: RemovalCause) {
+        self(key, value, cause)
+    }
+}
+
+/// The removal listeners registered on a backend.
+#[derive(Clone, Default)]
+pub(crate) struct RemovalListeners {
+    listeners: Vec<Arc<dyn RemovalListener>>,
+}
+
+impl RemovalListeners {
+    /// Registers another listener.
+    pub(crate) fn push(&mut self, listener: impl RemovalListener + 'static) {
+        self.listeners.push(Arc::new(listener));
+    }
+
+    /// Returns `true` if no listener is registered.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Reports a removed entry to every listener.
+    pub(crate) fn notify(&self, key: &Key, value: &Value, cause: RemovalCause) {
+        for listener in &self.listeners {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                listener.on_removal(key, value, cause);
+            }));
+            if result.is_err() {
+                eprintln!(
+                    "Warning: removal listener panicked for key {:?} ({:?})",
+                    key, cause
+                );
+            }
+        }
+    }
+}
+
+impl Debug for RemovalListeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemovalListeners")
+            .field("count", &self.listeners.len())
+            .finish()
+    }
+}
+
 /// Trait defining the interface for all cache backends.
 ///
 /// This trait provides a uniform interface for interacting with different cache
@@ -172,6 +395,31 @@ pub trait CacheBackend: Send + Sync + Debug {
     /// * `Err(...)` - An error occurred while storing the value
     async fn set(&self, key: Key, value: Value, ttl: Option<Duration>) -> crate::Result<()>;
 
+    /// Sets a value in the cache with extended per-entry options.
+    ///
+    /// Backends that support sliding expiration override this method. The
+    /// default implementation falls back to [`CacheBackend::set`] using
+    /// [`SetOptions::fixed_ttl`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to store the value under
+    /// * `value` - The value to store in the cache
+    /// * `options` - Expiration options for the entry
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The value was successfully stored
+    /// * `Err(...)` - An error occurred while storing the value
+    async fn set_with_options(
+        &self,
+        key: Key,
+        value: Value,
+        options: SetOptions,
+    ) -> crate::Result<()> {
+        self.set(key, value, options.fixed_ttl()).await
+    }
+
     /// Removes a value from the cache by key.
     ///
     /// This method removes the entry with the specified key from the cache.
@@ -213,6 +461,48 @@ pub trait CacheBackend: Send + Sync + Debug {
     /// * `Ok(())` - The cache was successfully cleared
     /// * `Err(...)` - An error occurred while clearing the cache
     async fn clear(&self) -> crate::Result<()>;
+
+    /// Returns the cached value of `key`, computing and storing it on a miss.
+    ///
+    /// The default implementation reads the key, and on a miss awaits
+    /// `compute` and stores its result with `options`; a failure to store
+    /// it is logged and the computed value is still returned. Backends
+    /// shared between processes override this so that only one process
+    /// computes a missing key while the others wait for its result.
+    ///
+    /// Implementations may drop `compute` without polling it, but once they
+    /// poll it they must await it until it completes, even if storing the
+    /// result is going to fail. See [`Compute`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to read or fill
+    /// * `options` - Expiration options for a computed value
+    /// * `compute` - Computes the value on a miss
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(value)` - The cached or the computed value
+    /// * `Err(...)` - Reading the key or computing the value failed
+    async fn get_or_compute(
+        &self,
+        key: Key,
+        options: SetOptions,
+        compute: Compute<'_>,
+    ) -> crate::Result<Value> {
+        if let Some(value) = self.get(&key).await? {
+            return Ok(value);
+        }
+
+        let value = compute.await?;
+        if let Err(e) = self
+            .set_with_options(key.clone(), value.clone(), options)
+            .await
+        {
+            eprintln!("Warning: failed to cache computed value of {}: {}", key, e);
+        }
+        Ok(value)
+    }
 }
 
 /// A boxed cache backend that can be used as a trait object.
@@ -231,3 +521,135 @@ pub trait CacheBackend: Send + Sync + Debug {
 /// }
 /// ```
 pub type Backend = Box<dyn CacheBackend>;
+
+/// Size of the chunks in which backends read, store and return streamed values.
+pub const STREAM_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A reader over a cached value, returned by [`StreamingBackend::get_stream`].
+pub type ValueReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Streaming access to values too large to hold in memory.
+///
+/// [`CacheBackend`] moves whole values as `Vec<u8>`, so caching a blob of
+/// several hundred megabytes takes at least as much memory. Backends that
+/// implement this extension also store a value read from an [`AsyncRead`]
+/// and return one as an [`AsyncRead`], keeping about one
+/// [`STREAM_CHUNK_SIZE`] chunk of it in memory at a time.
+///
+/// Values stored with [`put_stream`](Self::put_stream) are read back by
+/// [`CacheBackend::get`] and values stored with [`CacheBackend::set`] by
+/// [`get_stream`](Self::get_stream).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "file-backend")]
+/// # async fn example() -> fncache::Result<()> {
+/// use fncache::backends::{file::FileBackend, StreamingBackend};
+/// use tokio::io::AsyncReadExt;
+///
+/// let backend = FileBackend::new("/tmp/fncache")?;
+///
+/// let upload = tokio::fs::File::open("/data/model.bin").await?;
+/// let len = backend.put_stream("model".to_string(), upload, None).await?;
+/// println!("Cached {} bytes", len);
+///
+/// if let Some(mut reader) = backend.get_stream(&"model".to_string()).await? {
+///     let mut header = [0; 16];
+///     reader.read_exact(&mut header).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait StreamingBackend: CacheBackend {
+    /// Stores the bytes read from `reader` until its end as the value of `key`.
+    ///
+    /// The previous value of `key`, if any, stays readable until the new one
+    /// has been read completely, and is kept if reading `reader` fails.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(len)` - The number of bytes stored
+    /// * `Err(...)` - Reading `reader` or storing the value failed
+    async fn put_stream<R>(&self, key: Key, reader: R, ttl: Option<Duration>) -> crate::Result<u64>
+    where
+        R: AsyncRead + Send + Unpin;
+
+    /// Returns a reader over the value of `key`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(reader))` - The value was found in the cache
+    /// * `Ok(None)` - The value was not found or has expired
+    /// * `Err(...)` - An error occurred while accessing the cache
+    async fn get_stream(&self, key: &Key) -> crate::Result<Option<ValueReader>>;
+}
+
+/// Reads the next chunk of at most [`STREAM_CHUNK_SIZE`] bytes from `reader`
+/// into `chunk`, returning its length, which is 0 at the end of the reader.
+///
+/// Chunks are only shorter than [`STREAM_CHUNK_SIZE`] at the end.
+#[cfg(any(
+    feature = "file-backend",
+    feature = "redis-backend",
+    feature = "rocksdb-backend"
+))]
+pub(crate) async fn read_chunk<R>(reader: &mut R, chunk: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: AsyncRead + Unpin + ?Sized,
+{
+    chunk.clear();
+    reader
+        .take(STREAM_CHUNK_SIZE as u64)
+        .read_to_end(chunk)
+        .await
+}
+
+/// Returns a [`ValueReader`] over the chunks produced by `chunks`.
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+pub(crate) fn chunk_reader<S>(chunks: S) -> ValueReader
+where
+    S: Stream<Item = crate::Result<Vec<u8>>> + Send + 'static,
+{
+    Box::pin(ChunkReader {
+        chunks: Box::pin(chunks),
+        chunk: Vec::new(),
+        position: 0,
+    })
+}
+
+/// Reader over a stream of chunks, fetching the next chunk once the current
+/// one has been read.
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+struct ChunkReader {
+    chunks: Pin<Box<dyn Stream<Item = crate::Result<Vec<u8>>> + Send>>,
+    chunk: Vec<u8>,
+    /// How much of `chunk` has been read
+    position: usize,
+}
+
+#[cfg(any(feature = "redis-backend", feature = "rocksdb-backend"))]
+impl AsyncRead for ChunkReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.position == self.chunk.len() {
+            match ready!(self.chunks.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                Some(Err(e)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+
+        let len = buf.remaining().min(self.chunk.len() - self.position);
+        buf.put_slice(&self.chunk[self.position..self.position + len]);
+        self.position += len;
+        Poll::Ready(Ok(()))
+    }
+}
@@ -10,7 +10,7 @@
 //! * Distributed caching across multiple application instances
 //! * TTL (time-to-live) support using Redis native expiration
 //! * Key prefixing to prevent collisions in shared Redis instances
-//! * JSON serialization for storing complex values
+//! * Values stored as raw bytes behind a small binary header
 //! * Built-in metrics for hits, misses, and insertions
 //! * Async operations using tokio-based Redis client
 //!
@@ -45,34 +45,795 @@
 //! # }
 //! ```
 //!
+//! # Deployments
+//!
+//! [`RedisBackendConfig`] selects a single server, a Redis Cluster or a
+//! Sentinel-managed primary, along with timeouts, the connection pool size
+//! and whether reads may be served by replicas:
+//!
+//! ```rust,no_run
+//! use fncache::backends::redis::{RedisBackend, RedisBackendConfig};
+//! use std::time::Duration;
+//!
+//! # async fn example() -> fncache::Result<()> {
+//! let config = RedisBackendConfig::sentinel(
+//!     ["redis://10.0.0.1:26379", "redis://10.0.0.2:26379"],
+//!     "mymaster",
+//! )
+//! .with_prefix("myapp:")
+//! .with_connection_timeout(Duration::from_secs(2))
+//! .with_response_timeout(Duration::from_millis(500))
+//! .with_pool_size(4);
+//! let backend = RedisBackend::with_config(config).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! In a cluster, keys are stored as `{<prefix><bucket>}<key>`, with the prefix and
+//! a bucket number between braces. Redis hashes only that hash tag, so all the
+//! keys of a bucket share one slot and `clear` can scan and unlink them in
+//! batches; the buckets themselves spread over the cluster. With Sentinel,
+//! a connection that fails or reaches a demoted primary is re-resolved
+//! through the sentinels and the command is retried once.
+//!
+//! # Tags
+//!
+//! [`RedisBackend::set_with_tags`] records each key in one Redis set per tag,
+//! written atomically with the value. Because the index lives in Redis, it
+//! survives restarts and is shared by every process using the prefix.
+//! [`AsyncCacheInvalidation::invalidate_tag`] deletes the members of the set
+//! and the set itself in one atomic script, and
+//! [`AsyncCacheInvalidation::invalidate_prefix`] unlinks the matching keys
+//! with `SCAN`. On a Redis Cluster, each hash tag bucket keeps its own set
+//! per tag, in the slot of the keys it indexes, and tag operations visit
+//! every bucket. Keys starting with `__tag__:` are reserved.
+//!
+//! ```rust,no_run
+//! use fncache::backends::redis::RedisBackend;
+//! use fncache::invalidation::{AsyncCacheInvalidation, Tag};
+//!
+//! # async fn example() -> fncache::Result<()> {
+//! let backend = RedisBackend::new("redis://127.0.0.1:6379", Some("myapp:")).await?;
+//! backend
+//!     .set_with_tags("user:123:profile".to_string(), vec![1, 2, 3], None, [Tag::new("user:123")])
+//!     .await?;
+//!
+//! // Removes the profile for every process sharing the Redis server.
+//! backend.invalidate_tag(&Tag::new("user:123")).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Single-Flight Computation
+//!
+//! When a popular key expires, every process that misses it would otherwise
+//! recompute it at once. [`CacheBackend::get_or_compute`], and functions
+//! marked `#[fncache(single_flight)]`, take a lease-based lock on the key
+//! first (`SET NX PX`): one process computes the value while the others poll
+//! for it, and compute it themselves only after the wait timeout. A value
+//! computed after the wait timed out is returned without being stored. Each
+//! lock carries a fencing token from a counter in Redis, incremented by the
+//! same script that takes the lock, so failed attempts do not write; in a
+//! cluster each hash tag bucket has its own counter. The computed value is
+//! stored only while the lock still holds that token, so a process whose
+//! lease ran out mid-computation cannot overwrite a newer value. The lease,
+//! the wait timeout and the polling interval are set on
+//! [`RedisBackendConfig`]. Keys starting with `__lock__:` are reserved, as
+//! are keys starting with `__stream__:` (see [Streaming](#streaming)).
+//!
+//! Waiting processes poll for the value, doubling the delay from 5 ms up to
+//! the polling interval, rather than subscribe to a notification. Pub/sub
+//! would need a dedicated connection per waiting process, is not available
+//! through the cluster connections, and would still need a poll for a value
+//! filled before the subscription started.
+//!
+//! # Streaming
+//!
+//! `RedisBackend` implements [`StreamingBackend`] for values too large to
+//! hold in memory. `put_stream` writes the entry header to a staging key
+//! below `__stream__:` and appends the value to it one chunk at a time, then
+//! renames the staging key over the entry, so readers see the old value until
+//! the new one is complete. A staging key left by an abandoned upload expires
+//! an hour after its last chunk. `get_stream` reads the entry in chunks with
+//! `GETRANGE`, checking its header and length along with every chunk; a
+//! reader fails rather than mix two values if the entry is replaced or
+//! removed while it is read. The header records the creation time in seconds,
+//! so a replacement of the same length within the same second goes
+//! unnoticed. A streamed value is still stored as a single Redis string, so
+//! it is subject to the server's string size limit (`proto-max-bulk-len`,
+//! 512 MB by default).
+//!
 //! # Implementation Details
 //!
-//! * Cache entries are serialized to JSON before storage
+//! * Cache entries are stored as a 12-byte header followed by the raw value bytes
+//!   (see [Storage Format](#storage-format))
 //! * TTL is implemented using Redis's native expiration mechanism
 //! * All keys are prefixed (default: "fncache:") to avoid collisions
-//! * Clear operation only removes keys with the configured prefix
+//! * Clear operation only removes keys with the configured prefix, scanning
+//!   them in batches with `SCAN` and deleting each batch with `UNLINK`, so it
+//!   never blocks the server for the whole keyspace
+//!
+//! # Generation Namespaces
+//!
+//! Clearing by scan still visits every key with the prefix. With
+//! [`RedisBackend::with_generation_namespace`] the keys are stored under
+//! `{prefix}{generation}:{key}` instead, where the generation is a counter kept
+//! in Redis. `clear` then only increments the counter: the old keys become
+//! unreachable at once and are left to expire. Each process caches the
+//! generation for a configurable refresh interval, so a clear made by another
+//! process is seen within that interval. Entries set without a TTL are never
+//! reclaimed after a clear unless Redis evicts them, so give every entry a TTL
+//! or run Redis with a `maxmemory` eviction policy in this mode.
+//!
+//! # Storage Format
+//!
+//! Each Redis value is laid out as:
+//!
+//! | Bytes  | Content                                           |
+//! |--------|---------------------------------------------------|
+//! | 0..3   | Magic `0xFC 'f' 'n'`                              |
+//! | 3      | Format version, currently `1`                     |
+//! | 4..12  | Creation time, Unix seconds, big-endian `u64`     |
+//! | 12..   | The value bytes, unmodified                       |
+//!
+//! Earlier releases stored a JSON object `{"value":[...],"created_at":...}`,
+//! which took three to four bytes per value byte. Entries in that legacy
+//! format are still read, and are replaced by the binary format on their next
+//! write. Reading the legacy format will be removed in the next release.
 
-use crate::{backends::CacheBackend, error::Error, metrics::Metrics, Result};
+use crate::backends::{
+    chunk_reader, read_chunk, CacheBackend, Compute, Key, SetOptions, StreamingBackend,
+    ValueReader, STREAM_CHUNK_SIZE,
+};
+use crate::invalidation::{AsyncCacheInvalidation, Tag};
+use crate::{error::Error, metrics::Metrics, Result};
 use async_trait::async_trait;
-use redis::{aio::ConnectionManager, AsyncCommands, Client, RedisError};
-use serde::{Deserialize, Serialize};
+use futures::{
+    future,
+    stream::{self, StreamExt},
+};
+use redis::{
+    aio::{ConnectionLike, ConnectionManager, MultiplexedConnection},
+    cluster::ClusterClientBuilder,
+    cluster_async::ClusterConnection,
+    cluster_routing::{get_slot, Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr},
+    sentinel::{SentinelClient, SentinelServerType},
+    AsyncCommands, Client, Cmd, FromRedisValue, Pipeline, RedisError, RedisFuture, Script, Value,
+};
+use serde::Deserialize;
 use std::{
+    collections::HashSet,
     fmt,
-    sync::Arc,
-    time::{Duration, SystemTime},
+    future::Future,
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime},
 };
+use tokio::io::AsyncRead;
+
+/// Number of keys requested per `SCAN` call when clearing.
+const SCAN_BATCH: usize = 500;
+
+/// Name of the generation counter, below the key prefix.
+const GENERATION_KEY: &str = "__generation__";
+
+/// Start of the names of tag sets, below the key prefix.
+const TAG_SET_PREFIX: &str = "__tag__:";
+
+/// How cache keys are laid out below the prefix.
+#[derive(Debug, Clone)]
+enum Namespace {
+    /// Keys are stored as `{prefix}{key}` and cleared by scanning.
+    Fixed,
+    /// Keys are stored as `{prefix}{generation}:{key}` and cleared by
+    /// incrementing the generation.
+    Generation {
+        /// How long a fetched generation is used before it is read again
+        refresh: Duration,
+        /// The last generation fetched and when, shared between clones
+        cached: Arc<Mutex<Option<(u64, Instant)>>>,
+    },
+}
+
+/// Magic bytes at the start of every entry in the binary format.
+///
+/// `0xFC` is not valid as the first byte of UTF-8, so it can never start a
+/// legacy JSON entry.
+const ENTRY_MAGIC: [u8; 3] = [0xFC, b'f', b'n'];
 
-/// Entry stored in the Redis cache
+/// Current version of the binary entry format.
+const ENTRY_VERSION: u8 = 1;
+
+/// Length of the binary entry header: magic, version and creation time.
+const ENTRY_HEADER_LEN: usize = 12;
+
+/// Entry stored by earlier releases as JSON.
 ///
-/// This structure represents a single cache entry that's serialized to JSON
-/// and stored in Redis. It contains both the value bytes and creation timestamp.
-#[derive(Debug, Serialize, Deserialize)]
-struct CacheEntry {
+/// Only read, to migrate existing data; new entries use the binary format.
+#[derive(Debug, Deserialize)]
+struct LegacyCacheEntry {
     /// The cached value as bytes
     value: Vec<u8>,
-    /// Unix timestamp (seconds since epoch) when the entry was created
-    /// Used for tracking age of entries in Redis
-    created_at: u64,
+}
+
+/// Encodes a value in the binary entry format.
+fn encode_entry(value: &[u8], created_at: u64) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(ENTRY_HEADER_LEN + value.len());
+    bytes.extend_from_slice(&ENTRY_MAGIC);
+    bytes.push(ENTRY_VERSION);
+    bytes.extend_from_slice(&created_at.to_be_bytes());
+    bytes.extend_from_slice(value);
+    bytes
+}
+
+/// Decodes an entry in the binary format, or in the legacy JSON format.
+fn decode_entry(mut bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if bytes.starts_with(&ENTRY_MAGIC) {
+        return match bytes.get(ENTRY_MAGIC.len()) {
+            Some(&ENTRY_VERSION) if bytes.len() >= ENTRY_HEADER_LEN => {
+                bytes.drain(..ENTRY_HEADER_LEN);
+                Ok(bytes)
+            }
+            Some(&ENTRY_VERSION) => Err(Error::Codec("Truncated cache entry header".into())),
+            version => Err(Error::Codec(format!(
+                "Unsupported cache entry version: {:?}",
+                version
+            ))),
+        };
+    }
+
+    serde_json::from_slice::<LegacyCacheEntry>(&bytes)
+        .map(|entry| entry.value)
+        .map_err(|e| Error::Codec(format!("Failed to deserialize cache entry: {}", e)))
+}
+
+/// Default number of hash tag buckets keys are spread over in a cluster.
+const DEFAULT_HASH_TAG_BUCKETS: u16 = 16;
+
+/// Name of the fencing token counter, below the key prefix, or below each
+/// hash tag in a cluster.
+const FENCE_KEY: &str = "__fence__";
+
+/// Start of the names of single-flight locks, below the hash tag if any.
+const LOCK_PREFIX: &str = "__lock__:";
+
+/// Start of the names of the keys streamed values are staged in, below the
+/// hash tag if any.
+const STREAM_PREFIX: &str = "__stream__:";
+
+/// How long a staged streamed value is kept after its last chunk was
+/// appended, should the upload be abandoned.
+const STREAM_STAGING_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// First delay between two checks for a value another process computes.
+const FIRST_POLL_DELAY: Duration = Duration::from_millis(5);
+
+/// Settings of the single-flight locks used by [`RedisBackend::get_or_compute`].
+#[derive(Debug, Clone, Copy)]
+struct LockSettings {
+    /// How long a lock is held before it expires on its own
+    lease: Duration,
+    /// How long to wait for another process before computing anyway
+    wait_timeout: Duration,
+    /// Longest delay between two checks while waiting
+    poll_interval: Duration,
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        Self {
+            lease: Duration::from_secs(30),
+            wait_timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// A single-flight lock held on a key, acquired with
+/// [`RedisBackend::try_lock`].
+///
+/// The lock expires on its own once its lease runs out. Every lock carries a
+/// fencing token that is greater than the tokens of all earlier locks, so
+/// that writes made by a holder whose lease ran out can be told apart from
+/// those of the current holder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    /// The Redis key of the lock
+    lock_key: String,
+    /// The fencing token stored in the lock
+    token: u64,
+}
+
+impl Lease {
+    /// Returns the fencing token of the lock.
+    ///
+    /// Pass it on to other systems written while holding the lock, so that
+    /// they can reject writes carrying a smaller token.
+    pub fn token(&self) -> u64 {
+        self.token
+    }
+}
+
+/// The Redis deployment a [`RedisBackend`] connects to.
+#[derive(Debug, Clone)]
+enum Topology {
+    /// A single server
+    Single(String),
+    /// A Redis Cluster, reached through any of its nodes
+    Cluster(Vec<String>),
+    /// A primary managed by Redis Sentinel
+    Sentinel {
+        /// URLs of the sentinels
+        sentinels: Vec<String>,
+        /// Name the sentinels monitor the primary under
+        service_name: String,
+    },
+}
+
+/// Configuration for [`RedisBackend::with_config`].
+///
+/// Start from [`RedisBackendConfig::new`], [`RedisBackendConfig::cluster`]
+/// or [`RedisBackendConfig::sentinel`] and adjust the defaults with the
+/// `with_*` methods. No timeouts are applied by default.
+#[derive(Debug, Clone)]
+pub struct RedisBackendConfig {
+    topology: Topology,
+    prefix: String,
+    connection_timeout: Option<Duration>,
+    response_timeout: Option<Duration>,
+    pool_size: usize,
+    read_from_replicas: bool,
+    hash_tag_buckets: u16,
+    locks: LockSettings,
+}
+
+impl RedisBackendConfig {
+    fn with_topology(topology: Topology) -> Self {
+        Self {
+            topology,
+            prefix: "fncache:".to_string(),
+            connection_timeout: None,
+            response_timeout: None,
+            pool_size: 1,
+            read_from_replicas: false,
+            hash_tag_buckets: DEFAULT_HASH_TAG_BUCKETS,
+            locks: LockSettings::default(),
+        }
+    }
+
+    /// Connects to a single Redis server (e.g. `"redis://127.0.0.1:6379"`).
+    pub fn new(redis_url: impl Into<String>) -> Self {
+        Self::with_topology(Topology::Single(redis_url.into()))
+    }
+
+    /// Connects to a Redis Cluster through the given seed nodes.
+    pub fn cluster<I, S>(nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::with_topology(Topology::Cluster(
+            nodes.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Connects to the primary that the given sentinels monitor as
+    /// `service_name`, following it through failovers.
+    pub fn sentinel<I, S>(sentinels: I, service_name: impl Into<String>) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::with_topology(Topology::Sentinel {
+            sentinels: sentinels.into_iter().map(Into::into).collect(),
+            service_name: service_name.into(),
+        })
+    }
+
+    /// Sets the prefix of every key. Defaults to `"fncache:"`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sets how long establishing each connection may take.
+    pub fn with_connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long each command may wait for its response.
+    pub fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of connections commands are spread over. Defaults to 1.
+    ///
+    /// Each connection is multiplexed, so a few connections are usually
+    /// enough even for many concurrent tasks.
+    pub fn with_pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = pool_size;
+        self
+    }
+
+    /// Serves reads from replicas, which may lag behind the primary.
+    ///
+    /// Only supported for clusters and Sentinel deployments.
+    pub fn with_read_from_replicas(mut self, read_from_replicas: bool) -> Self {
+        self.read_from_replicas = read_from_replicas;
+        self
+    }
+
+    /// Sets the number of hash tag buckets keys are spread over in a
+    /// cluster. Defaults to 16.
+    ///
+    /// More buckets spread the keys over more slots, and therefore nodes,
+    /// but `clear` scans once per bucket. Ignored outside clusters.
+    pub fn with_hash_tag_buckets(mut self, buckets: u16) -> Self {
+        self.hash_tag_buckets = buckets;
+        self
+    }
+
+    /// Sets how long a single-flight lock lasts before it expires on its
+    /// own, so that a crashed process cannot block a key. Defaults to 30
+    /// seconds.
+    ///
+    /// Set it above the time a computation usually takes: once it runs out,
+    /// another process may start computing the same key.
+    pub fn with_lock_lease(mut self, lease: Duration) -> Self {
+        self.locks.lease = lease;
+        self
+    }
+
+    /// Sets how long [`RedisBackend::get_or_compute`] waits for another
+    /// process to compute a key before computing it itself. Defaults to 10
+    /// seconds.
+    pub fn with_lock_wait_timeout(mut self, timeout: Duration) -> Self {
+        self.locks.wait_timeout = timeout;
+        self
+    }
+
+    /// Sets the longest delay between two checks for a value another
+    /// process is computing. Defaults to 100 milliseconds.
+    ///
+    /// Waiting starts with short delays that double up to this interval.
+    pub fn with_lock_poll_interval(mut self, interval: Duration) -> Self {
+        self.locks.poll_interval = interval;
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.pool_size == 0 {
+            return Err(Error::Config("Redis pool size must be at least 1".into()));
+        }
+        if self.locks.lease.as_millis() == 0 {
+            return Err(Error::Config(
+                "the lock lease must be at least one millisecond".into(),
+            ));
+        }
+        match &self.topology {
+            Topology::Single(_) if self.read_from_replicas => Err(Error::Config(
+                "reading from replicas requires a cluster or Sentinel deployment".into(),
+            )),
+            Topology::Cluster(_) if self.hash_tag_buckets == 0 => Err(Error::Config(
+                "a cluster needs at least one hash tag bucket".into(),
+            )),
+            Topology::Cluster(_) if self.prefix.contains(['{', '}']) => Err(Error::Config(
+                "the key prefix of a cluster must not contain braces".into(),
+            )),
+            Topology::Cluster(nodes) if nodes.is_empty() => {
+                Err(Error::Config("a cluster needs at least one node".into()))
+            }
+            Topology::Sentinel { sentinels, .. } if sentinels.is_empty() => {
+                Err(Error::Config("Sentinel needs at least one sentinel".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns `key` stored under `prefix`, inside one of `buckets` hash tags.
+fn hash_tagged_key(prefix: &str, buckets: u16, key: &str) -> String {
+    format!("{}{}", hash_tag(prefix, buckets, key), key)
+}
+
+/// Returns the hash tag `key` is stored in, between braces.
+fn hash_tag(prefix: &str, buckets: u16, key: &str) -> String {
+    let bucket = get_slot(key.as_bytes()) % buckets;
+    format!("{{{}{}}}", prefix, bucket)
+}
+
+fn timeout_error() -> RedisError {
+    io::Error::new(io::ErrorKind::TimedOut, "Redis command timed out").into()
+}
+
+/// Awaits `future`, failing with a timeout error after `timeout`, if any.
+async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = redis::RedisResult<T>>,
+) -> redis::RedisResult<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| Err(timeout_error())),
+        None => future.await,
+    }
+}
+
+/// Establishes a connection, failing after `timeout`, if any.
+async fn connect<T>(
+    timeout: Option<Duration>,
+    future: impl Future<Output = redis::RedisResult<T>>,
+) -> Result<T> {
+    with_timeout(timeout, future)
+        .await
+        .map_err(|e| Error::Backend(format!("Failed to connect to Redis: {}", e)))
+}
+
+/// Whether `err` suggests that Sentinel has moved the primary elsewhere.
+fn is_failover_error(err: &RedisError) -> bool {
+    err.is_io_error()
+        || err.is_connection_dropped()
+        || err.is_connection_refusal()
+        || err.kind() == redis::ErrorKind::ReadOnly
+}
+
+/// A connection to a server that Sentinel resolves, re-resolved after
+/// failures.
+#[derive(Clone)]
+struct SentinelConnection {
+    client: Arc<tokio::sync::Mutex<SentinelClient>>,
+    current: Arc<Mutex<Option<MultiplexedConnection>>>,
+}
+
+impl SentinelConnection {
+    async fn connect(client: Arc<tokio::sync::Mutex<SentinelClient>>) -> redis::RedisResult<Self> {
+        let connection = client.lock().await.get_async_connection().await?;
+        Ok(Self {
+            client,
+            current: Arc::new(Mutex::new(Some(connection))),
+        })
+    }
+
+    async fn connection(&self) -> redis::RedisResult<MultiplexedConnection> {
+        if let Some(connection) = self.current.lock().unwrap().clone() {
+            return Ok(connection);
+        }
+        let connection = self.client.lock().await.get_async_connection().await?;
+        *self.current.lock().unwrap() = Some(connection.clone());
+        Ok(connection)
+    }
+
+    /// Runs `request` on the current connection and, after a failover
+    /// error, once more on a freshly resolved one.
+    async fn retry_after_failover<T, F>(&self, request: F) -> redis::RedisResult<T>
+    where
+        F: Fn(MultiplexedConnection) -> RedisFuture<'static, T>,
+    {
+        match request(self.connection().await?).await {
+            Err(e) if is_failover_error(&e) => {
+                *self.current.lock().unwrap() = None;
+                request(self.connection().await?).await
+            }
+            result => result,
+        }
+    }
+}
+
+/// One connection of the pool, to whichever deployment is configured.
+#[derive(Clone)]
+enum Connection {
+    Single(ConnectionManager),
+    Cluster(ClusterConnection),
+    Sentinel(SentinelConnection),
+}
+
+/// A pooled connection, applying the configured response timeout.
+#[derive(Clone)]
+struct PooledConnection {
+    connection: Connection,
+    response_timeout: Option<Duration>,
+}
+
+impl PooledConnection {
+    /// Runs a keyless command on the primary owning `slot` in a cluster, or
+    /// on the server elsewhere.
+    async fn query_on_slot<T: FromRedisValue>(
+        &mut self,
+        cmd: &Cmd,
+        slot: Option<u16>,
+    ) -> redis::RedisResult<T> {
+        match (&mut self.connection, slot) {
+            (Connection::Cluster(cluster), Some(slot)) => {
+                let routing =
+                    SingleNodeRoutingInfo::SpecificNode(Route::new(slot, SlotAddr::Master));
+                let value = with_timeout(
+                    self.response_timeout,
+                    cluster.route_command(cmd, RoutingInfo::SingleNode(routing)),
+                )
+                .await?;
+                T::from_redis_value(&value)
+            }
+            _ => cmd.query_async(self).await,
+        }
+    }
+}
+
+impl ConnectionLike for PooledConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let timeout = self.response_timeout;
+        Box::pin(async move {
+            match &mut self.connection {
+                Connection::Single(manager) => {
+                    with_timeout(timeout, manager.req_packed_command(cmd)).await
+                }
+                Connection::Cluster(cluster) => {
+                    with_timeout(timeout, cluster.req_packed_command(cmd)).await
+                }
+                Connection::Sentinel(sentinel) => {
+                    let cmd = Arc::new(cmd.clone());
+                    let request = move |mut connection: MultiplexedConnection| {
+                        let cmd = Arc::clone(&cmd);
+                        Box::pin(async move {
+                            with_timeout(timeout, connection.req_packed_command(&cmd)).await
+                        }) as RedisFuture<'static, Value>
+                    };
+                    sentinel.retry_after_failover(request).await
+                }
+            }
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let timeout = self.response_timeout;
+        Box::pin(async move {
+            match &mut self.connection {
+                Connection::Single(manager) => {
+                    let request = manager.req_packed_commands(pipeline, offset, count);
+                    with_timeout(timeout, request).await
+                }
+                Connection::Cluster(cluster) => {
+                    let request = cluster.req_packed_commands(pipeline, offset, count);
+                    with_timeout(timeout, request).await
+                }
+                Connection::Sentinel(sentinel) => {
+                    let pipeline = Arc::new(pipeline.clone());
+                    let request = move |mut connection: MultiplexedConnection| {
+                        let pipeline = Arc::clone(&pipeline);
+                        Box::pin(async move {
+                            let request = connection.req_packed_commands(&pipeline, offset, count);
+                            with_timeout(timeout, request).await
+                        }) as RedisFuture<'static, Vec<Value>>
+                    };
+                    sentinel.retry_after_failover(request).await
+                }
+            }
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        match &self.connection {
+            Connection::Single(manager) => manager.get_db(),
+            Connection::Cluster(cluster) => cluster.get_db(),
+            Connection::Sentinel(_) => 0,
+        }
+    }
+}
+
+/// The connections of a [`RedisBackend`], handed out round-robin.
+#[derive(Clone)]
+struct Pool {
+    /// Connections to the primary, used for writes and by default for reads
+    primaries: Arc<[PooledConnection]>,
+    /// Connections to replicas, used for reads if configured
+    replicas: Arc<[PooledConnection]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl Pool {
+    async fn connect(config: &RedisBackendConfig) -> Result<Self> {
+        let connect = |server_type| Self::connect_all(config, server_type);
+        let primaries = connect(SentinelServerType::Master).await?;
+        let replicas = match config.topology {
+            Topology::Sentinel { .. } if config.read_from_replicas => {
+                connect(SentinelServerType::Replica).await?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            primaries: primaries.into(),
+            replicas: replicas.into(),
+            next: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Opens `pool_size` connections. `server_type` selects the servers a
+    /// Sentinel deployment connects to and is ignored otherwise.
+    async fn connect_all(
+        config: &RedisBackendConfig,
+        server_type: SentinelServerType,
+    ) -> Result<Vec<PooledConnection>> {
+        let timeout = config.connection_timeout;
+        let mut connections = Vec::with_capacity(config.pool_size);
+        match &config.topology {
+            Topology::Single(url) => {
+                let client = Client::open(url.as_str())
+                    .map_err(|e| Error::Backend(format!("Failed to create Redis client: {}", e)))?;
+                for _ in 0..config.pool_size {
+                    let manager = connect(timeout, client.get_connection_manager()).await?;
+                    connections.push(Connection::Single(manager));
+                }
+            }
+            Topology::Cluster(nodes) => {
+                let mut builder = ClusterClientBuilder::new(nodes.clone());
+                if config.read_from_replicas {
+                    builder = builder.read_from_replicas();
+                }
+                let client = builder.build().map_err(|e| {
+                    Error::Backend(format!("Failed to create Redis Cluster client: {}", e))
+                })?;
+                for _ in 0..config.pool_size {
+                    let cluster = connect(timeout, client.get_async_connection()).await?;
+                    connections.push(Connection::Cluster(cluster));
+                }
+            }
+            Topology::Sentinel {
+                sentinels,
+                service_name,
+            } => {
+                let client = SentinelClient::build(
+                    sentinels.clone(),
+                    service_name.clone(),
+                    None,
+                    server_type,
+                )
+                .map_err(|e| Error::Backend(format!("Failed to create Sentinel client: {}", e)))?;
+                let client = Arc::new(tokio::sync::Mutex::new(client));
+                for _ in 0..config.pool_size {
+                    let sentinel =
+                        connect(timeout, SentinelConnection::connect(Arc::clone(&client))).await?;
+                    connections.push(Connection::Sentinel(sentinel));
+                }
+            }
+        }
+
+        Ok(connections
+            .into_iter()
+            .map(|connection| PooledConnection {
+                connection,
+                response_timeout: config.response_timeout,
+            })
+            .collect())
+    }
+
+    fn pick(&self, connections: &[PooledConnection]) -> PooledConnection {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % connections.len();
+        connections[index].clone()
+    }
+
+    /// Returns a connection to the primary.
+    fn primary(&self) -> PooledConnection {
+        self.pick(&self.primaries)
+    }
+
+    /// Returns a connection for reads, to a replica if configured.
+    fn reader(&self) -> PooledConnection {
+        if self.replicas.is_empty() {
+            self.primary()
+        } else {
+            self.pick(&self.replicas)
+        }
+    }
 }
 
 /// Redis-based cache backend for distributed caching
@@ -86,7 +847,7 @@ struct CacheEntry {
 /// * Distributed caching with Redis
 /// * TTL support via Redis expiration
 /// * Key prefixing to prevent collisions
-/// * JSON serialization for values
+/// * Raw byte storage with a small binary header
 /// * Async operations
 /// * Metrics collection
 ///
@@ -94,11 +855,12 @@ struct CacheEntry {
 ///
 /// ```rust,no_run
 /// use fncache::backends::redis::RedisBackend;
+/// use fncache::backends::CacheBackend;
 /// use std::time::Duration;
 ///
 /// # async fn run() -> fncache::Result<()> {
 /// // Create a Redis backend with specific connection and prefix
-/// let backend = RedisBackend::new("redis://127.0.0.1:6379", Some("myapp:")).await?
+/// let backend = RedisBackend::new("redis://127.0.0.1:6379", Some("myapp:")).await?;
 ///
 /// // Store a value with 5-minute TTL
 /// let key = "user:profile:123".to_string();
@@ -114,10 +876,16 @@ struct CacheEntry {
 /// ```
 #[derive(Clone)]
 pub struct RedisBackend {
-    /// Redis async connection manager (multiplexed, cloneable)
-    manager: ConnectionManager,
+    /// Multiplexed, cloneable connections
+    pool: Pool,
     /// Key prefix for all cache entries
     prefix: String,
+    /// Number of hash tag buckets in a cluster, `None` outside clusters
+    hash_tag_buckets: Option<u16>,
+    /// Layout of the keys below the prefix
+    namespace: Namespace,
+    /// Settings of the single-flight locks
+    locks: LockSettings,
     /// Cache metrics
     metrics: Arc<Metrics>,
 }
@@ -135,22 +903,425 @@ impl RedisBackend {
     /// # Errors
     /// Returns an error if connection to Redis fails
     pub async fn new(redis_url: &str, prefix: Option<&str>) -> Result<Self> {
-        let client = Client::open(redis_url)
-            .map_err(|e| Error::Backend(format!("Failed to create Redis client: {}", e)))?;
-        let manager = client
-            .get_tokio_connection_manager()
-            .await
-            .map_err(|e| Error::Backend(format!("Failed to connect to Redis: {}", e)))?;
+        let config = RedisBackendConfig::new(redis_url).with_prefix(prefix.unwrap_or("fncache:"));
+        Self::with_config(config).await
+    }
+
+    /// Creates a RedisBackend for a Redis Cluster reached through `nodes`.
+    ///
+    /// # Errors
+    /// Returns an error if connecting to the cluster fails
+    pub async fn cluster(nodes: &[&str], prefix: Option<&str>) -> Result<Self> {
+        let config = RedisBackendConfig::cluster(nodes.iter().copied())
+            .with_prefix(prefix.unwrap_or("fncache:"));
+        Self::with_config(config).await
+    }
+
+    /// Creates a RedisBackend for the primary that `sentinels` monitor as
+    /// `service_name`.
+    ///
+    /// # Errors
+    /// Returns an error if no sentinel knows the primary or connecting to it
+    /// fails
+    pub async fn sentinel(
+        sentinels: &[&str],
+        service_name: &str,
+        prefix: Option<&str>,
+    ) -> Result<Self> {
+        let config = RedisBackendConfig::sentinel(sentinels.iter().copied(), service_name)
+            .with_prefix(prefix.unwrap_or("fncache:"));
+        Self::with_config(config).await
+    }
+
+    /// Creates a RedisBackend from a [`RedisBackendConfig`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Config`] for an invalid configuration and an error
+    /// if connecting fails
+    pub async fn with_config(config: RedisBackendConfig) -> Result<Self> {
+        config.validate()?;
+        let pool = Pool::connect(&config).await?;
+        let hash_tag_buckets = match config.topology {
+            Topology::Cluster(_) => Some(config.hash_tag_buckets),
+            _ => None,
+        };
 
         Ok(Self {
-            manager,
-            prefix: prefix.unwrap_or("fncache:").to_string(),
+            pool,
+            prefix: config.prefix,
+            hash_tag_buckets,
+            namespace: Namespace::Fixed,
+            locks: config.locks,
             metrics: Arc::new(Metrics::new()),
         })
     }
 
-    fn prefixed_key(&self, key: &str) -> String {
-        format!("{}{}", self.prefix, key)
+    /// Stores keys under a generation counter so that `clear` is O(1).
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// `refresh` bounds how long a clear made by another process goes
+    /// unnoticed; `Duration::ZERO` reads the counter on every operation, at
+    /// the cost of an extra round trip. See the
+    /// [module documentation](self#generation-namespaces) for the trade-offs.
+    pub fn with_generation_namespace(mut self, refresh: Duration) -> Self {
+        self.namespace = Namespace::Generation {
+            refresh,
+            cached: Arc::new(Mutex::new(None)),
+        };
+        self
+    }
+
+    async fn prefixed_key(&self, key: &str) -> Result<String> {
+        let key = self.namespaced_key(key).await?;
+        Ok(match self.hash_tag_buckets {
+            Some(buckets) => hash_tagged_key(&self.prefix, buckets, &key),
+            None => format!("{}{}", self.prefix, key),
+        })
+    }
+
+    /// Returns `key` below the current generation, if any, but without the
+    /// prefix.
+    async fn namespaced_key(&self, key: &str) -> Result<String> {
+        Ok(match &self.namespace {
+            Namespace::Fixed => key.to_string(),
+            Namespace::Generation { .. } => format!("{}:{}", self.generation().await?, key),
+        })
+    }
+
+    /// Returns what `namespaced_key` is stored below: the hash tag of its
+    /// bucket in a cluster, or the prefix otherwise.
+    fn key_scope(&self, namespaced_key: &str) -> String {
+        match self.hash_tag_buckets {
+            Some(buckets) => hash_tag(&self.prefix, buckets, namespaced_key),
+            None => self.prefix.clone(),
+        }
+    }
+
+    /// Returns every scope keys may be stored below, see
+    /// [`RedisBackend::key_scope`].
+    fn key_scopes(&self) -> Vec<String> {
+        match self.hash_tag_buckets {
+            Some(buckets) => (0..buckets)
+                .map(|bucket| format!("{{{}{}}}", self.prefix, bucket))
+                .collect(),
+            None => vec![self.prefix.clone()],
+        }
+    }
+
+    /// Returns the set indexing the keys stored below `scope` with the tag
+    /// `namespaced_tag`.
+    ///
+    /// In a cluster, each bucket has its own set in the slot of the keys it
+    /// indexes, so that one script can update or invalidate both.
+    fn tag_set_key(scope: &str, namespaced_tag: &str) -> String {
+        format!("{}{}{}", scope, TAG_SET_PREFIX, namespaced_tag)
+    }
+
+    /// Stores a value and adds it to the Redis-side index of each tag.
+    ///
+    /// The value and the tag memberships are written atomically, by a single
+    /// script. A tag set expires with the longest-lived of its members, and
+    /// never if one of them has no TTL. Invalidate the tags with
+    /// [`AsyncCacheInvalidation::invalidate_tag`], from any process.
+    ///
+    /// # Errors
+    /// Returns an error if the write fails
+    pub async fn set_with_tags(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+        tags: impl IntoIterator<Item = Tag>,
+    ) -> Result<()> {
+        let namespaced = self.namespaced_key(&key).await?;
+        let scope = self.key_scope(&namespaced);
+        let mut tag_sets = Vec::new();
+        for tag in tags {
+            let namespaced_tag = self.namespaced_key(tag.as_str()).await?;
+            tag_sets.push(Self::tag_set_key(&scope, &namespaced_tag));
+        }
+        let redis_key = format!("{}{}", scope, namespaced);
+        let entry = encode_entry(&value, Self::system_time_to_timestamp(SystemTime::now()));
+        // A zero TTL means "no TTL" to the script.
+        let ttl_ms = ttl.map_or(0, |ttl| ttl.as_millis().max(1) as u64);
+
+        let mut conn = self.pool.primary();
+        set_with_tags_script()
+            .key(redis_key)
+            .key(tag_sets)
+            .arg(entry)
+            .arg(ttl_ms)
+            .invoke_async::<_, ()>(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)?;
+        self.metrics.record_insertion();
+        Ok(())
+    }
+
+    /// Returns the keys in the Redis-side index of `tag`.
+    ///
+    /// The index may still list keys that have since expired or been
+    /// removed.
+    pub async fn keys_by_tag(&self, tag: &Tag) -> Result<HashSet<String>> {
+        let namespaced_tag = self.namespaced_key(tag.as_str()).await?;
+        let namespace = self.namespaced_key("").await?;
+
+        let mut conn = self.pool.reader();
+        let mut keys = HashSet::new();
+        for scope in self.key_scopes() {
+            let members: Vec<String> = conn
+                .smembers(Self::tag_set_key(&scope, &namespaced_tag))
+                .await
+                .map_err(Self::convert_redis_error)?;
+            let stored_prefix = format!("{}{}", scope, namespace);
+            keys.extend(
+                members
+                    .into_iter()
+                    .filter_map(|member| member.strip_prefix(&stored_prefix).map(str::to_string)),
+            );
+        }
+        Ok(keys)
+    }
+
+    /// Returns the key of the single-flight lock of the key stored as
+    /// `namespaced_key`, in the same slot as that key in a cluster.
+    fn lock_key(&self, namespaced_key: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.key_scope(namespaced_key),
+            LOCK_PREFIX,
+            namespaced_key
+        )
+    }
+
+    /// Returns the key a streamed value of the key stored as
+    /// `namespaced_key` is staged in until it is complete, in the same slot
+    /// as that key in a cluster.
+    ///
+    /// `token` tells concurrent uploads of the same key apart.
+    fn staging_key(&self, namespaced_key: &str, token: u64) -> String {
+        format!(
+            "{}{}{}:{}",
+            self.key_scope(namespaced_key),
+            STREAM_PREFIX,
+            token,
+            namespaced_key
+        )
+    }
+
+    /// Returns the fencing token counter used for the key stored as
+    /// `namespaced_key`, in the same slot as that key in a cluster.
+    fn fence_key(&self, namespaced_key: &str) -> String {
+        format!("{}{}", self.key_scope(namespaced_key), FENCE_KEY)
+    }
+
+    /// Appends the bytes of `reader` to the entry staged at `staging_key` a
+    /// chunk at a time, extending its TTL with each chunk, and returns the
+    /// length of the value.
+    async fn append_stream<R>(
+        conn: &mut PooledConnection,
+        staging_key: &str,
+        reader: &mut R,
+    ) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let mut chunk = Vec::with_capacity(STREAM_CHUNK_SIZE);
+        let mut len = 0;
+        while read_chunk(reader, &mut chunk).await? > 0 {
+            len += chunk.len() as u64;
+            let (stored,): (u64,) = redis::pipe()
+                .append(staging_key, &chunk)
+                .pexpire(staging_key, STREAM_STAGING_TTL.as_millis() as usize)
+                .ignore()
+                .query_async(conn)
+                .await
+                .map_err(Self::convert_redis_error)?;
+            // Should a clear remove the staged entry, APPEND starts a new
+            // one without a header.
+            if stored != ENTRY_HEADER_LEN as u64 + len {
+                return Err(Error::Backend(
+                    "streamed value was removed before it was complete".into(),
+                ));
+            }
+        }
+        Ok(len)
+    }
+
+    /// Acquires the single-flight lock of `key` unless another process
+    /// holds it.
+    ///
+    /// The lock expires after the configured lease (see
+    /// [`RedisBackendConfig::with_lock_lease`]) unless released earlier with
+    /// [`unlock`](Self::unlock).
+    pub async fn try_lock(&self, key: &str) -> Result<Option<Lease>> {
+        let namespaced = self.namespaced_key(key).await?;
+        let lock_key = self.lock_key(&namespaced);
+        let mut conn = self.pool.primary();
+        let token: Option<u64> = try_lock_script()
+            .key(&lock_key)
+            .key(self.fence_key(&namespaced))
+            .arg(self.locks.lease.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)?;
+
+        Ok(token.map(|token| Lease { lock_key, token }))
+    }
+
+    /// Releases a lock if it is still held with `lease`, returning whether it
+    /// was.
+    pub async fn unlock(&self, lease: &Lease) -> Result<bool> {
+        let mut conn = self.pool.primary();
+        unlock_script()
+            .key(&lease.lock_key)
+            .arg(lease.token)
+            .invoke_async::<_, bool>(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)
+    }
+
+    /// Stores a value only if `lease` still holds the lock of its key,
+    /// returning whether it did.
+    ///
+    /// A process whose lease ran out while it was computing cannot overwrite
+    /// the value written by the process that took the lock over.
+    pub async fn set_fenced(
+        &self,
+        lease: &Lease,
+        key: &str,
+        value: &[u8],
+        ttl: Option<Duration>,
+    ) -> Result<bool> {
+        let redis_key = self.prefixed_key(key).await?;
+        let entry = encode_entry(value, Self::system_time_to_timestamp(SystemTime::now()));
+        let ttl_ms = ttl.map_or(0, |ttl| ttl.as_millis().max(1) as u64);
+
+        let mut conn = self.pool.primary();
+        let written = set_fenced_script()
+            .key(&lease.lock_key)
+            .key(redis_key)
+            .arg(lease.token)
+            .arg(entry)
+            .arg(ttl_ms)
+            .invoke_async::<_, bool>(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)?;
+        if written {
+            self.metrics.record_insertion();
+        }
+        Ok(written)
+    }
+
+    /// Waits for the lock of `key`, or for another process to fill it.
+    async fn lock_or_wait(&self, key: &Key) -> Result<Fill> {
+        let deadline = Instant::now() + self.locks.wait_timeout;
+        let mut delay = FIRST_POLL_DELAY.min(self.locks.poll_interval);
+
+        loop {
+            if let Some(lease) = self.try_lock(key).await? {
+                // The previous holder may have filled the key meanwhile.
+                return Ok(match self.get(key).await? {
+                    Some(value) => {
+                        self.unlock(&lease).await?;
+                        Fill::Filled(value)
+                    }
+                    None => Fill::Locked(lease),
+                });
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(Fill::TimedOut);
+            }
+            tokio::time::sleep(delay.min(deadline - now)).await;
+            delay = (delay * 2).min(self.locks.poll_interval);
+
+            if let Some(value) = self.get(key).await? {
+                return Ok(Fill::Filled(value));
+            }
+        }
+    }
+
+    fn generation_key(&self) -> String {
+        format!("{}{}", self.prefix, GENERATION_KEY)
+    }
+
+    /// Returns the current generation, reading it from Redis if the cached
+    /// one is older than the refresh interval.
+    async fn generation(&self) -> Result<u64> {
+        let Namespace::Generation { refresh, cached } = &self.namespace else {
+            return Ok(0);
+        };
+        if let Some((generation, fetched_at)) = *cached.lock().unwrap() {
+            if fetched_at.elapsed() < *refresh {
+                return Ok(generation);
+            }
+        }
+
+        let mut conn = self.pool.reader();
+        let generation: Option<u64> = conn
+            .get(self.generation_key())
+            .await
+            .map_err(Self::convert_redis_error)?;
+        let generation = generation.unwrap_or(0);
+        *cached.lock().unwrap() = Some((generation, Instant::now()));
+        Ok(generation)
+    }
+
+    /// Removes every key below the prefix whose remainder starts with
+    /// `start`, one `SCAN` batch at a time.
+    async fn unlink_by_scan(&self, start: &str) -> Result<()> {
+        match self.hash_tag_buckets {
+            Some(buckets) => {
+                for bucket in 0..buckets {
+                    let tag = format!("{{{}{}}}", self.prefix, bucket);
+                    let slot = get_slot(tag.as_bytes());
+                    let pattern = format!("{}{}*", escape_glob(&tag), escape_glob(start));
+                    self.scan_and_unlink(&pattern, Some(slot)).await?;
+                }
+                Ok(())
+            }
+            None => {
+                let pattern = format!("{}{}*", escape_glob(&self.prefix), escape_glob(start));
+                self.scan_and_unlink(&pattern, None).await
+            }
+        }
+    }
+
+    /// Unlinks the keys matching `pattern` on the node owning `slot`, or on
+    /// the server outside clusters.
+    ///
+    /// In a cluster, every key matching the pattern must hash to `slot`.
+    async fn scan_and_unlink(&self, pattern: &str, slot: Option<u16>) -> Result<()> {
+        let mut conn = self.pool.primary();
+        let mut cursor: u64 = 0;
+
+        loop {
+            let mut scan = redis::cmd("SCAN");
+            scan.arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(SCAN_BATCH);
+            let (next, keys): (u64, Vec<Vec<u8>>) = conn
+                .query_on_slot(&scan, slot)
+                .await
+                .map_err(Self::convert_redis_error)?;
+
+            if !keys.is_empty() {
+                redis::cmd("UNLINK")
+                    .arg(keys)
+                    .query_async::<_, i64>(&mut conn)
+                    .await
+                    .map_err(Self::convert_redis_error)?;
+            }
+
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
     }
 
     fn convert_redis_error(err: RedisError) -> Error {
@@ -172,6 +1343,8 @@ impl fmt::Debug for RedisBackend {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RedisBackend")
             .field("prefix", &self.prefix)
+            .field("hash_tag_buckets", &self.hash_tag_buckets)
+            .field("namespace", &self.namespace)
             .finish()
     }
 }
@@ -180,122 +1353,503 @@ impl fmt::Debug for RedisBackend {
 ///
 /// This implementation provides:
 /// * Distributed caching via Redis
-/// * JSON serialization for values
+/// * Raw byte storage, reading legacy JSON entries
 /// * TTL support using Redis's native key expiration
 /// * Key prefixing to avoid collisions
 /// * Metrics for hits, misses and insertions
 #[async_trait]
 impl CacheBackend for RedisBackend {
     async fn get(&self, key: &String) -> Result<Option<Vec<u8>>> {
-        let redis_key = self.prefixed_key(key);
-        let mut conn = self.manager.clone();
+        let redis_key = self.prefixed_key(key).await?;
+        let mut conn = self.pool.reader();
+
+        let result: redis::RedisResult<Option<Vec<u8>>> = conn.get(&redis_key).await;
+
+        match result {
+            Ok(Some(bytes)) => match decode_entry(bytes) {
+                Ok(value) => {
+                    self.metrics.record_hit();
+                    Ok(Some(value))
+                }
+                Err(e) => {
+                    self.metrics.record_miss();
+                    Err(e)
+                }
+            },
+            Ok(None) => {
+                self.metrics.record_miss();
+                Ok(None)
+            }
+            Err(e) => {
+                self.metrics.record_miss();
+                Err(Self::convert_redis_error(e))
+            }
+        }
+    }
+
+    async fn set(&self, key: String, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
+        let redis_key = self.prefixed_key(&key).await?;
+        let mut conn = self.pool.primary();
+
+        let entry = encode_entry(&value, Self::system_time_to_timestamp(SystemTime::now()));
+
+        let result: redis::RedisResult<()> = match ttl {
+            Some(duration) => {
+                let ttl_secs = Self::duration_to_ttl_secs(duration);
+                conn.set_ex(redis_key, entry, ttl_secs as usize).await
+            }
+            None => conn.set(redis_key, entry).await,
+        };
+
+        match result {
+            Ok(_) => {
+                self.metrics.record_insertion();
+                Ok(())
+            }
+            Err(e) => Err(Self::convert_redis_error(e)),
+        }
+    }
+
+    async fn remove(&self, key: &String) -> Result<()> {
+        let redis_key = self.prefixed_key(key).await?;
+        let mut conn = self.pool.primary();
+
+        let result: redis::RedisResult<i64> = conn.del(redis_key).await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(Self::convert_redis_error(e)),
+        }
+    }
+
+    async fn contains_key(&self, key: &String) -> Result<bool> {
+        let redis_key = self.prefixed_key(key).await?;
+        let mut conn = self.pool.reader();
+
+        let result: redis::RedisResult<bool> = conn.exists(redis_key).await;
+
+        match result {
+            Ok(exists) => Ok(exists),
+            Err(e) => Err(Self::convert_redis_error(e)),
+        }
+    }
+
+    async fn clear(&self) -> Result<()> {
+        match &self.namespace {
+            Namespace::Fixed => self.unlink_by_scan("").await,
+            Namespace::Generation { cached, .. } => {
+                let mut conn = self.pool.primary();
+                let generation: u64 = conn
+                    .incr(self.generation_key(), 1)
+                    .await
+                    .map_err(Self::convert_redis_error)?;
+                *cached.lock().unwrap() = Some((generation, Instant::now()));
+                Ok(())
+            }
+        }
+    }
+
+    /// Computes a missing key in only one process at a time.
+    ///
+    /// The process that takes the key's lock computes the value and stores
+    /// it; the others poll for it until the wait timeout, then compute it
+    /// themselves rather than fail, without storing it. The value is stored
+    /// only while the lock is still held, so a computation that outlived its
+    /// lease, or ran without the lock, cannot overwrite a newer value.
+    async fn get_or_compute(
+        &self,
+        key: Key,
+        options: SetOptions,
+        compute: Compute<'_>,
+    ) -> Result<Vec<u8>> {
+        if let Some(value) = self.get(&key).await? {
+            return Ok(value);
+        }
+
+        match self.lock_or_wait(&key).await? {
+            Fill::Filled(value) => Ok(value),
+            Fill::Locked(lease) => {
+                let computed = compute.await;
+                if let Ok(value) = &computed {
+                    match self
+                        .set_fenced(&lease, &key, value, options.fixed_ttl())
+                        .await
+                    {
+                        Ok(true) => {}
+                        Ok(false) => eprintln!(
+                            "Warning: lock on {} expired while computing, value not cached",
+                            key
+                        ),
+                        Err(e) => {
+                            eprintln!("Warning: failed to cache computed value of {}: {}", key, e)
+                        }
+                    }
+                }
+                if let Err(e) = self.unlock(&lease).await {
+                    eprintln!("Warning: failed to release lock on {}: {}", key, e);
+                }
+                computed
+            }
+            Fill::TimedOut => {
+                // Storing the value without the lock could overwrite the one
+                // the lock holder stores, so it is only returned.
+                eprintln!(
+                    "Warning: timed out waiting for {} to be computed elsewhere, computing it",
+                    key
+                );
+                compute.await
+            }
+        }
+    }
+}
+
+/// Outcome of waiting for the single-flight lock of a key.
+enum Fill {
+    /// This process holds the lock and computes the value
+    Locked(Lease),
+    /// Another process computed the value
+    Filled(Vec<u8>),
+    /// Another process held the lock for longer than the wait timeout
+    TimedOut,
+}
+
+/// Streams values in chunks of [`STREAM_CHUNK_SIZE`] bytes.
+///
+/// `put_stream` stages the value in a separate key with `APPEND` and renames
+/// it over the entry once complete; `get_stream` reads the entry with
+/// `GETRANGE`. See the [module documentation](self#streaming).
+#[async_trait]
+impl StreamingBackend for RedisBackend {
+    async fn put_stream<R>(&self, key: Key, mut reader: R, ttl: Option<Duration>) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let namespaced = self.namespaced_key(&key).await?;
+        let redis_key = format!("{}{}", self.key_scope(&namespaced), namespaced);
+        let mut conn = self.pool.primary();
+        let token: u64 = conn
+            .incr(self.fence_key(&namespaced), 1)
+            .await
+            .map_err(Self::convert_redis_error)?;
+        let staging_key = self.staging_key(&namespaced, token);
+
+        let header = encode_entry(&[], Self::system_time_to_timestamp(SystemTime::now()));
+        redis::cmd("SET")
+            .arg(&staging_key)
+            .arg(header)
+            .arg("PX")
+            .arg(STREAM_STAGING_TTL.as_millis() as u64)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)?;
 
-        let result: redis::RedisResult<Option<String>> = conn.get(&redis_key).await;
+        let len = match Self::append_stream(&mut conn, &staging_key, &mut reader).await {
+            Ok(len) => len,
+            Err(e) => {
+                let _: redis::RedisResult<i64> = conn.del(&staging_key).await;
+                return Err(e);
+            }
+        };
 
-        match result {
-            Ok(Some(json_str)) => match serde_json::from_str::<CacheEntry>(&json_str) {
-                Ok(entry) => {
-                    self.metrics.record_hit();
-                    Ok(Some(entry.value))
-                }
-                Err(e) => {
-                    self.metrics.record_miss();
-                    Err(Error::Codec(format!(
-                        "Failed to deserialize cache entry: {}",
-                        e
-                    )))
-                }
-            },
-            Ok(None) => {
+        // A zero TTL means "no TTL" to the script.
+        let ttl_ms = ttl.map_or(0, |ttl| ttl.as_millis().max(1) as u64);
+        let committed = commit_stream_script()
+            .key(&staging_key)
+            .key(&redis_key)
+            .arg(ttl_ms)
+            .invoke_async::<_, bool>(&mut conn)
+            .await
+            .map_err(Self::convert_redis_error)?;
+        if !committed {
+            return Err(Error::Backend(
+                "streamed value was removed before it was complete".into(),
+            ));
+        }
+        self.metrics.record_insertion();
+        Ok(len)
+    }
+
+    async fn get_stream(&self, key: &Key) -> Result<Option<ValueReader>> {
+        let redis_key = self.prefixed_key(key).await?;
+        let mut conn = self.pool.reader();
+
+        let result: redis::RedisResult<(Vec<u8>, u64)> = redis::pipe()
+            .getrange(
+                &redis_key,
+                0,
+                (ENTRY_HEADER_LEN + STREAM_CHUNK_SIZE - 1) as isize,
+            )
+            .strlen(&redis_key)
+            .query_async(&mut conn)
+            .await;
+        let (first, len) = match result {
+            Ok((_, 0)) => {
                 self.metrics.record_miss();
-                Ok(None)
+                return Ok(None);
             }
+            Ok(first) => first,
             Err(e) => {
                 self.metrics.record_miss();
-                Err(Self::convert_redis_error(e))
+                return Err(Self::convert_redis_error(e));
             }
+        };
+        if !first.starts_with(&ENTRY_MAGIC) {
+            // Legacy entries are read whole; they predate streaming.
+            return Ok(self
+                .get(key)
+                .await?
+                .map(|value| Box::pin(io::Cursor::new(value)) as ValueReader));
         }
-    }
 
-    async fn set(&self, key: String, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
-        let redis_key = self.prefixed_key(&key);
-        let mut conn = self.manager.clone();
+        let header = first[..ENTRY_HEADER_LEN.min(first.len())].to_vec();
+        let value = match decode_entry(first) {
+            Ok(value) => value,
+            Err(e) => {
+                self.metrics.record_miss();
+                return Err(e);
+            }
+        };
+        self.metrics.record_hit();
 
-        let entry = CacheEntry {
-            value,
-            created_at: Self::system_time_to_timestamp(SystemTime::now()),
+        let rest = RangeReads {
+            conn,
+            offset: (ENTRY_HEADER_LEN + value.len()) as u64,
+            key: redis_key,
+            header,
+            len,
         };
+        let chunks = stream::once(future::ready(Ok(value)))
+            .chain(stream::try_unfold(rest, RangeReads::next));
+        Ok(Some(chunk_reader(chunks)))
+    }
+}
 
-        let json_str = serde_json::to_string(&entry)
-            .map_err(|e| Error::Codec(format!("Failed to serialize cache entry: {}", e)))?;
+/// The chunks of an entry that [`RedisBackend::get_stream`] has yet to read.
+struct RangeReads {
+    conn: PooledConnection,
+    key: String,
+    /// Header of the entry when its first chunk was read
+    header: Vec<u8>,
+    /// Offset of the next chunk in the Redis value
+    offset: u64,
+    /// Length of the Redis value when its first chunk was read
+    len: u64,
+}
 
-        let result: redis::RedisResult<()> = match ttl {
-            Some(duration) => {
-                let ttl_secs = Self::duration_to_ttl_secs(duration);
-                conn.set_ex(redis_key, json_str, ttl_secs as usize).await
-            }
-            None => conn.set(redis_key, json_str).await,
-        };
+impl RangeReads {
+    /// Reads the next chunk, failing if the entry was replaced or removed
+    /// since the first one was read.
+    async fn next(mut self) -> Result<Option<(Vec<u8>, Self)>> {
+        if self.offset >= self.len {
+            return Ok(None);
+        }
 
-        match result {
-            Ok(_) => {
-                self.metrics.record_insertion();
-                Ok(())
-            }
-            Err(e) => Err(Self::convert_redis_error(e)),
+        // The header and length are read after the chunk, so a replacement
+        // before or while the chunk is read shows in them.
+        let end = self.offset + STREAM_CHUNK_SIZE as u64 - 1;
+        let (chunk, header, len): (Vec<u8>, Vec<u8>, u64) = redis::pipe()
+            .getrange(&self.key, self.offset as isize, end as isize)
+            .getrange(&self.key, 0, ENTRY_HEADER_LEN as isize - 1)
+            .strlen(&self.key)
+            .query_async(&mut self.conn)
+            .await
+            .map_err(RedisBackend::convert_redis_error)?;
+        if header != self.header || len != self.len || chunk.is_empty() {
+            return Err(Error::Backend(
+                "cache entry changed while it was streamed".into(),
+            ));
         }
+
+        self.offset += chunk.len() as u64;
+        Ok(Some((chunk, self)))
     }
+}
 
-    async fn remove(&self, key: &String) -> Result<()> {
-        let redis_key = self.prefixed_key(key);
-        let mut conn = self.manager.clone();
+/// Implementation of tag and prefix invalidation for RedisBackend
+///
+/// The tag index lives in Redis, so invalidating a tag or a prefix removes
+/// the keys written by every process sharing the prefix, and the index
+/// survives restarts. Tag invalidation is atomic, per hash tag bucket on a
+/// Redis Cluster.
+#[async_trait]
+impl AsyncCacheInvalidation for RedisBackend {
+    /// The index lives in Redis and cannot be read synchronously, so this
+    /// always returns an empty set. Use [`RedisBackend::keys_by_tag`]
+    /// instead.
+    fn get_keys_by_tag(&self, _tag: &Tag) -> HashSet<String> {
+        HashSet::new()
+    }
 
-        let result: redis::RedisResult<i64> = conn.del(redis_key).await;
+    /// Keys are not tracked locally, so this always returns an empty set.
+    /// [`invalidate_prefix`](AsyncCacheInvalidation::invalidate_prefix)
+    /// scans Redis instead.
+    fn get_keys_by_prefix(&self, _prefix: &str) -> HashSet<String> {
+        HashSet::new()
+    }
 
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) => Err(Self::convert_redis_error(e)),
+    /// Runs the invalidation script once per hash tag bucket in a cluster,
+    /// so each run only touches the keys of one slot.
+    async fn invalidate_tag(&self, tag: &Tag) -> Result<()> {
+        let namespaced_tag = self.namespaced_key(tag.as_str()).await?;
+        let mut conn = self.pool.primary();
+        for scope in self.key_scopes() {
+            invalidate_tag_script()
+                .key(Self::tag_set_key(&scope, &namespaced_tag))
+                .invoke_async::<_, i64>(&mut conn)
+                .await
+                .map_err(Self::convert_redis_error)?;
         }
+        Ok(())
     }
 
-    async fn contains_key(&self, key: &String) -> Result<bool> {
-        let redis_key = self.prefixed_key(key);
-        let mut conn = self.manager.clone();
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<()> {
+        let start = self.namespaced_key(prefix).await?;
+        self.unlink_by_scan(&start).await
+    }
+}
 
-        let result: redis::RedisResult<bool> = conn.exists(redis_key).await;
+/// Stores a value and adds it to its tag sets.
+///
+/// `KEYS[1]` is the value key and the remaining keys are tag sets.
+/// `ARGV[1]` is the entry and `ARGV[2]` the TTL in milliseconds, 0 for none.
+/// Each tag set is kept alive for as long as its longest-lived member.
+fn set_with_tags_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+local ttl = tonumber(ARGV[2])
+if ttl > 0 then
+    redis.call('SET', KEYS[1], ARGV[1], 'PX', ttl)
+else
+    redis.call('SET', KEYS[1], ARGV[1])
+end
+for i = 2, #KEYS do
+    local remaining = redis.call('PTTL', KEYS[i])
+    redis.call('SADD', KEYS[i], KEYS[1])
+    if ttl == 0 then
+        if remaining >= 0 then
+            redis.call('PERSIST', KEYS[i])
+        end
+    elseif remaining == -2 or (remaining >= 0 and remaining < ttl) then
+        redis.call('PEXPIRE', KEYS[i], ttl)
+    end
+end
+",
+        )
+    })
+}
 
-        match result {
-            Ok(exists) => Ok(exists),
-            Err(e) => Err(Self::convert_redis_error(e)),
-        }
-    }
+/// Renames the staged streamed value `KEYS[1]` over the entry `KEYS[2]` and
+/// gives it the TTL `ARGV[1]` in milliseconds, 0 for none. Returns whether the
+/// staged value still existed.
+fn commit_stream_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+redis.call('RENAME', KEYS[1], KEYS[2])
+local ttl = tonumber(ARGV[1])
+if ttl > 0 then
+    redis.call('PEXPIRE', KEYS[2], ttl)
+else
+    redis.call('PERSIST', KEYS[2])
+end
+return 1
+",
+        )
+    })
+}
 
-    async fn clear(&self) -> Result<()> {
-        let mut conn = self.manager.clone();
+/// Takes the lock `KEYS[1]` for `ARGV[1]` milliseconds unless it is held,
+/// returning the new fencing token from the counter `KEYS[2]`, or nil.
+///
+/// The counter is only incremented when the lock is taken.
+fn try_lock_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+if redis.call('EXISTS', KEYS[1]) == 1 then
+    return false
+end
+local token = redis.call('INCR', KEYS[2])
+redis.call('SET', KEYS[1], token, 'PX', ARGV[1])
+return token
+",
+        )
+    })
+}
 
-        let pattern = format!("{}*", self.prefix);
-        let keys: redis::RedisResult<Vec<String>> = redis::cmd("KEYS")
-            .arg(&pattern)
-            .query_async(&mut conn)
-            .await;
+/// Deletes the lock `KEYS[1]` if it still holds the token `ARGV[1]`.
+fn unlock_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+",
+        )
+    })
+}
 
-        match keys {
-            Ok(keys) => {
-                if !keys.is_empty() {
-                    let result: redis::RedisResult<()> =
-                        redis::cmd("DEL").arg(keys).query_async(&mut conn).await;
+/// Stores the entry `ARGV[2]` at `KEYS[2]`, with a TTL of `ARGV[3]`
+/// milliseconds unless 0, if the lock `KEYS[1]` still holds the token
+/// `ARGV[1]`.
+fn set_fenced_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+local ttl = tonumber(ARGV[3])
+if ttl > 0 then
+    redis.call('SET', KEYS[2], ARGV[2], 'PX', ttl)
+else
+    redis.call('SET', KEYS[2], ARGV[2])
+end
+return 1
+",
+        )
+    })
+}
 
-                    match result {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(Self::convert_redis_error(e)),
-                    }
-                } else {
-                    Ok(())
-                }
-            }
-            Err(e) => Err(Self::convert_redis_error(e)),
+/// Deletes the members of the tag set `KEYS[1]` and the set itself,
+/// returning the number of members.
+fn invalidate_tag_script() -> &'static Script {
+    static SCRIPT: OnceLock<Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        Script::new(
+            r"
+local members = redis.call('SMEMBERS', KEYS[1])
+for i = 1, #members, 1000 do
+    redis.call('UNLINK', unpack(members, i, math.min(i + 999, #members)))
+end
+redis.call('UNLINK', KEYS[1])
+return #members
+",
+        )
+    })
+}
+
+/// Escapes the glob characters of `MATCH` patterns in `s`.
+fn escape_glob(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
         }
+        escaped.push(c);
     }
+    escaped
 }
 
 #[cfg(test)]
@@ -307,6 +1861,114 @@ mod tests {
         RedisBackend::new("redis://127.0.0.1:6379", Some("test:")).await
     }
 
+    #[test]
+    fn test_entry_round_trip() {
+        let value = vec![0, 1, 2, 255];
+        let entry = encode_entry(&value, 1_700_000_000);
+        assert_eq!(entry.len(), ENTRY_HEADER_LEN + value.len());
+        assert_eq!(decode_entry(entry).unwrap(), value);
+
+        assert_eq!(
+            decode_entry(encode_entry(&[], 0)).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_hash_tagged_keys_share_the_slot_of_their_bucket() {
+        let keys: Vec<String> = (0..100)
+            .map(|i| hash_tagged_key("app:", 4, &format!("user:{}", i)))
+            .collect();
+
+        let mut buckets = std::collections::HashSet::new();
+        for key in &keys {
+            let close = key.find('}').unwrap();
+            let tag = &key[..=close];
+            assert!(tag.starts_with("{app:"));
+            assert_eq!(get_slot(key.as_bytes()), get_slot(tag.as_bytes()));
+            buckets.insert(tag.to_string());
+        }
+        assert_eq!(buckets.len(), 4);
+        assert_eq!(keys[7], hash_tagged_key("app:", 4, "user:7"));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(RedisBackendConfig::new("redis://127.0.0.1")
+            .validate()
+            .is_ok());
+
+        let invalid = [
+            RedisBackendConfig::new("redis://127.0.0.1").with_pool_size(0),
+            RedisBackendConfig::new("redis://127.0.0.1").with_read_from_replicas(true),
+            RedisBackendConfig::cluster(["redis://127.0.0.1:7000"]).with_prefix("{app}:"),
+            RedisBackendConfig::cluster(["redis://127.0.0.1:7000"]).with_hash_tag_buckets(0),
+            RedisBackendConfig::cluster(Vec::<String>::new()),
+            RedisBackendConfig::sentinel(Vec::<String>::new(), "mymaster"),
+        ];
+        for config in invalid {
+            assert!(
+                matches!(config.validate(), Err(Error::Config(_))),
+                "{:?} should be rejected",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape_glob() {
+        assert_eq!(escape_glob("app:"), "app:");
+        assert_eq!(escape_glob("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+
+    #[test]
+    fn test_decode_legacy_json_entry() {
+        let legacy = br#"{"value":[104,105],"created_at":1700000000}"#.to_vec();
+        assert_eq!(decode_entry(legacy).unwrap(), b"hi".to_vec());
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_entries() {
+        let mut future = encode_entry(b"value", 0);
+        future[3] = ENTRY_VERSION + 1;
+        assert!(matches!(decode_entry(future), Err(Error::Codec(_))));
+
+        let truncated = encode_entry(b"", 0)[..6].to_vec();
+        assert!(matches!(decode_entry(truncated), Err(Error::Codec(_))));
+
+        assert!(matches!(
+            decode_entry(b"not an entry".to_vec()),
+            Err(Error::Codec(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_reads_legacy_entries_and_rewrites_them_as_binary() -> Result<()> {
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        let key = "test_legacy".to_string();
+        let mut conn = backend.pool.primary();
+        let legacy = r#"{"value":[104,105],"created_at":1700000000}"#;
+        let _: () = conn
+            .set(backend.prefixed_key(&key).await?, legacy)
+            .await
+            .map_err(RedisBackend::convert_redis_error)?;
+        assert_eq!(backend.get(&key).await?, Some(b"hi".to_vec()));
+
+        backend.set(key.clone(), b"hi".to_vec(), None).await?;
+        let stored: Vec<u8> = conn
+            .get(backend.prefixed_key(&key).await?)
+            .await
+            .map_err(RedisBackend::convert_redis_error)?;
+        assert!(stored.starts_with(&ENTRY_MAGIC));
+        assert_eq!(stored.len(), ENTRY_HEADER_LEN + 2);
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     #[ignore]
@@ -406,4 +2068,353 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_clear_scans_in_batches() -> Result<()> {
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        for i in 0..(SCAN_BATCH * 3) {
+            backend.set(format!("batch_{}", i), vec![1], None).await?;
+        }
+        backend.clear().await?;
+
+        for i in 0..(SCAN_BATCH * 3) {
+            assert!(!backend.contains_key(&format!("batch_{}", i)).await?);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_generation_namespace_clear() -> Result<()> {
+        let a = create_test_backend()
+            .await?
+            .with_generation_namespace(Duration::ZERO);
+        let b = create_test_backend()
+            .await?
+            .with_generation_namespace(Duration::from_secs(60));
+
+        let key = "test_generation".to_string();
+        a.set(
+            key.clone(),
+            b"value".to_vec(),
+            Some(Duration::from_secs(60)),
+        )
+        .await?;
+        assert!(b.contains_key(&key).await?);
+
+        b.clear().await?;
+        assert!(!a.contains_key(&key).await?);
+        assert_eq!(a.get(&key).await?, None);
+
+        // `b` cached the generation it bumped, so it keeps working.
+        b.set(key.clone(), b"new".to_vec(), Some(Duration::from_secs(60)))
+            .await?;
+        assert_eq!(a.get(&key).await?, Some(b"new".to_vec()));
+        Ok(())
+    }
+
+    /// Expects the cluster started by `utils/create-cluster` in the Redis
+    /// source tree, with primaries on ports 30001 to 30003.
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_cluster() -> Result<()> {
+        let config = RedisBackendConfig::cluster([
+            "redis://127.0.0.1:30001",
+            "redis://127.0.0.1:30002",
+            "redis://127.0.0.1:30003",
+        ])
+        .with_prefix("test:")
+        .with_pool_size(2);
+        let backend = RedisBackend::with_config(config).await?;
+        backend.clear().await?;
+
+        for i in 0..100 {
+            backend.set(format!("key_{}", i), vec![i], None).await?;
+        }
+        assert_eq!(backend.get(&"key_42".to_string()).await?, Some(vec![42]));
+
+        backend.clear().await?;
+        for i in 0..100 {
+            assert!(!backend.contains_key(&format!("key_{}", i)).await?);
+        }
+
+        // Tagged keys land in every bucket; each bucket keeps its own set.
+        let tag = Tag::new("cluster");
+        let mut expected = HashSet::new();
+        for i in 0..100 {
+            let key = format!("tagged_{}", i);
+            backend
+                .set_with_tags(key.clone(), vec![i], None, [tag.clone()])
+                .await?;
+            expected.insert(key);
+        }
+        assert_eq!(backend.keys_by_tag(&tag).await?, expected);
+
+        backend.invalidate_tag(&tag).await?;
+        assert!(!backend.contains_key(&"tagged_42".to_string()).await?);
+        assert!(backend.keys_by_tag(&tag).await?.is_empty());
+        Ok(())
+    }
+
+    /// Expects a sentinel on port 26379 monitoring a primary as `mymaster`.
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_sentinel() -> Result<()> {
+        let backend =
+            RedisBackend::sentinel(&["redis://127.0.0.1:26379"], "mymaster", Some("test:")).await?;
+        backend.clear().await?;
+
+        let key = "test_sentinel".to_string();
+        backend.set(key.clone(), b"value".to_vec(), None).await?;
+        assert_eq!(backend.get(&key).await?, Some(b"value".to_vec()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_tag_invalidation_is_shared_between_processes() -> Result<()> {
+        let a = create_test_backend().await?;
+        let b = create_test_backend().await?;
+        a.clear().await?;
+
+        let tag = Tag::new("user:1");
+        a.set_with_tags("profile".to_string(), vec![1], None, [tag.clone()])
+            .await?;
+        a.set_with_tags(
+            "settings".to_string(),
+            vec![2],
+            Some(Duration::from_secs(60)),
+            [tag.clone(), Tag::new("settings")],
+        )
+        .await?;
+        a.set("unrelated".to_string(), vec![3], None).await?;
+
+        let expected: HashSet<String> = ["profile", "settings"].map(String::from).into();
+        assert_eq!(b.keys_by_tag(&tag).await?, expected);
+
+        b.invalidate_tag(&tag).await?;
+        assert!(!a.contains_key(&"profile".to_string()).await?);
+        assert!(!a.contains_key(&"settings".to_string()).await?);
+        assert!(a.contains_key(&"unrelated".to_string()).await?);
+        assert!(a.keys_by_tag(&tag).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_tag_set_expires_with_its_longest_lived_member() -> Result<()> {
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        let tag = Tag::new("expiring");
+        for (key, ttl) in [("short", 1), ("long", 60), ("shorter", 1)] {
+            backend
+                .set_with_tags(
+                    key.to_string(),
+                    vec![1],
+                    Some(Duration::from_secs(ttl)),
+                    [tag.clone()],
+                )
+                .await?;
+        }
+
+        let mut conn = backend.pool.primary();
+        let tag_set = RedisBackend::tag_set_key(&backend.prefix, tag.as_str());
+        let remaining: i64 = conn
+            .pttl(tag_set)
+            .await
+            .map_err(RedisBackend::convert_redis_error)?;
+        assert!(remaining > 30_000, "remaining TTL was {}ms", remaining);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_invalidate_prefix() -> Result<()> {
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        for key in ["user:1:a", "user:1:b", "user:2:a"] {
+            backend.set(key.to_string(), vec![1], None).await?;
+        }
+        backend.invalidate_prefix("user:1:").await?;
+
+        assert!(!backend.contains_key(&"user:1:a".to_string()).await?);
+        assert!(!backend.contains_key(&"user:1:b".to_string()).await?);
+        assert!(backend.contains_key(&"user:2:a".to_string()).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_get_or_compute_computes_once_across_backends() -> Result<()> {
+        use std::sync::atomic::AtomicUsize;
+
+        create_test_backend().await?.clear().await?;
+        let computations = Arc::new(AtomicUsize::new(0));
+        let mut tasks = Vec::new();
+        for _ in 0..8 {
+            // A backend per task stands in for a process per node.
+            let backend = create_test_backend().await?;
+            let computations = Arc::clone(&computations);
+            tasks.push(tokio::spawn(async move {
+                backend
+                    .get_or_compute(
+                        "test_single_flight".to_string(),
+                        SetOptions::new().with_ttl(Duration::from_secs(60)),
+                        Box::pin(async move {
+                            computations.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                            Ok(b"computed".to_vec())
+                        }),
+                    )
+                    .await
+            }));
+        }
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap()?, b"computed".to_vec());
+        }
+        assert_eq!(computations.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_expired_lease_cannot_write() -> Result<()> {
+        let config = RedisBackendConfig::new("redis://127.0.0.1:6379")
+            .with_prefix("test:")
+            .with_lock_lease(Duration::from_millis(100));
+        let backend = RedisBackend::with_config(config).await?;
+        backend.clear().await?;
+
+        let key = "test_fencing";
+        let stale = backend.try_lock(key).await?.unwrap();
+        assert!(backend.try_lock(key).await?.is_none());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let current = backend.try_lock(key).await?.unwrap();
+        // Failed attempts do not consume tokens.
+        assert_eq!(current.token(), stale.token() + 1);
+
+        assert!(!backend.set_fenced(&stale, key, b"stale", None).await?);
+        assert!(backend.set_fenced(&current, key, b"fresh", None).await?);
+        assert!(!backend.unlock(&stale).await?);
+        assert!(backend.unlock(&current).await?);
+        assert_eq!(
+            backend.get(&key.to_string()).await?,
+            Some(b"fresh".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_value_computed_after_a_timed_out_wait_is_not_stored() -> Result<()> {
+        let config = RedisBackendConfig::new("redis://127.0.0.1:6379")
+            .with_prefix("test:")
+            .with_lock_wait_timeout(Duration::from_millis(50));
+        let backend = RedisBackend::with_config(config).await?;
+        backend.clear().await?;
+
+        let key = "test_timed_out_wait";
+        let holder = backend.try_lock(key).await?.unwrap();
+        let value = backend
+            .get_or_compute(
+                key.to_string(),
+                SetOptions::new(),
+                Box::pin(async { Ok(b"unfenced".to_vec()) }),
+            )
+            .await?;
+        assert_eq!(value, b"unfenced".to_vec());
+        assert_eq!(backend.get(&key.to_string()).await?, None);
+
+        assert!(backend.set_fenced(&holder, key, b"fenced", None).await?);
+        assert_eq!(
+            backend.get(&key.to_string()).await?,
+            Some(b"fenced".to_vec())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_put_stream_and_get_stream() -> Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        let key = "test_stream".to_string();
+        let value: Vec<u8> = (0..STREAM_CHUNK_SIZE * 5 / 2).map(|i| i as u8).collect();
+        let len = backend
+            .put_stream(key.clone(), value.as_slice(), Some(Duration::from_secs(60)))
+            .await?;
+        assert_eq!(len, value.len() as u64);
+        assert_eq!(backend.get(&key).await?, Some(value.clone()));
+
+        let mut streamed = Vec::new();
+        backend
+            .get_stream(&key)
+            .await?
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .await?;
+        assert_eq!(streamed, value);
+
+        backend
+            .set("small".to_string(), b"value".to_vec(), None)
+            .await?;
+        let mut small = Vec::new();
+        backend
+            .get_stream(&"small".to_string())
+            .await?
+            .unwrap()
+            .read_to_end(&mut small)
+            .await?;
+        assert_eq!(small, b"value");
+        assert!(backend.get_stream(&"missing".to_string()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_get_stream_fails_when_the_entry_is_replaced() -> Result<()> {
+        use tokio::io::AsyncReadExt;
+
+        let backend = create_test_backend().await?;
+        backend.clear().await?;
+
+        let key = "test_stream_replaced".to_string();
+        let value = vec![1; STREAM_CHUNK_SIZE * 2];
+        backend
+            .put_stream(key.clone(), value.as_slice(), None)
+            .await?;
+
+        let mut reader = backend.get_stream(&key).await?.unwrap();
+        let mut first = vec![0; STREAM_CHUNK_SIZE];
+        reader.read_exact(&mut first).await?;
+        backend.set(key.clone(), vec![2; 10], None).await?;
+
+        let mut rest = Vec::new();
+        assert!(reader.read_to_end(&mut rest).await.is_err());
+
+        Ok(())
+    }
 }
@@ -45,35 +45,389 @@
 //!
 //! # Implementation Details
 //!
-//! * Cache entries are serialized using bincode for efficient binary storage
-//! * TTL is implemented by storing expiration timestamps with each entry
-//! * Expired entries are cleaned up when accessed
+//! * Cache entries are stored behind a fixed-width binary header holding their
+//!   expiration time (see [Storage Format](#storage-format))
+//! * Expired entries are removed when they are read and dropped by RocksDB
+//!   compactions (see [Expiration](#expiration))
 //! * Key-value pairs are stored directly in RocksDB's native format
-//! * The clear operation iterates through all keys for deletion
+//! * Keys can be split into namespaces stored in column families of their own
+//!   (see [Namespaces](#namespaces))
+//! * The clear operation drops the namespaces and deletes the rest of the keys
+//!   with a single range tombstone
+//!
+//! # Expiration
+//!
+//! The backend installs a compaction filter that drops expired entries and
+//! chunks whenever RocksDB compacts the files holding them, so expired data
+//! does not stay on disk just because it is never read again. Compactions
+//! are triggered by writes; for a cache that is mostly read,
+//! [`RocksDBBackend::spawn_janitor`] also compacts the whole key range on an
+//! interval. Reads check the expiration time in the header of an entry
+//! without decoding the rest of it.
+//!
+//! # Namespaces
+//!
+//! With [`RocksDBConfig::with_namespace_separator`], the text of a key before
+//! the first separator names its namespace, and each namespace is stored in a
+//! column family of its own, created on its first write. Keys without a
+//! namespace stay in the default column family. Dropping a namespace with
+//! [`RocksDBBackend::drop_namespace`] drops its column family at once instead
+//! of deleting its keys one by one. Every column family is opened along with
+//! the database, so namespaces suit a bounded set of key groups, such as the
+//! functions of an application, rather than one per user or request. Choose
+//! the separator when the database is created: entries stored under another
+//! setting are not found, although `clear` still removes them.
+//!
+//! `RocksDBBackend` implements [`AsyncCacheInvalidation`].
+//! `invalidate_prefix` drops the namespaces all of whose keys start with the
+//! prefix and deletes the matching keys of the other column families with a
+//! range tombstone, reading only the keys in the range through a bounded
+//! iterator to find the chunks of streamed values. Prefixes may be of any
+//! length, so the column families have no prefix extractor; the iterator's
+//! bounds skip the table files outside the range instead. Tags are not
+//! recorded, so `invalidate_tag` removes nothing.
+//!
+//! # Storage Format
+//!
+//! Each entry is stored under its key as:
+//!
+//! | Bytes  | Content                                                        |
+//! |--------|----------------------------------------------------------------|
+//! | 0..4   | Magic `'f' 'n' 'r' 0xFF`                                       |
+//! | 4      | Format version, currently `1`                                  |
+//! | 5      | `0` if the value follows, `1` if it is stored in chunks         |
+//! | 6..14  | Expiry in milliseconds since the Unix epoch, 0 = never, big-endian |
+//! | 14..   | The value bytes, or the chunk id (`u64`) and count (`u32`), big-endian |
+//!
+//! Earlier releases stored entries encoded with bincode. Those entries are
+//! still read, and are replaced by the binary format on their next write.
+//!
+//! The database also records the format version under a metadata key that
+//! sorts after every cache key. Opening a database recorded in another
+//! version fails with [`Error::Config`].
+//!
+//! # Streaming
+//!
+//! `RocksDBBackend` implements [`StreamingBackend`]. `put_stream` stores the
+//! value in chunks of [`STREAM_CHUNK_SIZE`] bytes under keys of their own,
+//! starting with the byte `0xFF`, which no UTF-8 cache key starts with, and
+//! then writes the entry pointing at them in the same batch that deletes the
+//! chunks of the value it replaces. Writes of one key are serialized, so that
+//! concurrent uploads to a key never leave the chunks of a replaced value
+//! behind. Each chunk starts with the expiry of its entry, so the compaction
+//! filter drops the chunks along with the entry. Chunks that no entry points
+//! at, left behind by an upload that crashed or was abandoned, are deleted by
+//! [`RocksDBBackend::compact`], and so by the janitor, whether or not they
+//! have an expiry. `get_stream` reads one chunk at a time; `get` reassembles
+//! the whole value.
+//!
+//! # Checkpoints
+//!
+//! [`RocksDBBackend::checkpoint`] writes a consistent snapshot of the cache,
+//! namespaces included, to a new directory, so that a batch job can warm a
+//! cache once and ship it to fresh instances, which then start hot.
+//! [`RocksDBBackend::open_from_checkpoint`] checks a shipped checkpoint
+//! before using it: a checkpoint in another entry format is refused, and one
+//! whose entries do not decode or whose streamed values miss chunks is
+//! reported as inconsistent. It then copies the checkpoint into the cache
+//! directory and opens it there, leaving the checkpoint untouched.
+//!
+//! ```rust,no_run
+//! use fncache::backends::rocksdb::{RocksDBBackend, RocksDBConfig};
+//!
+//! # async fn run() -> fncache::Result<()> {
+//! // In the batch job
+//! let warm = RocksDBBackend::new("/var/cache/warmer/rocksdb")?;
+//! // ... fill the cache ...
+//! warm.checkpoint("/var/cache/warmer/checkpoint").await?;
+//!
+//! // On a fresh instance, once the checkpoint was shipped
+//! let config = RocksDBConfig::new("/var/cache/app/rocksdb");
+//! let backend = RocksDBBackend::open_from_checkpoint("/mnt/shipped/checkpoint", config)?;
+//! # Ok(())
+//! # }
+//! ```
 
-use crate::{backends::CacheBackend, error::Error, metrics::Metrics, Result};
+use crate::{
+    backends::{
+        chunk_reader, read_chunk, CacheBackend, Key, StreamingBackend, ValueReader,
+        STREAM_CHUNK_SIZE,
+    },
+    error::Error,
+    invalidation::{AsyncCacheInvalidation, Tag},
+    metrics::Metrics,
+    Result,
+};
 use async_trait::async_trait;
-use rocksdb::{Options, DB};
-use serde::{Deserialize, Serialize};
+use futures::{stream, StreamExt};
+use rocksdb::{
+    checkpoint::Checkpoint, compaction_filter::Decision, AsColumnFamilyRef, BlockBasedOptions,
+    BoundColumnFamily, Cache, ColumnFamilyDescriptor, DBCompressionType, DBWithThreadMode,
+    IteratorMode, MultiThreaded, Options, ReadOptions, WriteBatch, WriteOptions,
+    DEFAULT_COLUMN_FAMILY_NAME,
+};
+use serde::Deserialize;
 use std::{
-    path::Path,
-    sync::Arc,
-    time::{Duration, SystemTime},
+    collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use tokio::io::AsyncRead;
+
+/// Magic bytes at the start of every entry in the binary format.
+///
+/// Read as the little-endian length that starts a bincode entry of earlier
+/// releases, these bytes would announce a value of more than 4 GiB, so no
+/// such entry starts with them.
+const ENTRY_MAGIC: [u8; 4] = [b'f', b'n', b'r', 0xFF];
+
+/// Current version of the binary entry format.
+const ENTRY_VERSION: u8 = 1;
+
+/// Length of the binary entry header: magic, version, kind and expiry.
+const ENTRY_HEADER_LEN: usize = 14;
+
+/// Entry kind of a value stored in the entry itself.
+const KIND_INLINE: u8 = 0;
+
+/// Entry kind of a value stored in chunks.
+const KIND_CHUNKED: u8 = 1;
+
+/// Start of the keys that hold the chunks of streamed values.
+///
+/// `0xFF` never occurs in UTF-8, so these keys cannot clash with cache keys.
+const CHUNK_KEY_PREFIX: &[u8] = b"\xFFchunk:";
+
+/// Length of the expiry that starts every chunk.
+const CHUNK_HEADER_LEN: usize = 8;
+
+/// Exclusive upper bound of every cache and chunk key: cache keys are UTF-8
+/// and chunk keys continue `0xFF` with a letter. Metadata keys start here, so
+/// `clear` and prefix deletions never reach them.
+const KEYSPACE_END: &[u8] = b"\xFF\xFF";
+
+/// Key of the entry format version the database is written in, in the
+/// default column family.
+const FORMAT_VERSION_KEY: &[u8] = b"\xFF\xFFformat-version";
+
+/// Name the compaction filter is registered under.
+const COMPACTION_FILTER_NAME: &str = "fncache_ttl";
+
+/// Start of the column family names of namespaces, which keeps them apart
+/// from the default column family.
+const NAMESPACE_CF_PREFIX: &str = "ns:";
+
+/// Number of locks that writes of keys are striped over.
+const KEY_LOCK_STRIPES: usize = 64;
+
+/// RocksDB handle; column families are created and dropped through a shared
+/// reference.
+type Db = DBWithThreadMode<MultiThreaded>;
 
 /// Entry stored in the RocksDB cache
 ///
-/// This structure represents a single cache entry that's serialized using bincode
-/// and stored in RocksDB. It contains both the value bytes and optional expiration time.
-#[derive(Debug, Serialize, Deserialize)]
+/// Entries are stored in the binary format described in the
+/// [module documentation](self#storage-format).
+#[derive(Debug)]
 struct CacheEntry {
-    /// The cached value as bytes
+    /// The cached value as bytes, empty if it is stored in chunks
+    value: Vec<u8>,
+    /// Expiry in milliseconds since the Unix epoch, 0 if the entry never expires
+    expires_at: u64,
+    /// Where the value is stored instead, if it was streamed in chunks
+    chunks: Option<Chunks>,
+}
+
+impl CacheEntry {
+    fn is_expired(&self, now: u64) -> bool {
+        is_expired(self.expires_at, now)
+    }
+}
+
+/// Entry stored by earlier releases, encoded with bincode.
+///
+/// Only read, to migrate existing data; new entries use the binary format.
+#[derive(Debug, Deserialize)]
+struct LegacyCacheEntry {
     value: Vec<u8>,
-    /// When the entry expires (if ever)
-    /// If None, the entry never expires
     expires_at: Option<SystemTime>,
 }
 
+/// The chunks a streamed value is stored in.
+#[derive(Debug, Clone, Copy)]
+struct Chunks {
+    /// Identifies the chunks of this value among those of other values
+    id: u64,
+    /// Number of chunks
+    count: u32,
+}
+
+/// Encodes an entry in the binary format.
+fn encode_entry(entry: &CacheEntry) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(ENTRY_HEADER_LEN + entry.value.len().max(12));
+    bytes.extend_from_slice(&ENTRY_MAGIC);
+    bytes.push(ENTRY_VERSION);
+    match entry.chunks {
+        None => {
+            bytes.push(KIND_INLINE);
+            bytes.extend_from_slice(&entry.expires_at.to_be_bytes());
+            bytes.extend_from_slice(&entry.value);
+        }
+        Some(chunks) => {
+            bytes.push(KIND_CHUNKED);
+            bytes.extend_from_slice(&entry.expires_at.to_be_bytes());
+            bytes.extend_from_slice(&chunks.id.to_be_bytes());
+            bytes.extend_from_slice(&chunks.count.to_be_bytes());
+        }
+    }
+    bytes
+}
+
+/// Decodes an entry in the binary format, or in the bincode format of
+/// earlier releases.
+fn decode_entry(mut bytes: Vec<u8>) -> Result<CacheEntry> {
+    if !bytes.starts_with(&ENTRY_MAGIC) {
+        return bincode::deserialize::<LegacyCacheEntry>(&bytes)
+            .map(|entry| CacheEntry {
+                value: entry.value,
+                expires_at: entry.expires_at.map_or(0, |time| to_millis(time).max(1)),
+                chunks: None,
+            })
+            .map_err(|e| Error::Codec(format!("Failed to deserialize cache entry: {}", e)));
+    }
+
+    let expires_at = header_expiry(&bytes)?;
+    match bytes[5] {
+        KIND_INLINE => {
+            bytes.drain(..ENTRY_HEADER_LEN);
+            Ok(CacheEntry {
+                value: bytes,
+                expires_at,
+                chunks: None,
+            })
+        }
+        KIND_CHUNKED if bytes.len() == ENTRY_HEADER_LEN + 12 => Ok(CacheEntry {
+            value: Vec::new(),
+            expires_at,
+            chunks: Some(Chunks {
+                id: u64::from_be_bytes(bytes[14..22].try_into().expect("8 bytes")),
+                count: u32::from_be_bytes(bytes[22..26].try_into().expect("4 bytes")),
+            }),
+        }),
+        kind => Err(Error::Codec(format!(
+            "Invalid cache entry kind {} of length {}",
+            kind,
+            bytes.len()
+        ))),
+    }
+}
+
+/// Reads the expiry from the header of an entry in the binary format.
+fn header_expiry(bytes: &[u8]) -> Result<u64> {
+    match bytes.get(ENTRY_MAGIC.len()) {
+        Some(&ENTRY_VERSION) if bytes.len() >= ENTRY_HEADER_LEN => Ok(u64::from_be_bytes(
+            bytes[6..ENTRY_HEADER_LEN].try_into().expect("8 bytes"),
+        )),
+        Some(&ENTRY_VERSION) => Err(Error::Codec("Truncated cache entry header".into())),
+        version => Err(Error::Codec(format!(
+            "Unsupported cache entry version: {:?}",
+            version
+        ))),
+    }
+}
+
+/// Returns the expiry of the entry stored as `bytes`, reading only the header
+/// unless it is an entry of an earlier release.
+fn entry_expiry(bytes: &[u8]) -> Result<u64> {
+    if bytes.starts_with(&ENTRY_MAGIC) {
+        header_expiry(bytes)
+    } else {
+        decode_entry(bytes.to_vec()).map(|entry| entry.expires_at)
+    }
+}
+
+/// Decides whether a compaction keeps a record: expired entries and chunks
+/// are removed, everything else, including records it cannot decode, is kept.
+fn compaction_filter(_level: u32, key: &[u8], value: &[u8]) -> Decision {
+    let expires_at = if key.starts_with(KEYSPACE_END) {
+        None
+    } else if key.starts_with(CHUNK_KEY_PREFIX) {
+        value
+            .get(..CHUNK_HEADER_LEN)
+            .map(|header| u64::from_be_bytes(header.try_into().expect("8 bytes")))
+    } else {
+        entry_expiry(value).ok()
+    };
+    match expires_at {
+        Some(expires_at) if is_expired(expires_at, now_millis()) => Decision::Remove,
+        _ => Decision::Keep,
+    }
+}
+
+/// Returns the key of chunk `index` of the value with chunk id `id`.
+fn chunk_key(id: u64, index: u32) -> Vec<u8> {
+    let mut key = CHUNK_KEY_PREFIX.to_vec();
+    key.extend_from_slice(&id.to_be_bytes());
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Returns the chunk id of the chunk key `key`.
+fn chunk_id(key: &[u8]) -> Option<u64> {
+    let id = key.get(CHUNK_KEY_PREFIX.len()..CHUNK_KEY_PREFIX.len() + 8)?;
+    Some(u64::from_be_bytes(id.try_into().ok()?))
+}
+
+/// Returns the smallest key after every key starting with `prefix`, which is
+/// UTF-8 text and so holds no `0xFF` byte.
+fn prefix_end(prefix: &[u8]) -> Vec<u8> {
+    match prefix.split_last() {
+        Some((last, rest)) => {
+            let mut end = rest.to_vec();
+            end.push(last + 1);
+            end
+        }
+        None => KEYSPACE_END.to_vec(),
+    }
+}
+
+/// Returns the name of the column family of `namespace`.
+fn namespace_column_family(namespace: &str) -> String {
+    format!("{}{}", NAMESPACE_CF_PREFIX, namespace)
+}
+
+/// Whether an expiry in milliseconds since the Unix epoch, 0 for never, has
+/// passed at `now`.
+fn is_expired(expires_at: u64, now: u64) -> bool {
+    expires_at != 0 && now > expires_at
+}
+
+/// Returns the expiry of an entry written now with `ttl`, 0 for never.
+fn expires_at(ttl: Option<Duration>) -> u64 {
+    ttl.map_or(0, |ttl| {
+        now_millis()
+            .saturating_add(ttl.as_millis().min(u64::MAX as u128) as u64)
+            .max(1)
+    })
+}
+
+fn to_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64
+}
+
+fn now_millis() -> u64 {
+    to_millis(SystemTime::now())
+}
+
 /// RocksDB-based cache backend for high-performance persistent caching
 ///
 /// This backend stores cache entries in a RocksDB database, providing high-performance
@@ -118,10 +472,199 @@ struct CacheEntry {
 /// ```
 #[derive(Debug)]
 pub struct RocksDBBackend {
-    /// RocksDB database handle
-    db: Arc<DB>,
-    /// Cache metrics
-    metrics: Arc<Metrics>,
+    /// Database and settings shared with the blocking tasks
+    store: Arc<Store>,
+    /// Chunk id of the next streamed value
+    next_chunk_id: AtomicU64,
+    /// Chunk ids of the uploads in progress, whose chunks no entry points at
+    /// yet
+    uploads: Mutex<HashSet<u64>>,
+}
+
+/// An upload in progress, registered until it is dropped.
+struct Upload<'a> {
+    uploads: &'a Mutex<HashSet<u64>>,
+    id: u64,
+}
+
+impl Drop for Upload<'_> {
+    fn drop(&mut self) {
+        self.uploads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+/// Compression of the data blocks, set with [`RocksDBConfig::with_compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RocksDBCompression {
+    /// No compression
+    None,
+    /// Snappy, fast with a moderate ratio
+    #[default]
+    Snappy,
+    /// LZ4, about as fast as Snappy with a slightly better ratio
+    Lz4,
+    /// Zstandard, the best ratio at a higher CPU cost
+    Zstd,
+}
+
+impl From<RocksDBCompression> for DBCompressionType {
+    fn from(compression: RocksDBCompression) -> Self {
+        match compression {
+            RocksDBCompression::None => DBCompressionType::None,
+            RocksDBCompression::Snappy => DBCompressionType::Snappy,
+            RocksDBCompression::Lz4 => DBCompressionType::Lz4,
+            RocksDBCompression::Zstd => DBCompressionType::Zstd,
+        }
+    }
+}
+
+/// Configuration for [`RocksDBBackend::with_config`].
+///
+/// Start from [`RocksDBConfig::new`] and adjust the defaults with the
+/// `with_*` methods. Settings left alone keep the defaults of RocksDB.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use fncache::backends::rocksdb::{RocksDBBackend, RocksDBCompression, RocksDBConfig};
+///
+/// # fn run() -> fncache::Result<()> {
+/// let config = RocksDBConfig::new("/path/to/rocksdb")
+///     .with_block_cache_size(256 * 1024 * 1024)
+///     .with_bloom_filter(10.0)
+///     .with_compression(RocksDBCompression::Lz4)
+///     .with_wal_disabled(true);
+/// let backend = RocksDBBackend::with_config(config)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RocksDBConfig {
+    path: PathBuf,
+    block_cache_size: Option<usize>,
+    bloom_filter_bits: Option<f64>,
+    compression: RocksDBCompression,
+    write_buffer_size: Option<usize>,
+    wal_disabled: bool,
+    read_only: bool,
+    namespace_separator: Option<char>,
+}
+
+impl RocksDBConfig {
+    /// Stores the database in `path`, which is created if it does not exist.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            block_cache_size: None,
+            bloom_filter_bits: None,
+            compression: RocksDBCompression::default(),
+            write_buffer_size: None,
+            wal_disabled: false,
+            read_only: false,
+            namespace_separator: None,
+        }
+    }
+
+    /// Sets the size in bytes of the LRU cache of uncompressed data blocks,
+    /// shared by every namespace. 0 disables the block cache.
+    pub fn with_block_cache_size(mut self, bytes: usize) -> Self {
+        self.block_cache_size = Some(bytes);
+        self
+    }
+
+    /// Adds a Bloom filter with `bits_per_key` bits per key to every table
+    /// file, so that looking up a missing key rarely reads a data block.
+    ///
+    /// 10 bits per key give about 1% false positives.
+    pub fn with_bloom_filter(mut self, bits_per_key: f64) -> Self {
+        self.bloom_filter_bits = Some(bits_per_key);
+        self
+    }
+
+    /// Sets the compression of the data blocks. Defaults to
+    /// [`RocksDBCompression::Snappy`].
+    pub fn with_compression(mut self, compression: RocksDBCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the size in bytes of each memtable, which is written to a table
+    /// file once full.
+    pub fn with_write_buffer_size(mut self, bytes: usize) -> Self {
+        self.write_buffer_size = Some(bytes);
+        self
+    }
+
+    /// Skips the write-ahead log, which makes writes cheaper. Entries not yet
+    /// flushed to a table file are lost on a crash, which a cache can
+    /// usually afford.
+    pub fn with_wal_disabled(mut self, wal_disabled: bool) -> Self {
+        self.wal_disabled = wal_disabled;
+        self
+    }
+
+    /// Opens an existing database read-only, for instance to serve a cache
+    /// that another process writes.
+    ///
+    /// The backend sees the entries as of opening. Writes fail with
+    /// [`Error::Backend`], expired entries are skipped instead of removed and
+    /// nothing is compacted.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Splits keys into namespaces, each stored in a column family of its
+    /// own.
+    ///
+    /// The text of a key before the first `separator` names its namespace;
+    /// keys without a separator, or starting with one, stay in the default
+    /// column family. Keys generated by `#[fncache]` start with the function
+    /// name followed by `-`, so `'-'` gives every cached function a
+    /// namespace. See the [module documentation](self#namespaces).
+    pub fn with_namespace_separator(mut self, separator: char) -> Self {
+        self.namespace_separator = Some(separator);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.write_buffer_size == Some(0) {
+            return Err(Error::Config(
+                "the RocksDB write buffer size must be at least 1 byte".into(),
+            ));
+        }
+        match self.bloom_filter_bits {
+            Some(bits) if !(bits.is_finite() && bits > 0.0) => Err(Error::Config(
+                "the Bloom filter needs a positive number of bits per key".into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the options every column family is opened or created with.
+    fn column_family_options(&self, block_cache: Option<&Cache>) -> Options {
+        let mut options = Options::default();
+        options.set_compaction_filter(COMPACTION_FILTER_NAME, compaction_filter);
+        options.set_compression_type(self.compression.into());
+        if let Some(bytes) = self.write_buffer_size {
+            options.set_write_buffer_size(bytes);
+        }
+
+        let mut table_options = BlockBasedOptions::default();
+        match (self.block_cache_size, block_cache) {
+            (Some(0), _) => table_options.disable_cache(),
+            (_, Some(cache)) => table_options.set_block_cache(cache),
+            _ => {}
+        }
+        if let Some(bits) = self.bloom_filter_bits {
+            table_options.set_bloom_filter(bits, false);
+        }
+        options.set_block_based_table_factory(&table_options);
+        options
+    }
 }
 
 impl RocksDBBackend {
@@ -136,24 +679,1039 @@ impl RocksDBBackend {
     /// # Errors
     /// Returns an error if the RocksDB database could not be opened
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let mut options = Options::default();
-        options.create_if_missing(true);
+        Self::with_config(RocksDBConfig::new(db_path))
+    }
+
+    /// Creates a RocksDBBackend from a [`RocksDBConfig`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Config`] for an invalid configuration and an error
+    /// if the RocksDB database could not be opened
+    pub fn with_config(config: RocksDBConfig) -> Result<Self> {
+        config.validate()?;
+        let block_cache = match config.block_cache_size {
+            Some(bytes) if bytes > 0 => Some(Cache::new_lru_cache(bytes)),
+            _ => None,
+        };
+        let mut options = config.column_family_options(block_cache.as_ref());
+        options.create_if_missing(!config.read_only);
+
+        // RocksDB refuses to open a database without all of its column
+        // families, so open the namespaces of earlier runs too.
+        let mut names = Db::list_cf(&options, &config.path)
+            .unwrap_or_else(|_| vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()]);
+        if !names.iter().any(|name| name == DEFAULT_COLUMN_FAMILY_NAME) {
+            names.push(DEFAULT_COLUMN_FAMILY_NAME.to_string());
+        }
+        let namespaces = names
+            .iter()
+            .filter_map(|name| name.strip_prefix(NAMESPACE_CF_PREFIX))
+            .map(str::to_string)
+            .collect();
+        let descriptors = names.into_iter().map(|name| {
+            ColumnFamilyDescriptor::new(name, config.column_family_options(block_cache.as_ref()))
+        });
+
+        let db = if config.read_only {
+            Db::open_cf_descriptors_read_only(&options, &config.path, descriptors, false)
+        } else {
+            Db::open_cf_descriptors(&options, &config.path, descriptors)
+        }
+        .map_err(|e| Error::Backend(format!("Failed to open RocksDB: {}", e)))?;
+
+        let mut write_options = WriteOptions::default();
+        write_options.disable_wal(config.wal_disabled);
+
+        // Starting from the clock keeps ids unique across restarts without
+        // storing a counter.
+        let first_chunk_id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+
+        let store = Store {
+            db,
+            metrics: Metrics::new(),
+            namespaces: Mutex::new(namespaces),
+            key_locks: (0..KEY_LOCK_STRIPES).map(|_| Mutex::new(())).collect(),
+            write_options,
+            block_cache,
+            config,
+        };
+        store.init_format_version()?;
+
+        Ok(Self {
+            store: Arc::new(store),
+            next_chunk_id: AtomicU64::new(first_chunk_id),
+            uploads: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Writes a consistent snapshot of the cache to `path`, which must not
+    /// exist yet.
+    ///
+    /// The memtables are flushed first, so the checkpoint holds every entry
+    /// even with the write-ahead log disabled. On the same file system, table
+    /// files are hard-linked rather than copied. The checkpoint is a RocksDB
+    /// database of its own; ship it to other hosts and open it with
+    /// [`RocksDBBackend::open_from_checkpoint`]. See the
+    /// [module documentation](self#checkpoints).
+    pub async fn checkpoint(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref().to_path_buf();
+        self.blocking(move |store| store.checkpoint(&path)).await
+    }
+
+    /// Opens a cache restored from a checkpoint made by
+    /// [`RocksDBBackend::checkpoint`], for instance one warmed by a batch job
+    /// and shipped to a fresh instance.
+    ///
+    /// The checkpoint is checked first: it must be in the entry format of
+    /// this release, every entry must decode and every chunk of a streamed
+    /// value must be present. It is then copied to the path of `config`,
+    /// which must not exist or be empty, and opened with `config`; table
+    /// files are hard-linked where possible, since RocksDB never modifies
+    /// them. The files are copied into a sibling directory that is renamed
+    /// into place once complete, and removed again if the copy does not open,
+    /// so a failed restore can be retried. A `config` pointing at the
+    /// checkpoint itself opens it in place. The check reads the whole
+    /// checkpoint and blocks.
+    ///
+    /// # Errors
+    /// Returns [`Error::Config`] for a checkpoint in another entry format or
+    /// a destination that already holds files, and an error if the
+    /// checkpoint is inconsistent or could not be copied or opened
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::rocksdb::{RocksDBBackend, RocksDBConfig};
+    ///
+    /// # fn run() -> fncache::Result<()> {
+    /// let config = RocksDBConfig::new("/var/cache/app/rocksdb");
+    /// let backend = RocksDBBackend::open_from_checkpoint("/mnt/shipped/checkpoint", config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_from_checkpoint(
+        checkpoint: impl AsRef<Path>,
+        config: RocksDBConfig,
+    ) -> Result<Self> {
+        let checkpoint = checkpoint.as_ref();
+        config.validate()?;
+        verify_checkpoint(checkpoint)?;
+        let in_place = match (checkpoint.canonicalize(), config.path.canonicalize()) {
+            (Ok(checkpoint), Ok(path)) => checkpoint == path,
+            _ => false,
+        };
+        if in_place {
+            return Self::with_config(config);
+        }
+
+        copy_checkpoint(checkpoint, &config.path)?;
+        let path = config.path.clone();
+        Self::with_config(config).map_err(|e| {
+            // Leave the destination as it was, so that the restore can be
+            // retried.
+            if let Err(e) = fs::remove_dir_all(&path) {
+                eprintln!("Warning: failed to remove a restored checkpoint: {}", e);
+            }
+            e
+        })
+    }
+
+    /// Returns the namespaces that have a column family, in order.
+    pub fn namespaces(&self) -> Vec<String> {
+        self.store.namespace_set().iter().cloned().collect()
+    }
+
+    /// Removes every entry of `namespace` by dropping its column family.
+    ///
+    /// The column family is created again by the next write to the
+    /// namespace. Dropping a namespace that has no column family does
+    /// nothing. A write made while the namespace is dropped may be lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::rocksdb::{RocksDBBackend, RocksDBConfig};
+    ///
+    /// # async fn run() -> fncache::Result<()> {
+    /// let config = RocksDBConfig::new("/path/to/rocksdb").with_namespace_separator(':');
+    /// let backend = RocksDBBackend::with_config(config)?;
+    ///
+    /// // "user:42" is stored in the column family of the "user" namespace
+    /// // ...
+    ///
+    /// // Removes every "user:" key at once
+    /// backend.drop_namespace("user").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn drop_namespace(&self, namespace: &str) -> Result<()> {
+        let namespace = namespace.to_string();
+        self.blocking(move |store| store.drop_namespace(&namespace))
+            .await
+    }
+
+    /// Compacts the whole key range, dropping expired entries and chunks,
+    /// after deleting the chunks of uploads that never completed.
+    pub async fn compact(&self) -> Result<()> {
+        // Taken together, so that every id below `next_chunk_id` that is not
+        // in progress belongs to an upload that completed or was abandoned.
+        let (uploads, next_chunk_id) = {
+            let uploads = self.uploads.lock().unwrap_or_else(PoisonError::into_inner);
+            (uploads.clone(), self.next_chunk_id.load(Ordering::Relaxed))
+        };
+        self.blocking(move |store| {
+            store.remove_orphaned_chunks(next_chunk_id, &uploads)?;
+            store.compact()
+        })
+        .await
+    }
+
+    /// Spawns a background task that runs [`RocksDBBackend::compact`] on an
+    /// interval.
+    ///
+    /// Compactions triggered by writes already drop expired entries; the
+    /// janitor also reclaims them when the cache is mostly read, along with
+    /// the chunks of abandoned uploads. The first compaction runs after one
+    /// interval. The janitor holds a weak reference to the backend and stops
+    /// on its own once the backend is dropped. Must be called from within a
+    /// tokio runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use fncache::backends::rocksdb::RocksDBBackend;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let backend = Arc::new(RocksDBBackend::new("/tmp/fncache-rocksdb").unwrap());
+    /// let janitor = backend.spawn_janitor(Duration::from_secs(3600));
+    ///
+    /// // ... use the backend ...
+    ///
+    /// janitor.abort();
+    /// # }
+    /// ```
+    pub fn spawn_janitor(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let backend = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes at once; skip it so the first
+            // compaction runs after one interval.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match backend.upgrade() {
+                    Some(backend) => {
+                        if let Err(e) = backend.compact().await {
+                            eprintln!("Warning: {}", e);
+                        }
+                    }
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Takes the chunk id of a new upload and registers the upload until the
+    /// returned guard is dropped.
+    fn start_upload(&self) -> Upload<'_> {
+        let mut uploads = self.uploads.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = self.next_chunk_id.fetch_add(1, Ordering::Relaxed);
+        uploads.insert(id);
+        Upload {
+            uploads: &self.uploads,
+            id,
+        }
+    }
+
+    /// Runs `operation` on the store off the async executor. See
+    /// [`run_blocking`].
+    async fn blocking<T, F>(&self, operation: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Store) -> Result<T> + Send + 'static,
+    {
+        run_blocking(Arc::clone(&self.store), operation).await
+    }
+
+    /// Writes the chunks read from `reader` for the value of `key` under
+    /// chunk id `id`, each behind the expiry of their entry, returning their
+    /// number and total length.
+    async fn write_chunks<R>(
+        &self,
+        key: &str,
+        id: u64,
+        reader: &mut R,
+        expires_at: u64,
+    ) -> Result<(u32, u64)>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        let mut chunk = Vec::with_capacity(STREAM_CHUNK_SIZE);
+        let mut count: u32 = 0;
+        let mut len: u64 = 0;
+        while read_chunk(reader, &mut chunk).await? > 0 {
+            let mut record = Vec::with_capacity(CHUNK_HEADER_LEN + chunk.len());
+            record.extend_from_slice(&expires_at.to_be_bytes());
+            record.extend_from_slice(&chunk);
+            let key = key.to_string();
+            self.blocking(move |store| store.put_chunk(&key, id, count, &record))
+                .await?;
+            count = count
+                .checked_add(1)
+                .ok_or_else(|| Error::Backend("streamed value is too large".into()))?;
+            len += chunk.len() as u64;
+        }
+        Ok((count, len))
+    }
+}
+
+/// Runs `operation` on `store` on tokio's blocking thread pool, so that
+/// RocksDB calls, which may stall behind flushes and compactions, do not hold
+/// up an executor thread.
+///
+/// Without a tokio runtime, as when a synchronous `#[fncache]` function
+/// drives the backend with `futures::executor::block_on`, there is no pool to
+/// hand the call to and it runs in place.
+async fn run_blocking<T, F>(store: Arc<Store>, operation: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&Store) -> Result<T> + Send + 'static,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => runtime
+            .spawn_blocking(move || operation(&store))
+            .await
+            .map_err(|e| Error::Backend(format!("RocksDB task failed: {}", e)))?,
+        Err(_) => operation(&store),
+    }
+}
+
+/// Refuses a database in an entry format other than the one of this
+/// release.
+fn check_format_version(version: &[u8]) -> Result<()> {
+    match version {
+        [ENTRY_VERSION] => Ok(()),
+        [version] => Err(Error::Config(format!(
+            "the RocksDB cache is in entry format version {}, but this release reads version {}",
+            version, ENTRY_VERSION
+        ))),
+        _ => Err(Error::Codec("Invalid RocksDB entry format version".into())),
+    }
+}
+
+/// Checks that the checkpoint in `path` is in the entry format of this
+/// release, that its entries decode and that the chunks of its streamed
+/// values are present.
+fn verify_checkpoint(path: &Path) -> Result<()> {
+    let inconsistent =
+        |e: rocksdb::Error| Error::Backend(format!("Failed to read RocksDB checkpoint: {}", e));
+    let options = Options::default();
+    let names = Db::list_cf(&options, path).map_err(inconsistent)?;
+    let descriptors = names
+        .iter()
+        .map(|name| ColumnFamilyDescriptor::new(name, Options::default()));
+    let db = Db::open_cf_descriptors_read_only(&options, path, descriptors, false)
+        .map_err(inconsistent)?;
+
+    let default = db
+        .cf_handle(DEFAULT_COLUMN_FAMILY_NAME)
+        .ok_or_else(|| Error::Backend("RocksDB default column family is missing".into()))?;
+    match db.get_pinned_cf(&default, FORMAT_VERSION_KEY) {
+        Ok(Some(version)) => check_format_version(&version)?,
+        Ok(None) => {
+            return Err(Error::Config(
+                "the RocksDB checkpoint records no entry format version".into(),
+            ))
+        }
+        Err(e) => return Err(inconsistent(e)),
+    }
+
+    for name in &names {
+        let Some(cf) = db.cf_handle(name) else {
+            continue;
+        };
+        // Chunks and metadata sort after every entry.
+        let mut read_options = ReadOptions::default();
+        read_options.fill_cache(false);
+        read_options.set_iterate_range(..CHUNK_KEY_PREFIX);
+        for item in db.iterator_cf_opt(&cf, read_options, IteratorMode::Start) {
+            let (key, value) = item.map_err(inconsistent)?;
+            let corrupt = |e: Error| {
+                Error::Backend(format!(
+                    "RocksDB checkpoint entry {} is corrupt: {}",
+                    String::from_utf8_lossy(&key),
+                    e
+                ))
+            };
+            entry_expiry(&value).map_err(corrupt)?;
+            if value.get(5) != Some(&KIND_CHUNKED) || !value.starts_with(&ENTRY_MAGIC) {
+                continue;
+            }
+            let chunks = decode_entry(value.to_vec())
+                .map_err(corrupt)?
+                .chunks
+                .expect("chunked entries have chunks");
+            for index in 0..chunks.count {
+                match db.get_pinned_cf(&cf, chunk_key(chunks.id, index)) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => {
+                        return Err(corrupt(Error::Backend(format!(
+                            "chunk {} of its value is missing",
+                            index
+                        ))))
+                    }
+                    Err(e) => return Err(inconsistent(e)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Copies the files of the checkpoint in `from` into the directory `to`,
+/// which must not exist or be empty, hard-linking the table files where
+/// possible.
+///
+/// The files go to a sibling of `to` first, which is renamed to `to` once
+/// they all are copied, so that a copy that fails leaves `to` as it was.
+fn copy_checkpoint(from: &Path, to: &Path) -> Result<()> {
+    match fs::read_dir(to) {
+        Ok(mut files) if files.next().is_some() => {
+            return Err(Error::Config(format!(
+                "cannot restore a RocksDB checkpoint into {}: the directory is not empty",
+                to.display()
+            )));
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let Some(name) = to.file_name() else {
+        return Err(Error::Config(format!(
+            "cannot restore a RocksDB checkpoint into {}",
+            to.display()
+        )));
+    };
+    let mut staging_name = name.to_os_string();
+    staging_name.push(".restoring");
+    let staging = to.with_file_name(staging_name);
+
+    // Left behind by a restore that crashed.
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    let copied = copy_files(from, &staging).and_then(|()| {
+        // Only an empty directory is in the way, and a rename cannot
+        // replace a directory everywhere.
+        if to.exists() {
+            fs::remove_dir(to)?;
+        }
+        fs::rename(&staging, to)?;
+        Ok(())
+    });
+    if copied.is_err() {
+        if let Err(e) = fs::remove_dir_all(&staging) {
+            eprintln!("Warning: failed to remove a partial checkpoint copy: {}", e);
+        }
+    }
+    copied
+}
+
+/// Copies the files of the directory `from` into the directory `to`,
+/// hard-linking the table files where possible.
+fn copy_files(from: &Path, to: &Path) -> Result<()> {
+    for file in fs::read_dir(from)? {
+        let file = file?;
+        // A checkpoint is a flat directory of files.
+        if !file.file_type()?.is_file() {
+            continue;
+        }
+        let source = file.path();
+        let target = to.join(file.file_name());
+        let is_table = source
+            .extension()
+            .is_some_and(|extension| extension == "sst");
+        if !(is_table && fs::hard_link(&source, &target).is_ok()) {
+            fs::copy(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// The database and its settings, shared with the blocking tasks that run
+/// RocksDB calls. All methods block.
+struct Store {
+    /// RocksDB database handle
+    db: Db,
+    /// Cache metrics
+    metrics: Metrics,
+    /// Namespaces that have a column family; held while one is created or
+    /// dropped
+    namespaces: Mutex<BTreeSet<String>>,
+    /// Locks striped over the keys; held from reading the entry a write
+    /// replaces or removes until the write, so that the chunks of the entry
+    /// it read are still those it deletes
+    key_locks: Box<[Mutex<()>]>,
+    /// Options of every write, which may skip the write-ahead log
+    write_options: WriteOptions,
+    /// Block cache shared by the column families, including those created
+    /// later
+    block_cache: Option<Cache>,
+    config: RocksDBConfig,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("db", &self.db)
+            .field("metrics", &self.metrics)
+            .field("namespaces", &self.namespaces)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Store {
+    /// Checks the entry format version recorded in the database, recording
+    /// it in a new database.
+    fn init_format_version(&self) -> Result<()> {
+        let cf = self
+            .db
+            .cf_handle(DEFAULT_COLUMN_FAMILY_NAME)
+            .ok_or_else(|| Error::Backend("RocksDB default column family is missing".into()))?;
+        match self.db.get_pinned_cf(&cf, FORMAT_VERSION_KEY) {
+            Ok(Some(version)) => check_format_version(&version),
+            // Databases of earlier releases have no version; their entries
+            // are read all the same.
+            Ok(None) if self.config.read_only => Ok(()),
+            Ok(None) => self
+                .db
+                .put_cf_opt(
+                    &cf,
+                    FORMAT_VERSION_KEY,
+                    [ENTRY_VERSION],
+                    &self.write_options,
+                )
+                .map_err(|e| Error::Backend(format!("Failed to store in RocksDB: {}", e))),
+            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
+        }
+    }
+
+    fn checkpoint(&self, path: &Path) -> Result<()> {
+        Checkpoint::new(&self.db)
+            .and_then(|checkpoint| checkpoint.create_checkpoint(path))
+            .map_err(|e| Error::Backend(format!("Failed to create RocksDB checkpoint: {}", e)))
+    }
+
+    /// Fails if the database was opened read-only.
+    fn check_writable(&self) -> Result<()> {
+        if self.config.read_only {
+            Err(Error::Backend(
+                "the RocksDB cache was opened read-only".into(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write(&self, batch: WriteBatch) -> std::result::Result<(), rocksdb::Error> {
+        self.db.write_opt(batch, &self.write_options)
+    }
+
+    /// Locks the set of namespaces, recovering from a poisoned lock.
+    fn namespace_set(&self) -> MutexGuard<'_, BTreeSet<String>> {
+        self.namespaces
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the stripe of `key`, recovering from a poisoned lock.
+    fn lock_key(&self, key: &str) -> MutexGuard<'_, ()> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let stripe = hasher.finish() as usize % self.key_locks.len();
+        self.key_locks[stripe]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the namespace of `key`, if keys are split into namespaces and
+    /// it has one.
+    fn namespace_of<'k>(&self, key: &'k str) -> Option<&'k str> {
+        let separator = self.config.namespace_separator?;
+        key.split_once(separator)
+            .map(|(namespace, _)| namespace)
+            .filter(|namespace| !namespace.is_empty())
+    }
+
+    /// Returns the name of the column family `key` is stored in.
+    fn column_family_name(&self, key: &str) -> String {
+        match self.namespace_of(key) {
+            Some(namespace) => namespace_column_family(namespace),
+            None => DEFAULT_COLUMN_FAMILY_NAME.to_string(),
+        }
+    }
+
+    /// Returns the column family `key` is stored in, creating the column
+    /// family of its namespace if it has none yet.
+    fn column_family_for_write(&self, key: &str) -> Result<Arc<BoundColumnFamily<'_>>> {
+        let name = self.column_family_name(key);
+        if let Some(cf) = self.db.cf_handle(&name) {
+            return Ok(cf);
+        }
+        if let Some(namespace) = self.namespace_of(key) {
+            let mut namespaces = self.namespace_set();
+            if self.db.cf_handle(&name).is_none() {
+                let options = self.config.column_family_options(self.block_cache.as_ref());
+                self.db.create_cf(&name, &options).map_err(|e| {
+                    Error::Backend(format!("Failed to create RocksDB namespace: {}", e))
+                })?;
+            }
+            namespaces.insert(namespace.to_string());
+        }
+        self.db
+            .cf_handle(&name)
+            .ok_or_else(|| Error::Backend(format!("RocksDB column family {} is missing", name)))
+    }
+
+    /// Reads the entry of `key`, removing it if it has expired, and records
+    /// a hit or a miss.
+    fn lookup(&self, key: &str) -> Result<Option<CacheEntry>> {
+        let Some(cf) = self.db.cf_handle(&self.column_family_name(key)) else {
+            self.metrics.record_miss();
+            return Ok(None);
+        };
+        match self.db.get_cf(&cf, key.as_bytes()) {
+            Ok(Some(bytes)) => match decode_entry(bytes) {
+                Ok(entry) => {
+                    if entry.is_expired(now_millis()) {
+                        if !self.config.read_only {
+                            self.delete_expired(&cf, key)?;
+                        }
+                        self.metrics.record_miss();
+                        Ok(None)
+                    } else {
+                        self.metrics.record_hit();
+                        Ok(Some(entry))
+                    }
+                }
+                Err(e) => {
+                    self.metrics.record_miss();
+                    Err(e)
+                }
+            },
+            Ok(None) => {
+                self.metrics.record_miss();
+                Ok(None)
+            }
+            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
+        }
+    }
+
+    /// Reads the value of `key`, reassembling it if it was streamed.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let Some(entry) = self.lookup(key)? else {
+            return Ok(None);
+        };
+        match entry.chunks {
+            None => Ok(Some(entry.value)),
+            Some(chunks) => {
+                let cf_name = self.column_family_name(key);
+                let mut value = Vec::new();
+                for index in 0..chunks.count {
+                    value.extend_from_slice(&self.load_chunk(&cf_name, chunks.id, index)?);
+                }
+                Ok(Some(value))
+            }
+        }
+    }
+
+    /// Stores `entry` under `key`, deleting the chunks of the entry it
+    /// replaces in the same batch.
+    fn store(&self, key: &str, entry: &CacheEntry) -> Result<()> {
+        self.check_writable()?;
+        let bytes = encode_entry(entry);
+        let cf = self.column_family_for_write(key)?;
+
+        let _key_lock = self.lock_key(key);
+        let mut batch = WriteBatch::default();
+        if let Some(chunks) = self.stored_chunks(&cf, key)? {
+            Self::delete_chunks(&mut batch, &cf, chunks);
+        }
+        batch.put_cf(&cf, key.as_bytes(), bytes);
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("Failed to store in RocksDB: {}", e)))?;
+
+        self.metrics.record_insertion();
+        Ok(())
+    }
+
+    /// Removes `key` and the chunks of its value.
+    fn remove(&self, key: &str) -> Result<()> {
+        self.check_writable()?;
+        let Some(cf) = self.db.cf_handle(&self.column_family_name(key)) else {
+            return Ok(());
+        };
+        let _key_lock = self.lock_key(key);
+        let mut batch = WriteBatch::default();
+        if let Some(chunks) = self.stored_chunks(&cf, key)? {
+            Self::delete_chunks(&mut batch, &cf, chunks);
+        }
+        batch.delete_cf(&cf, key.as_bytes());
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("Failed to remove from RocksDB: {}", e)))
+    }
+
+    /// Whether `key` has an entry that has not expired, reading only its
+    /// header.
+    fn contains_key(&self, key: &str) -> Result<bool> {
+        let Some(cf) = self.db.cf_handle(&self.column_family_name(key)) else {
+            return Ok(false);
+        };
+        match self.db.get_pinned_cf(&cf, key.as_bytes()) {
+            Ok(Some(bytes)) => match entry_expiry(&bytes) {
+                Ok(expires_at) => Ok(!is_expired(expires_at, now_millis())),
+                Err(_) => Ok(false),
+            },
+            Ok(None) => Ok(false),
+            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
+        }
+    }
+
+    /// Drops the column family of every namespace and deletes the default
+    /// column family with a single range tombstone.
+    fn clear(&self) -> Result<()> {
+        self.check_writable()?;
+        let namespaces: Vec<String> = self.namespace_set().iter().cloned().collect();
+        for namespace in namespaces {
+            self.drop_namespace(&namespace)?;
+        }
+
+        let cf = self
+            .db
+            .cf_handle(DEFAULT_COLUMN_FAMILY_NAME)
+            .ok_or_else(|| Error::Backend("RocksDB default column family is missing".into()))?;
+        let mut batch = WriteBatch::default();
+        batch.delete_range_cf(&cf, b"".as_slice(), KEYSPACE_END);
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("Failed to clear RocksDB: {}", e)))
+    }
+
+    /// Drops the column family of `namespace`, if it has one.
+    fn drop_namespace(&self, namespace: &str) -> Result<()> {
+        self.check_writable()?;
+        let mut namespaces = self.namespace_set();
+        if namespaces.contains(namespace) {
+            self.db
+                .drop_cf(&namespace_column_family(namespace))
+                .map_err(|e| Error::Backend(format!("Failed to drop RocksDB namespace: {}", e)))?;
+            namespaces.remove(namespace);
+        }
+        Ok(())
+    }
+
+    /// Deletes the chunks that no entry points at, with ids below
+    /// `next_chunk_id` other than those of `uploads` in progress.
+    ///
+    /// They belong to uploads that crashed or were abandoned before storing
+    /// their entry, in this run or an earlier one. Entries are read first: an
+    /// upload that stores its entry meanwhile was still in `uploads`.
+    fn remove_orphaned_chunks(&self, next_chunk_id: u64, uploads: &HashSet<u64>) -> Result<()> {
+        if self.config.read_only {
+            return Ok(());
+        }
+        let mut names = vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()];
+        names.extend(
+            self.namespace_set()
+                .iter()
+                .map(|ns| namespace_column_family(ns)),
+        );
+        for name in names {
+            let Some(cf) = self.db.cf_handle(&name) else {
+                continue;
+            };
+
+            let mut referenced = HashSet::new();
+            for item in self.prefix_iterator(&cf, b"", CHUNK_KEY_PREFIX) {
+                let (_, value) =
+                    item.map_err(|e| Error::Backend(format!("RocksDB error: {}", e)))?;
+                if value.get(5) == Some(&KIND_CHUNKED) && value.starts_with(&ENTRY_MAGIC) {
+                    if let Some(chunks) = decode_entry(value.into_vec())
+                        .ok()
+                        .and_then(|entry| entry.chunks)
+                    {
+                        referenced.insert(chunks.id);
+                    }
+                }
+            }
+
+            // Visits the first chunk of each id only, seeking past the rest.
+            let mut read_options = ReadOptions::default();
+            read_options.fill_cache(false);
+            read_options.set_iterate_range(CHUNK_KEY_PREFIX.to_vec()..chunk_key(next_chunk_id, 0));
+            let mut chunks = self.db.raw_iterator_cf_opt(&cf, read_options);
+            chunks.seek_to_first();
+            let mut batch = WriteBatch::default();
+            while let Some(id) = chunks.key().and_then(chunk_id) {
+                if !referenced.contains(&id) && !uploads.contains(&id) {
+                    Self::delete_chunks(
+                        &mut batch,
+                        &cf,
+                        Chunks {
+                            id,
+                            count: u32::MAX,
+                        },
+                    );
+                }
+                match id.checked_add(1) {
+                    Some(next) => chunks.seek(chunk_key(next, 0)),
+                    None => break,
+                }
+            }
+            chunks
+                .status()
+                .map_err(|e| Error::Backend(format!("RocksDB error: {}", e)))?;
+            drop(chunks);
+
+            self.write(batch)
+                .map_err(|e| Error::Backend(format!("Failed to remove from RocksDB: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Compacts every column family, unless the database is read-only.
+    fn compact(&self) -> Result<()> {
+        if self.config.read_only {
+            return Ok(());
+        }
+        let mut names = vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()];
+        names.extend(
+            self.namespace_set()
+                .iter()
+                .map(|ns| namespace_column_family(ns)),
+        );
+        for name in names {
+            // A namespace dropped meanwhile has nothing left to compact.
+            if let Some(cf) = self.db.cf_handle(&name) {
+                self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes `key` and the chunks of its entry if the entry is still
+    /// expired once the key is locked; it may have been replaced meanwhile.
+    fn delete_expired(&self, cf: &impl AsColumnFamilyRef, key: &str) -> Result<()> {
+        let _key_lock = self.lock_key(key);
+        let entry = match self.db.get_cf(cf, key.as_bytes()) {
+            Ok(Some(bytes)) => decode_entry(bytes)?,
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(Error::Backend(format!("RocksDB error: {}", e))),
+        };
+        if !entry.is_expired(now_millis()) {
+            return Ok(());
+        }
+
+        let mut batch = WriteBatch::default();
+        if let Some(chunks) = entry.chunks {
+            Self::delete_chunks(&mut batch, cf, chunks);
+        }
+        batch.delete_cf(cf, key.as_bytes());
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("Failed to delete expired key: {}", e)))
+    }
+
+    /// Returns the chunks of the value currently stored under `key`, if it
+    /// was streamed.
+    fn stored_chunks(&self, cf: &impl AsColumnFamilyRef, key: &str) -> Result<Option<Chunks>> {
+        match self.db.get_pinned_cf(cf, key.as_bytes()) {
+            // Only streamed entries in the binary format have chunks.
+            Ok(Some(bytes)) if bytes.starts_with(&ENTRY_MAGIC) => Ok(decode_entry(bytes.to_vec())
+                .ok()
+                .and_then(|entry| entry.chunks)),
+            Ok(Some(_)) => Ok(None),
+            Ok(None) => Ok(None),
+            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
+        }
+    }
+
+    /// Adds the deletion of `chunks` to `batch`, as a single range tombstone.
+    fn delete_chunks(batch: &mut WriteBatch, cf: &impl AsColumnFamilyRef, chunks: Chunks) {
+        batch.delete_range_cf(
+            cf,
+            chunk_key(chunks.id, 0),
+            chunk_key(chunks.id, chunks.count),
+        );
+    }
+
+    /// Reads chunk `index` of the value with chunk id `id` from column
+    /// family `cf_name`.
+    fn load_chunk(&self, cf_name: &str, id: u64, index: u32) -> Result<Vec<u8>> {
+        let Some(cf) = self.db.cf_handle(cf_name) else {
+            return Err(Error::Backend(
+                "chunk of a streamed value is missing; its namespace was dropped".into(),
+            ));
+        };
+        match self.db.get_pinned_cf(&cf, chunk_key(id, index)) {
+            Ok(Some(chunk)) => match chunk.get(CHUNK_HEADER_LEN..) {
+                Some(bytes) => Ok(bytes.to_vec()),
+                None => Err(Error::Codec("Truncated chunk of a streamed value".into())),
+            },
+            Ok(None) => Err(Error::Backend(
+                "chunk of a streamed value is missing; the entry was replaced or removed".into(),
+            )),
+            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
+        }
+    }
+
+    /// Stores chunk `index` of the value of `key` with chunk id `id`.
+    fn put_chunk(&self, key: &str, id: u64, index: u32, record: &[u8]) -> Result<()> {
+        let cf = self.column_family_for_write(key)?;
+        self.db
+            .put_cf_opt(&cf, chunk_key(id, index), record, &self.write_options)
+            .map_err(|e| Error::Backend(format!("Failed to store in RocksDB: {}", e)))
+    }
+
+    /// Deletes the chunks with chunk id `id` of an upload to `key` that
+    /// failed. Their number is unknown, so the whole id range goes.
+    fn discard_chunks(&self, key: &str, id: u64) -> Result<()> {
+        let Some(cf) = self.db.cf_handle(&self.column_family_name(key)) else {
+            return Ok(());
+        };
+        let mut batch = WriteBatch::default();
+        Self::delete_chunks(
+            &mut batch,
+            &cf,
+            Chunks {
+                id,
+                count: u32::MAX,
+            },
+        );
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("RocksDB error: {}", e)))
+    }
+
+    /// Drops the namespaces whose keys all start with `prefix` and deletes
+    /// the matching keys of the other column families.
+    fn invalidate_prefix(&self, prefix: &str) -> Result<()> {
+        self.check_writable()?;
+        let (column_families, namespaces) = self.prefix_scope(prefix);
+        for namespace in namespaces {
+            self.drop_namespace(&namespace)?;
+        }
+        for name in column_families {
+            self.delete_prefix(&name, prefix)?;
+        }
+        Ok(())
+    }
 
-        let db = DB::open(&options, db_path)
-            .map_err(|e| Error::Backend(format!("Failed to open RocksDB: {}", e)))?;
+    /// Deletes the keys of column family `cf_name` starting with `prefix`,
+    /// and the chunks of their streamed values, with range tombstones.
+    fn delete_prefix(&self, cf_name: &str, prefix: &str) -> Result<()> {
+        let Some(cf) = self.db.cf_handle(cf_name) else {
+            return Ok(());
+        };
+        let start = prefix.as_bytes().to_vec();
+        let end = prefix_end(&start);
 
-        Ok(Self {
-            db: Arc::new(db),
-            metrics: Arc::new(Metrics::new()),
-        })
+        // Streamed values keep their chunks elsewhere in the column family,
+        // so find them before the entries pointing at them go.
+        let mut batch = WriteBatch::default();
+        for item in self.prefix_iterator(&cf, &start, &end) {
+            let (key, value) = item.map_err(|e| Error::Backend(format!("RocksDB error: {}", e)))?;
+            if key.starts_with(CHUNK_KEY_PREFIX) {
+                break;
+            }
+            if value.get(5) == Some(&KIND_CHUNKED) && value.starts_with(&ENTRY_MAGIC) {
+                if let Some(chunks) = decode_entry(value.into_vec())
+                    .ok()
+                    .and_then(|entry| entry.chunks)
+                {
+                    Self::delete_chunks(&mut batch, &cf, chunks);
+                }
+            }
+        }
+        batch.delete_range_cf(&cf, start, end);
+        self.write(batch)
+            .map_err(|e| Error::Backend(format!("Failed to remove from RocksDB: {}", e)))
     }
 
-    fn is_expired(entry: &CacheEntry) -> bool {
-        if let Some(expires_at) = entry.expires_at {
-            SystemTime::now() > expires_at
-        } else {
-            false
+    /// Returns the cache keys starting with `prefix`.
+    fn keys_by_prefix(&self, prefix: &str) -> Result<HashSet<String>> {
+        let (column_families, namespaces) = self.prefix_scope(prefix);
+        let names = column_families
+            .into_iter()
+            .chain(namespaces.iter().map(|ns| namespace_column_family(ns)));
+
+        let mut keys = HashSet::new();
+        for name in names {
+            let Some(cf) = self.db.cf_handle(&name) else {
+                continue;
+            };
+            let start = prefix.as_bytes().to_vec();
+            let end = prefix_end(&start);
+            for item in self.prefix_iterator(&cf, &start, &end) {
+                let (key, _) = item.map_err(|e| Error::Backend(format!("RocksDB error: {}", e)))?;
+                if key.starts_with(CHUNK_KEY_PREFIX) {
+                    break;
+                }
+                keys.insert(String::from_utf8_lossy(&key).into_owned());
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Iterates over the records of `cf` from `start` up to `end`, bypassing
+    /// the block cache.
+    ///
+    /// The column families have no prefix extractor, on purpose. Prefixes are
+    /// arbitrary text rather than of one length, and an extractor fixes a
+    /// single prefix per key: iterating in prefix mode over another one would
+    /// miss keys, and changing the extractor later leaves the prefix filters
+    /// of the existing tables unused. One on the namespace separator would not
+    /// help either, since every key of a namespace column family shares that
+    /// prefix. The bounds already let RocksDB skip the table files outside
+    /// the range and stop at its end.
+    fn prefix_iterator<'a>(
+        &'a self,
+        cf: &impl AsColumnFamilyRef,
+        start: &[u8],
+        end: &[u8],
+    ) -> rocksdb::DBIteratorWithThreadMode<'a, Db> {
+        let mut read_options = ReadOptions::default();
+        read_options.fill_cache(false);
+        read_options.set_iterate_range(start.to_vec()..end.to_vec());
+        self.db
+            .iterator_cf_opt(cf, read_options, IteratorMode::Start)
+    }
+
+    /// Returns the column families that may hold keys starting with
+    /// `prefix`, and the namespaces all of whose keys start with it.
+    fn prefix_scope(&self, prefix: &str) -> (Vec<String>, Vec<String>) {
+        let Some(separator) = self.config.namespace_separator else {
+            return (vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()], Vec::new());
+        };
+        if let Some(namespace) = self.namespace_of(prefix) {
+            let whole = prefix.len() == namespace.len() + separator.len_utf8();
+            return if whole {
+                (Vec::new(), vec![namespace.to_string()])
+            } else {
+                (vec![namespace_column_family(namespace)], Vec::new())
+            };
         }
+        let covered = self
+            .namespace_set()
+            .iter()
+            .filter(|namespace| format!("{}{}", namespace, separator).starts_with(prefix))
+            .cloned()
+            .collect();
+        (vec![DEFAULT_COLUMN_FAMILY_NAME.to_string()], covered)
     }
 }
 
@@ -164,103 +1722,129 @@ impl RocksDBBackend {
 /// * TTL support with automatic cleanup of expired entries
 /// * Atomic read/write operations
 /// * Metrics for hits, misses and insertions
-/// * Bincode serialization for efficient binary storage
+/// * Fixed-width binary entry headers, so expiry checks skip the value
+/// * RocksDB calls on the blocking thread pool, off the async executor
 #[async_trait]
 impl CacheBackend for RocksDBBackend {
     async fn get(&self, key: &String) -> Result<Option<Vec<u8>>> {
-        match self.db.get(key.as_bytes()) {
-            Ok(Some(bytes)) => match bincode::deserialize::<CacheEntry>(&bytes) {
-                Ok(entry) => {
-                    if Self::is_expired(&entry) {
-                        if let Err(e) = self.db.delete(key.as_bytes()) {
-                            return Err(Error::Backend(format!(
-                                "Failed to delete expired key: {}",
-                                e
-                            )));
-                        }
-                        self.metrics.record_miss();
-                        Ok(None)
-                    } else {
-                        self.metrics.record_hit();
-                        Ok(Some(entry.value))
-                    }
-                }
-                Err(e) => {
-                    self.metrics.record_miss();
-                    Err(Error::Codec(format!(
-                        "Failed to deserialize cache entry: {}",
-                        e
-                    )))
-                }
-            },
-            Ok(None) => {
-                self.metrics.record_miss();
-                Ok(None)
-            }
-            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
-        }
+        let key = key.clone();
+        self.blocking(move |store| store.get(&key)).await
     }
 
     async fn set(&self, key: String, value: Vec<u8>, ttl: Option<Duration>) -> Result<()> {
-        let expires_at = ttl.map(|duration| {
-            SystemTime::now()
-                .checked_add(duration)
-                .unwrap_or_else(|| SystemTime::now() + duration)
-        });
-
-        let entry = CacheEntry { value, expires_at };
-
-        let bytes = bincode::serialize(&entry)
-            .map_err(|e| Error::Codec(format!("Failed to serialize cache entry: {}", e)))?;
-
-        self.db
-            .put(key.as_bytes(), bytes)
-            .map_err(|e| Error::Backend(format!("Failed to store in RocksDB: {}", e)))?;
-
-        self.metrics.record_insertion();
-        Ok(())
+        let entry = CacheEntry {
+            value,
+            expires_at: expires_at(ttl),
+            chunks: None,
+        };
+        self.blocking(move |store| store.store(&key, &entry)).await
     }
 
     async fn remove(&self, key: &String) -> Result<()> {
-        self.db
-            .delete(key.as_bytes())
-            .map_err(|e| Error::Backend(format!("Failed to remove from RocksDB: {}", e)))?;
-
-        Ok(())
+        let key = key.clone();
+        self.blocking(move |store| store.remove(&key)).await
     }
 
     async fn contains_key(&self, key: &String) -> Result<bool> {
-        match self.db.get(key.as_bytes()) {
-            Ok(Some(bytes)) => match bincode::deserialize::<CacheEntry>(&bytes) {
-                Ok(entry) => {
-                    if Self::is_expired(&entry) {
-                        Ok(false)
-                    } else {
-                        Ok(true)
-                    }
-                }
-                Err(_) => Ok(false),
-            },
-            Ok(None) => Ok(false),
-            Err(e) => Err(Error::Backend(format!("RocksDB error: {}", e))),
-        }
+        let key = key.clone();
+        self.blocking(move |store| store.contains_key(&key)).await
     }
 
+    /// Drops the column family of every namespace and deletes the default
+    /// column family with a single range tombstone.
     async fn clear(&self) -> Result<()> {
-        let iter = self.db.iterator(rocksdb::IteratorMode::Start);
+        self.blocking(Store::clear).await
+    }
+}
 
-        let keys: Vec<Vec<u8>> = iter.map(|item| item.unwrap().0.to_vec()).collect();
+/// Prefix invalidation over bounded iterators and range tombstones.
+///
+/// Keys are not tagged in RocksDB, so tag invalidation finds no keys.
+#[async_trait]
+impl AsyncCacheInvalidation for RocksDBBackend {
+    /// Tags are not recorded by this backend, so this always returns an
+    /// empty set.
+    fn get_keys_by_tag(&self, _tag: &Tag) -> HashSet<String> {
+        HashSet::new()
+    }
 
-        for key in keys {
-            if let Err(e) = self.db.delete(&key) {
-                return Err(Error::Backend(format!(
-                    "Failed to delete key during clear: {}",
-                    e
-                )));
+    fn get_keys_by_prefix(&self, prefix: &str) -> HashSet<String> {
+        self.store.keys_by_prefix(prefix).unwrap_or_else(|e| {
+            eprintln!("Warning: failed to list RocksDB keys: {}", e);
+            HashSet::new()
+        })
+    }
+
+    /// Drops the namespaces whose keys all start with `prefix` and deletes
+    /// the matching keys of the other column families with a range
+    /// tombstone, instead of removing them one by one.
+    async fn invalidate_prefix(&self, prefix: &str) -> Result<()> {
+        let prefix = prefix.to_string();
+        self.blocking(move |store| store.invalidate_prefix(&prefix))
+            .await
+    }
+}
+
+/// Streams values in chunks of [`STREAM_CHUNK_SIZE`] bytes stored under keys
+/// of their own. See the [module documentation](self#streaming).
+#[async_trait]
+impl StreamingBackend for RocksDBBackend {
+    async fn put_stream<R>(&self, key: Key, mut reader: R, ttl: Option<Duration>) -> Result<u64>
+    where
+        R: AsyncRead + Send + Unpin,
+    {
+        self.store.check_writable()?;
+        let expires_at = expires_at(ttl);
+        // Until the entry is stored, the upload keeps the compaction from
+        // taking its chunks for those of an abandoned upload.
+        let upload = self.start_upload();
+        let id = upload.id;
+        let (count, len) = match self.write_chunks(&key, id, &mut reader, expires_at).await {
+            Ok(written) => written,
+            Err(e) => {
+                // The chunks written so far belong to no entry.
+                let key = key.clone();
+                if let Err(e) = self
+                    .blocking(move |store| store.discard_chunks(&key, id))
+                    .await
+                {
+                    eprintln!("Warning: failed to delete chunks of a failed upload: {}", e);
+                }
+                return Err(e);
             }
-        }
+        };
 
-        Ok(())
+        let entry = CacheEntry {
+            value: Vec::new(),
+            expires_at,
+            chunks: Some(Chunks { id, count }),
+        };
+        self.blocking(move |store| store.store(&key, &entry))
+            .await?;
+        Ok(len)
+    }
+
+    async fn get_stream(&self, key: &Key) -> Result<Option<ValueReader>> {
+        let lookup_key = key.clone();
+        let Some(entry) = self
+            .blocking(move |store| store.lookup(&lookup_key))
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(match entry.chunks {
+            None => Box::pin(io::Cursor::new(entry.value)) as ValueReader,
+            Some(chunks) => {
+                let store = Arc::clone(&self.store);
+                let cf_name = store.column_family_name(key);
+                chunk_reader(stream::iter(0..chunks.count).then(move |index| {
+                    let cf_name = cf_name.clone();
+                    run_blocking(Arc::clone(&store), move |store| {
+                        store.load_chunk(&cf_name, chunks.id, index)
+                    })
+                }))
+            }
+        }))
     }
 }
 
@@ -271,6 +1855,16 @@ mod tests {
     use tempfile::tempdir;
     use tokio::time::sleep;
 
+    /// Counts the entries and chunks in the default column family.
+    fn record_count(backend: &RocksDBBackend) -> usize {
+        backend
+            .store
+            .db
+            .iterator(rocksdb::IteratorMode::Start)
+            .filter(|item| !item.as_ref().unwrap().0.starts_with(KEYSPACE_END))
+            .count()
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_get_set() {
@@ -357,15 +1951,536 @@ mod tests {
         let key = "test_metrics".to_string();
         let value = b"test_value".to_vec();
 
-        assert_eq!(backend.metrics.hits(), 0);
-        assert_eq!(backend.metrics.misses(), 0);
+        assert_eq!(backend.store.metrics.hits(), 0);
+        assert_eq!(backend.store.metrics.misses(), 0);
 
         assert!(backend.get(&key).await.unwrap().is_none());
-        assert_eq!(backend.metrics.misses(), 1);
+        assert_eq!(backend.store.metrics.misses(), 1);
 
         backend.set(key.clone(), value, None).await.unwrap();
 
         assert!(backend.get(&key).await.unwrap().is_some());
-        assert_eq!(backend.metrics.hits(), 1);
+        assert_eq!(backend.store.metrics.hits(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_put_stream_and_get_stream() {
+        use tokio::io::AsyncReadExt;
+
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path()).unwrap();
+
+        let key = "test_stream".to_string();
+        let value: Vec<u8> = (0..STREAM_CHUNK_SIZE * 5 / 2).map(|i| i as u8).collect();
+        let len = backend
+            .put_stream(key.clone(), value.as_slice(), None)
+            .await
+            .unwrap();
+        assert_eq!(len, value.len() as u64);
+        assert_eq!(backend.get(&key).await.unwrap(), Some(value.clone()));
+
+        let mut streamed = Vec::new();
+        backend
+            .get_stream(&key)
+            .await
+            .unwrap()
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .await
+            .unwrap();
+        assert_eq!(streamed, value);
+
+        // Replacing or removing a streamed value deletes its chunks.
+        backend
+            .set(key.clone(), b"small".to_vec(), None)
+            .await
+            .unwrap();
+        backend.remove(&key).await.unwrap();
+        assert_eq!(record_count(&backend), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[serial]
+    async fn test_concurrent_put_stream_leaves_no_chunks_behind() {
+        let temp_dir = tempdir().unwrap();
+        let backend = Arc::new(RocksDBBackend::new(temp_dir.path()).unwrap());
+        let key = "test_stream".to_string();
+
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let backend = backend.clone();
+                let key = key.clone();
+                tokio::spawn(async move {
+                    let value = vec![i; STREAM_CHUNK_SIZE * 5 / 2];
+                    backend.put_stream(key, value.as_slice(), None).await
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+
+        let value = backend.get(&key).await.unwrap().unwrap();
+        assert_eq!(value.len(), STREAM_CHUNK_SIZE * 5 / 2);
+        assert!(value.iter().all(|&byte| byte == value[0]));
+
+        // Every replaced upload's chunks went with the entry pointing at them.
+        backend.remove(&key).await.unwrap();
+        assert_eq!(record_count(&backend), 0);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_compact_removes_chunks_of_abandoned_uploads() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path()).unwrap();
+        let value: Vec<u8> = (0..STREAM_CHUNK_SIZE * 3 / 2).map(|i| i as u8).collect();
+        backend
+            .put_stream("kept".to_string(), value.as_slice(), None)
+            .await
+            .unwrap();
+        let stored = record_count(&backend);
+
+        // Chunks without an expiry of an upload that never stored its entry,
+        // and of one still in progress.
+        let abandoned = backend.start_upload().id;
+        backend
+            .store
+            .put_chunk("abandoned", abandoned, 0, &0u64.to_be_bytes())
+            .unwrap();
+        let in_progress = backend.start_upload();
+        backend
+            .store
+            .put_chunk("in_progress", in_progress.id, 0, &0u64.to_be_bytes())
+            .unwrap();
+
+        backend.compact().await.unwrap();
+        assert_eq!(record_count(&backend), stored + 1);
+        assert_eq!(backend.get(&"kept".to_string()).await.unwrap(), Some(value));
+
+        drop(in_progress);
+        backend.compact().await.unwrap();
+        assert_eq!(record_count(&backend), stored);
+    }
+
+    #[test]
+    fn test_decodes_entries_of_earlier_releases() {
+        #[derive(serde::Serialize)]
+        struct EarlierEntry {
+            value: Vec<u8>,
+            expires_at: Option<SystemTime>,
+        }
+
+        let bytes = bincode::serialize(&EarlierEntry {
+            value: b"value".to_vec(),
+            expires_at: None,
+        })
+        .unwrap();
+        assert_eq!(entry_expiry(&bytes).unwrap(), 0);
+        let entry = decode_entry(bytes).unwrap();
+        assert_eq!(entry.value, b"value");
+        assert_eq!(entry.expires_at, 0);
+        assert!(entry.chunks.is_none());
+    }
+
+    #[test]
+    fn test_entry_header_round_trip() {
+        let inline = encode_entry(&CacheEntry {
+            value: b"value".to_vec(),
+            expires_at: 42,
+            chunks: None,
+        });
+        assert_eq!(inline.len(), ENTRY_HEADER_LEN + 5);
+        assert_eq!(entry_expiry(&inline).unwrap(), 42);
+        let entry = decode_entry(inline).unwrap();
+        assert_eq!(entry.value, b"value");
+        assert_eq!(entry.expires_at, 42);
+        assert!(entry.chunks.is_none());
+
+        let chunked = encode_entry(&CacheEntry {
+            value: Vec::new(),
+            expires_at: 0,
+            chunks: Some(Chunks { id: 7, count: 3 }),
+        });
+        assert_eq!(entry_expiry(&chunked).unwrap(), 0);
+        let chunks = decode_entry(chunked).unwrap().chunks.unwrap();
+        assert_eq!((chunks.id, chunks.count), (7, 3));
+
+        let mut unknown = encode_entry(&CacheEntry {
+            value: Vec::new(),
+            expires_at: 0,
+            chunks: None,
+        });
+        unknown[ENTRY_MAGIC.len()] = ENTRY_VERSION + 1;
+        assert!(matches!(decode_entry(unknown), Err(Error::Codec(_))));
+    }
+
+    #[test]
+    fn test_compaction_filter_removes_expired_records() {
+        let entry = |expires_at| {
+            encode_entry(&CacheEntry {
+                value: b"value".to_vec(),
+                expires_at,
+                chunks: None,
+            })
+        };
+        let chunk = |expires_at: u64| [&expires_at.to_be_bytes()[..], b"chunk"].concat();
+        let now = now_millis();
+
+        let decide = |key: &[u8], value: &[u8]| compaction_filter(0, key, value);
+        assert!(matches!(decide(b"key", &entry(1)), Decision::Remove));
+        assert!(matches!(decide(b"key", &entry(0)), Decision::Keep));
+        assert!(matches!(
+            decide(b"key", &entry(now + 60_000)),
+            Decision::Keep
+        ));
+        assert!(matches!(
+            decide(&chunk_key(1, 0), &chunk(1)),
+            Decision::Remove
+        ));
+        assert!(matches!(
+            decide(&chunk_key(1, 0), &chunk(0)),
+            Decision::Keep
+        ));
+        assert!(matches!(decide(b"key", b"garbage"), Decision::Keep));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_compact_drops_expired_entries() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path()).unwrap();
+
+        backend
+            .set(
+                "expiring".to_string(),
+                b"value".to_vec(),
+                Some(Duration::from_millis(10)),
+            )
+            .await
+            .unwrap();
+        backend
+            .set("kept".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(20)).await;
+
+        backend.compact().await.unwrap();
+
+        assert!(backend.store.db.get(b"expiring").unwrap().is_none());
+        assert!(backend.store.db.get(b"kept").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_namespaces_use_column_families() {
+        let temp_dir = tempdir().unwrap();
+        let config = RocksDBConfig::new(temp_dir.path()).with_namespace_separator(':');
+        let backend = RocksDBBackend::with_config(config).unwrap();
+
+        for key in ["user:1", "user:2", "order:1", "plain"] {
+            backend
+                .set(key.to_string(), b"value".to_vec(), None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.namespaces(), vec!["order", "user"]);
+        assert!(backend.store.db.get(b"plain").unwrap().is_some());
+        assert!(backend.store.db.get(b"user:1").unwrap().is_none());
+
+        backend.drop_namespace("user").await.unwrap();
+        assert_eq!(backend.namespaces(), vec!["order"]);
+        assert!(!backend.contains_key(&"user:1".to_string()).await.unwrap());
+        assert!(backend.contains_key(&"order:1".to_string()).await.unwrap());
+
+        // The namespace comes back on its next write.
+        backend
+            .set("user:3".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        assert!(backend.contains_key(&"user:3".to_string()).await.unwrap());
+        drop(backend);
+
+        // Reopening opens the column families of the namespaces.
+        let config = RocksDBConfig::new(temp_dir.path()).with_namespace_separator(':');
+        let backend = RocksDBBackend::with_config(config).unwrap();
+        assert_eq!(backend.namespaces(), vec!["order", "user"]);
+        assert!(backend.contains_key(&"user:3".to_string()).await.unwrap());
+
+        backend.clear().await.unwrap();
+        assert!(backend.namespaces().is_empty());
+        assert!(!backend.contains_key(&"order:1".to_string()).await.unwrap());
+        assert!(!backend.contains_key(&"plain".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_invalidate_prefix() {
+        let temp_dir = tempdir().unwrap();
+        let config = RocksDBConfig::new(temp_dir.path()).with_namespace_separator(':');
+        let backend = RocksDBBackend::with_config(config).unwrap();
+
+        for key in [
+            "user:1:name",
+            "user:2:name",
+            "order:1",
+            "session-a",
+            "session-b",
+        ] {
+            backend
+                .set(key.to_string(), b"value".to_vec(), None)
+                .await
+                .unwrap();
+        }
+        let large = vec![7u8; STREAM_CHUNK_SIZE + 1];
+        backend
+            .put_stream("sessions".to_string(), large.as_slice(), None)
+            .await
+            .unwrap();
+
+        let keys = backend.get_keys_by_prefix("session");
+        assert_eq!(keys.len(), 3);
+
+        backend.invalidate_prefix("user:1").await.unwrap();
+        assert!(!backend
+            .contains_key(&"user:1:name".to_string())
+            .await
+            .unwrap());
+        assert!(backend
+            .contains_key(&"user:2:name".to_string())
+            .await
+            .unwrap());
+
+        backend.invalidate_prefix("session").await.unwrap();
+        assert!(!backend
+            .contains_key(&"session-a".to_string())
+            .await
+            .unwrap());
+        assert!(!backend.contains_key(&"sessions".to_string()).await.unwrap());
+        // The chunks of the streamed value went with it.
+        assert_eq!(record_count(&backend), 0);
+
+        // A prefix covering a whole namespace drops its column family.
+        backend.invalidate_prefix("ord").await.unwrap();
+        assert_eq!(backend.namespaces(), vec!["user"]);
+        assert!(!backend.contains_key(&"order:1".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_with_config() {
+        let temp_dir = tempdir().unwrap();
+        let config = RocksDBConfig::new(temp_dir.path())
+            .with_block_cache_size(1024 * 1024)
+            .with_bloom_filter(10.0)
+            .with_compression(RocksDBCompression::Lz4)
+            .with_write_buffer_size(4 * 1024 * 1024)
+            .with_wal_disabled(true);
+        let backend = RocksDBBackend::with_config(config).unwrap();
+
+        let key = "configured".to_string();
+        backend
+            .set(key.clone(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"value".to_vec()));
+
+        for config in [
+            RocksDBConfig::new(temp_dir.path()).with_write_buffer_size(0),
+            RocksDBConfig::new(temp_dir.path()).with_bloom_filter(0.0),
+            RocksDBConfig::new(temp_dir.path()).with_bloom_filter(f64::NAN),
+        ] {
+            assert!(matches!(
+                RocksDBBackend::with_config(config),
+                Err(Error::Config(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_read_only() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path()).unwrap();
+        let key = "shipped".to_string();
+        backend
+            .set(key.clone(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        backend
+            .set(
+                "expiring".to_string(),
+                b"value".to_vec(),
+                Some(Duration::from_millis(10)),
+            )
+            .await
+            .unwrap();
+        drop(backend);
+        sleep(Duration::from_millis(20)).await;
+
+        let config = RocksDBConfig::new(temp_dir.path()).with_read_only(true);
+        let backend = RocksDBBackend::with_config(config).unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(b"value".to_vec()));
+        assert_eq!(backend.get(&"expiring".to_string()).await.unwrap(), None);
+        assert!(backend
+            .set(key.clone(), b"other".to_vec(), None)
+            .await
+            .is_err());
+        assert!(backend.remove(&key).await.is_err());
+        assert!(backend.clear().await.is_err());
+        backend.compact().await.unwrap();
+        assert!(backend.contains_key(&key).await.unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_runs_without_a_runtime() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path()).unwrap();
+        let key = "sync".to_string();
+
+        futures::executor::block_on(backend.set(key.clone(), b"value".to_vec(), None)).unwrap();
+        let value = futures::executor::block_on(backend.get(&key)).unwrap();
+        assert_eq!(value, Some(b"value".to_vec()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_checkpoint_and_restore() {
+        let temp_dir = tempdir().unwrap();
+        let config =
+            RocksDBConfig::new(temp_dir.path().join("source")).with_namespace_separator(':');
+        let backend = RocksDBBackend::with_config(config).unwrap();
+        backend
+            .set("user:1".to_string(), b"alice".to_vec(), None)
+            .await
+            .unwrap();
+        let large = vec![3u8; STREAM_CHUNK_SIZE + 1];
+        backend
+            .put_stream("report".to_string(), large.as_slice(), None)
+            .await
+            .unwrap();
+
+        let checkpoint = temp_dir.path().join("checkpoint");
+        backend.checkpoint(&checkpoint).await.unwrap();
+        backend
+            .set("user:2".to_string(), b"bob".to_vec(), None)
+            .await
+            .unwrap();
+
+        let config =
+            RocksDBConfig::new(temp_dir.path().join("restored")).with_namespace_separator(':');
+        let restored = RocksDBBackend::open_from_checkpoint(&checkpoint, config).unwrap();
+        assert_eq!(restored.namespaces(), vec!["user"]);
+        assert_eq!(
+            restored.get(&"user:1".to_string()).await.unwrap(),
+            Some(b"alice".to_vec())
+        );
+        assert_eq!(restored.get(&"user:2".to_string()).await.unwrap(), None);
+        assert_eq!(
+            restored.get(&"report".to_string()).await.unwrap(),
+            Some(large)
+        );
+
+        // The shipped checkpoint is left as it was.
+        restored
+            .set("user:3".to_string(), b"carol".to_vec(), None)
+            .await
+            .unwrap();
+        drop(restored);
+        let config = RocksDBConfig::new(&checkpoint)
+            .with_namespace_separator(':')
+            .with_read_only(true);
+        let in_place = RocksDBBackend::open_from_checkpoint(&checkpoint, config).unwrap();
+        assert!(!in_place.contains_key(&"user:3".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_open_from_checkpoint_refuses_bad_checkpoints() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path().join("source")).unwrap();
+        let large = vec![3u8; STREAM_CHUNK_SIZE + 1];
+        backend
+            .put_stream("report".to_string(), large.as_slice(), None)
+            .await
+            .unwrap();
+        let checkpoint = temp_dir.path().join("checkpoint");
+        backend.checkpoint(&checkpoint).await.unwrap();
+
+        // A destination holding files is not overwritten.
+        let occupied = temp_dir.path().join("occupied");
+        fs::create_dir_all(&occupied).unwrap();
+        fs::write(occupied.join("file"), b"data").unwrap();
+        assert!(matches!(
+            RocksDBBackend::open_from_checkpoint(&checkpoint, RocksDBConfig::new(&occupied)),
+            Err(Error::Config(_))
+        ));
+
+        let db = Db::open_default(&checkpoint).unwrap();
+        db.delete(chunk_key_of(&db, "report", 1)).unwrap();
+        drop(db);
+        let restored = temp_dir.path().join("missing-chunk");
+        assert!(matches!(
+            RocksDBBackend::open_from_checkpoint(&checkpoint, RocksDBConfig::new(&restored)),
+            Err(Error::Backend(_))
+        ));
+
+        let db = Db::open_default(&checkpoint).unwrap();
+        db.put(FORMAT_VERSION_KEY, [ENTRY_VERSION + 1]).unwrap();
+        drop(db);
+        let restored = temp_dir.path().join("other-version");
+        assert!(matches!(
+            RocksDBBackend::open_from_checkpoint(&checkpoint, RocksDBConfig::new(&restored)),
+            Err(Error::Config(_))
+        ));
+        assert!(!restored.exists());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_open_from_checkpoint_can_be_retried() {
+        let temp_dir = tempdir().unwrap();
+        let backend = RocksDBBackend::new(temp_dir.path().join("source")).unwrap();
+        backend
+            .set("key".to_string(), b"value".to_vec(), None)
+            .await
+            .unwrap();
+        let checkpoint = temp_dir.path().join("checkpoint");
+        backend.checkpoint(&checkpoint).await.unwrap();
+
+        // An empty destination, and the partial copy of a restore that
+        // crashed next to it.
+        let restored = temp_dir.path().join("restored");
+        fs::create_dir_all(&restored).unwrap();
+        let staging = temp_dir.path().join("restored.restoring");
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("000001.sst"), b"partial").unwrap();
+
+        // An invalid configuration fails before anything is copied.
+        assert!(matches!(
+            RocksDBBackend::open_from_checkpoint(
+                &checkpoint,
+                RocksDBConfig::new(&restored).with_bloom_filter(0.0)
+            ),
+            Err(Error::Config(_))
+        ));
+        assert_eq!(fs::read_dir(&restored).unwrap().count(), 0);
+
+        let restored =
+            RocksDBBackend::open_from_checkpoint(&checkpoint, RocksDBConfig::new(&restored))
+                .unwrap();
+        assert!(!staging.exists());
+        assert_eq!(
+            restored.get(&"key".to_string()).await.unwrap(),
+            Some(b"value".to_vec())
+        );
+    }
+
+    /// Returns the key of chunk `index` of the value stored under `key`.
+    fn chunk_key_of(db: &Db, key: &str, index: u32) -> Vec<u8> {
+        let entry = decode_entry(db.get(key).unwrap().unwrap()).unwrap();
+        chunk_key(entry.chunks.unwrap().id, index)
     }
 }
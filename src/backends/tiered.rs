@@ -0,0 +1,402 @@
+//! A two-tier cache backend: a fast local L1 in front of a shared L2.
+//!
+//! `TieredBackend` serves reads from the L1 tier, typically a
+//! [`MemoryBackend`](super::memory::MemoryBackend), and falls back to the L2
+//! tier, typically Redis, on a miss. Values found in L2 are promoted into L1
+//! with a bounded local TTL, so hot keys stop reaching L2 while a value
+//! changed by another process is only served stale for at most that TTL.
+//!
+//! Writes always go to L2. In [`WriteMode::WriteThrough`] they are also
+//! written to L1; in [`WriteMode::WriteAround`] the L1 copy is dropped instead
+//! and the next read promotes the value.
+//!
+//! With an [`InvalidationBus`] attached via [`TieredBackend::with_bus`],
+//! writes, removals and clears are broadcast to the other processes, which
+//! drop their L1 copies right away instead of waiting for the L1 TTL.
+//!
+//! # Examples
+//!
+//! ```
+//! use fncache::backends::memory::MemoryBackend;
+//! use fncache::backends::tiered::{TieredBackend, WriteMode};
+//! use fncache::backends::CacheBackend;
+//! use std::time::Duration;
+//!
+//! # async fn example() -> fncache::Result<()> {
+//! // In production the L2 tier would be a shared backend such as Redis.
+//! let backend = TieredBackend::new(MemoryBackend::new().with_capacity(10_000), MemoryBackend::new())
+//!     .with_l1_ttl(Duration::from_secs(5))
+//!     .with_write_mode(WriteMode::WriteThrough);
+//!
+//! backend.set("user:123".to_string(), vec![1, 2, 3], None).await?;
+//! assert_eq!(backend.get(&"user:123".to_string()).await?, Some(vec![1, 2, 3]));
+//! # Ok(())
+//! # }
+//! ```
+
+use super::*;
+use crate::bus::{self, InvalidationBus, InvalidationEvent, InvalidationListener};
+
+/// Default upper bound on how long a value lives in the L1 tier.
+const DEFAULT_L1_TTL: Duration = Duration::from_secs(30);
+
+/// How [`TieredBackend`] treats the L1 tier on writes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Write to L2, then to L1. Reads right after a write are served locally.
+    #[default]
+    WriteThrough,
+    /// Write to L2 and drop the L1 copy. Keeps L1 for keys that are read,
+    /// which suits write-heavy keys that are rarely read back.
+    WriteAround,
+}
+
+/// A cache backend that layers a local L1 tier over a shared L2 tier.
+///
+/// See the [module documentation](self) for the read and write paths.
+#[derive(Debug)]
+pub struct TieredBackend<L1, L2> {
+    /// Local tier, consulted first
+    l1: L1,
+    /// Shared tier, the source of truth
+    l2: L2,
+    /// Upper bound on the TTL of every L1 entry
+    l1_ttl: Duration,
+    /// How writes treat the L1 tier
+    write_mode: WriteMode,
+    /// Bus that L1 invalidations are broadcast on
+    bus: Option<Arc<dyn InvalidationBus>>,
+}
+
+impl<L1, L2> TieredBackend<L1, L2>
+where
+    L1: CacheBackend,
+    L2: CacheBackend,
+{
+    /// Creates a tiered backend with write-through writes and a 30 second
+    /// L1 TTL bound.
+    pub fn new(l1: L1, l2: L2) -> Self {
+        Self {
+            l1,
+            l2,
+            l1_ttl: DEFAULT_L1_TTL,
+            write_mode: WriteMode::default(),
+            bus: None,
+        }
+    }
+
+    /// Sets the upper bound on how long a value lives in the L1 tier.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// Entries with a shorter TTL keep it. The bound is the longest time a
+    /// value changed in L2 by another process can still be served from L1.
+    pub fn with_l1_ttl(mut self, ttl: Duration) -> Self {
+        self.l1_ttl = ttl;
+        self
+    }
+
+    /// Sets how writes treat the L1 tier.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    pub fn with_write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = write_mode;
+        self
+    }
+
+    /// Broadcasts writes, removals and clears on `bus`, so that other
+    /// processes drop their stale L1 copies.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    /// Other processes apply the events once they call
+    /// [`spawn_bus_listener`](Self::spawn_bus_listener).
+    pub fn with_bus(mut self, bus: Arc<dyn InvalidationBus>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Starts applying the invalidations published by other processes to
+    /// the L1 tier.
+    ///
+    /// Returns [`Error::Config`](crate::error::Error::Config) if no bus was
+    /// attached with [`with_bus`](Self::with_bus).
+    pub fn spawn_bus_listener(self: &Arc<Self>) -> crate::Result<tokio::task::JoinHandle<()>>
+    where
+        L1: 'static,
+        L2: 'static,
+    {
+        let bus = self
+            .bus
+            .as_ref()
+            .ok_or_else(|| crate::error::Error::Config("no invalidation bus configured".into()))?;
+        Ok(bus::spawn_listener(self, bus.subscribe()))
+    }
+
+    /// Returns the L1 tier.
+    pub fn l1(&self) -> &L1 {
+        &self.l1
+    }
+
+    /// Returns the L2 tier.
+    pub fn l2(&self) -> &L2 {
+        &self.l2
+    }
+
+    /// Returns `options` with the TTL capped at the L1 bound.
+    fn local_options(&self, options: SetOptions) -> SetOptions {
+        let ttl = options.ttl.map_or(self.l1_ttl, |ttl| ttl.min(self.l1_ttl));
+        SetOptions {
+            ttl: Some(ttl),
+            ..options
+        }
+    }
+}
+
+#[async_trait]
+impl<L1, L2> CacheBackend for TieredBackend<L1, L2>
+where
+    L1: CacheBackend,
+    L2: CacheBackend,
+{
+    async fn get(&self, key: &Key) -> crate::Result<Option<Value>> {
+        if let Some(value) = self.l1.get(key).await? {
+            return Ok(Some(value));
+        }
+
+        let value = self.l2.get(key).await?;
+        if let Some(value) = &value {
+            let options = self.local_options(SetOptions::new());
+            if let Err(e) = self
+                .l1
+                .set_with_options(key.clone(), value.clone(), options)
+                .await
+            {
+                eprintln!("Warning: failed to promote {} into L1: {}", key, e);
+            }
+        }
+        Ok(value)
+    }
+
+    async fn set(&self, key: Key, value: Value, ttl: Option<Duration>) -> crate::Result<()> {
+        let options = SetOptions {
+            ttl,
+            ..SetOptions::default()
+        };
+        self.set_with_options(key, value, options).await
+    }
+
+    async fn set_with_options(
+        &self,
+        key: Key,
+        value: Value,
+        options: SetOptions,
+    ) -> crate::Result<()> {
+        // L2 first, so a failed write never leaves L1 ahead of L2.
+        self.l2
+            .set_with_options(key.clone(), value.clone(), options.clone())
+            .await?;
+
+        let event = self
+            .bus
+            .as_ref()
+            .map(|_| InvalidationEvent::Remove(key.clone()));
+        match self.write_mode {
+            WriteMode::WriteThrough => {
+                let options = self.local_options(options);
+                self.l1.set_with_options(key, value, options).await?
+            }
+            WriteMode::WriteAround => self.l1.remove(&key).await?,
+        }
+
+        if let Some(event) = event {
+            bus::publish_best_effort(self.bus.as_ref(), event).await;
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &Key) -> crate::Result<()> {
+        // L2 first, so a concurrent read cannot promote the old value again.
+        let removed = self.l2.remove(key).await;
+        self.l1.remove(key).await?;
+        bus::publish_best_effort(self.bus.as_ref(), InvalidationEvent::Remove(key.clone())).await;
+        removed
+    }
+
+    async fn contains_key(&self, key: &Key) -> crate::Result<bool> {
+        if self.l1.contains_key(key).await? {
+            return Ok(true);
+        }
+        self.l2.contains_key(key).await
+    }
+
+    async fn clear(&self) -> crate::Result<()> {
+        let cleared = self.l2.clear().await;
+        self.l1.clear().await?;
+        bus::publish_best_effort(self.bus.as_ref(), InvalidationEvent::Clear).await;
+        cleared
+    }
+
+    /// Serves L1 hits locally and leaves misses to L2, so that an L2 that
+    /// coordinates computations between processes still does. The result is
+    /// promoted into L1.
+    async fn get_or_compute(
+        &self,
+        key: Key,
+        options: SetOptions,
+        compute: Compute<'_>,
+    ) -> crate::Result<Value> {
+        if let Some(value) = self.l1.get(&key).await? {
+            return Ok(value);
+        }
+
+        let local_options = self.local_options(options.clone());
+        let value = self
+            .l2
+            .get_or_compute(key.clone(), options, compute)
+            .await?;
+        if let Err(e) = self
+            .l1
+            .set_with_options(key.clone(), value.clone(), local_options)
+            .await
+        {
+            eprintln!("Warning: failed to promote {} into L1: {}", key, e);
+        }
+        Ok(value)
+    }
+}
+
+/// Applies the invalidations of other processes to the L1 tier only; their
+/// writes already reached the shared L2 tier.
+///
+/// L1 does not track tags or prefixes, so tag and prefix events clear it.
+#[async_trait]
+impl<L1, L2> InvalidationListener for TieredBackend<L1, L2>
+where
+    L1: CacheBackend,
+    L2: CacheBackend,
+{
+    async fn on_invalidation(&self, event: &InvalidationEvent) -> crate::Result<()> {
+        match event {
+            InvalidationEvent::Remove(key) => self.l1.remove(key).await,
+            InvalidationEvent::Tag(_) | InvalidationEvent::Prefix(_) | InvalidationEvent::Clear => {
+                self.l1.clear().await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::memory::MemoryBackend;
+
+    fn tiered() -> TieredBackend<MemoryBackend, MemoryBackend> {
+        TieredBackend::new(MemoryBackend::new(), MemoryBackend::new())
+    }
+
+    #[tokio::test]
+    async fn test_l2_hit_is_promoted_into_l1() {
+        let backend = tiered();
+        let key = "key".to_string();
+        backend.l2().set(key.clone(), vec![1], None).await.unwrap();
+        assert!(!backend.l1().contains_key(&key).await.unwrap());
+
+        assert_eq!(backend.get(&key).await.unwrap(), Some(vec![1]));
+        assert_eq!(backend.l1().get(&key).await.unwrap(), Some(vec![1]));
+    }
+
+    #[tokio::test]
+    async fn test_write_modes() {
+        let key = "key".to_string();
+
+        let through = tiered();
+        through.set(key.clone(), vec![1], None).await.unwrap();
+        assert_eq!(through.l1().get(&key).await.unwrap(), Some(vec![1]));
+        assert_eq!(through.l2().get(&key).await.unwrap(), Some(vec![1]));
+
+        let around = tiered().with_write_mode(WriteMode::WriteAround);
+        around.l1().set(key.clone(), vec![0], None).await.unwrap();
+        around.set(key.clone(), vec![1], None).await.unwrap();
+        assert!(!around.l1().contains_key(&key).await.unwrap());
+        assert_eq!(around.get(&key).await.unwrap(), Some(vec![1]));
+    }
+
+    #[tokio::test]
+    async fn test_l1_ttl_bounds_staleness() {
+        let backend = tiered().with_l1_ttl(Duration::from_millis(50));
+        let key = "key".to_string();
+        backend.set(key.clone(), vec![1], None).await.unwrap();
+
+        // Another process updates L2 directly.
+        backend.l2().set(key.clone(), vec![2], None).await.unwrap();
+        assert_eq!(backend.get(&key).await.unwrap(), Some(vec![1]));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(backend.get(&key).await.unwrap(), Some(vec![2]));
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_clear_reach_both_tiers() {
+        let backend = tiered();
+        for key in ["a", "b"] {
+            backend.set(key.to_string(), vec![1], None).await.unwrap();
+        }
+
+        backend.remove(&"a".to_string()).await.unwrap();
+        assert!(!backend.contains_key(&"a".to_string()).await.unwrap());
+        assert!(backend.contains_key(&"b".to_string()).await.unwrap());
+
+        backend.clear().await.unwrap();
+        assert!(!backend.l1().contains_key(&"b".to_string()).await.unwrap());
+        assert!(!backend.l2().contains_key(&"b".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_bus_drops_stale_l1_copies_in_other_processes() {
+        use crate::bus::UdpBus;
+
+        let a_bus = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let b_bus = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let a_bus = a_bus.with_peer(b_bus.local_addr().unwrap());
+
+        let a = Arc::new(tiered().with_bus(Arc::new(a_bus)));
+        let b = Arc::new(tiered().with_bus(Arc::new(b_bus)));
+        b.spawn_bus_listener().unwrap();
+
+        let key = "key".to_string();
+        b.l1().set(key.clone(), vec![0], None).await.unwrap();
+        a.set(key.clone(), vec![1], None).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while b.l1().contains_key(&key).await.unwrap() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "the stale L1 copy was not invalidated"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_or_compute_fills_both_tiers() {
+        let backend = tiered();
+        let key = "key".to_string();
+
+        let value = backend
+            .get_or_compute(
+                key.clone(),
+                SetOptions::new(),
+                Box::pin(async { Ok(vec![1]) }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(value, vec![1]);
+        assert_eq!(backend.l1().get(&key).await.unwrap(), Some(vec![1]));
+        assert_eq!(backend.l2().get(&key).await.unwrap(), Some(vec![1]));
+
+        let value = backend
+            .get_or_compute(key, SetOptions::new(), Box::pin(async { unreachable!() }))
+            .await
+            .unwrap();
+        assert_eq!(value, vec![1]);
+    }
+}
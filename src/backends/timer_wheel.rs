@@ -0,0 +1,371 @@
+//! Hierarchical timer wheel used to expire in-memory cache entries.
+//!
+//! The wheel tracks one deadline per key with millisecond resolution. It is
+//! organised as [`LEVELS`] levels of [`SLOTS`] slots each; level `n` slots span
+//! `64^n` milliseconds. Scheduling a key is O(1), and advancing the wheel only
+//! touches occupied slots, so each entry is visited at most once per level on
+//! its way to expiring (amortized O(1)).
+//!
+//! Deadlines further out than the levels can tell apart (about 795 days) wait
+//! in an overflow list, which is redistributed into the levels each time the
+//! wheel crosses into the next span of `64^6` milliseconds.
+//!
+//! Every key's slot position is recorded, so rescheduling or cancelling a key
+//! removes its previous slot entry immediately instead of leaving it behind
+//! until that slot fires.
+//!
+//! The wheel only yields *candidates*. Callers must re-check the entry they
+//! own, because an entry's real deadline may have moved (for example through a
+//! time-to-idle refresh) since it was scheduled. A candidate that is not yet
+//! expired should simply be scheduled again with its current deadline.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+
+/// Number of slots per level. Must be 64 to match the occupancy bitmaps.
+const SLOTS: usize = 64;
+/// Number of bits used to index a slot within a level.
+const SLOT_BITS: u32 = 6;
+/// Number of levels; together they cover `64^6` ms (roughly 795 days).
+const LEVELS: usize = 6;
+/// Number of ticks covered by all levels together.
+const WHEEL_SPAN: u64 = 1 << (SLOT_BITS * LEVELS as u32);
+/// The pseudo-level used for keys in the overflow list.
+const OVERFLOW: usize = LEVELS;
+
+/// Where a scheduled key lives in the wheel.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    /// The deadline tick
+    tick: u64,
+    /// The level holding the key, or [`OVERFLOW`]
+    level: usize,
+    /// The slot within the level
+    slot: usize,
+    /// The index of the key within the slot
+    index: usize,
+}
+
+/// A hierarchical timer wheel keyed by cache key.
+#[derive(Debug)]
+pub(crate) struct TimerWheel<K> {
+    /// Reference point for converting instants into ticks
+    origin: Instant,
+    /// The tick up to which the wheel has been advanced
+    elapsed: u64,
+    /// `levels[level][slot]` holds the keys scheduled in that slot
+    levels: Vec<Vec<Vec<K>>>,
+    /// Bitmap of non-empty slots for each level
+    occupied: [u64; LEVELS],
+    /// Keys whose deadline lies beyond the span of the current top level
+    overflow: Vec<K>,
+    /// The deadline and slot position of each scheduled key
+    positions: HashMap<K, Position>,
+}
+
+impl<K> TimerWheel<K>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates an empty wheel anchored at the current instant.
+    pub(crate) fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed: 0,
+            levels: (0..LEVELS)
+                .map(|_| (0..SLOTS).map(|_| Vec::new()).collect())
+                .collect(),
+            occupied: [0; LEVELS],
+            overflow: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of keys with a live deadline.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the number of slot entries across all levels.
+    #[cfg(test)]
+    fn slot_entries(&self) -> usize {
+        self.levels.iter().flatten().map(Vec::len).sum::<usize>() + self.overflow.len()
+    }
+
+    /// Schedules `key` to expire at `deadline`, replacing any earlier schedule.
+    pub(crate) fn schedule(&mut self, key: K, deadline: Instant) {
+        let tick = self.deadline_tick(deadline);
+        if let Some(position) = self.positions.get(&key).copied() {
+            if position.tick == tick {
+                return;
+            }
+            self.detach(position);
+        }
+        self.insert(key, tick);
+    }
+
+    /// Forgets the deadline for `key`, removing its slot entry.
+    pub(crate) fn cancel(&mut self, key: &K) {
+        if let Some(position) = self.positions.remove(key) {
+            self.detach(position);
+        }
+    }
+
+    /// Removes every scheduled key.
+    pub(crate) fn clear(&mut self) {
+        for level in &mut self.levels {
+            for slot in level.iter_mut() {
+                slot.clear();
+            }
+        }
+        self.occupied = [0; LEVELS];
+        self.overflow.clear();
+        self.positions.clear();
+    }
+
+    /// Advances the wheel to `now` and returns the keys whose deadline passed.
+    pub(crate) fn advance(&mut self, now: Instant) -> Vec<K> {
+        let target = self.now_tick(now);
+        let mut expired = Vec::new();
+
+        while let Some((level, slot, slot_start)) = self.next_occupied() {
+            if slot_start > target {
+                break;
+            }
+
+            self.elapsed = self.elapsed.max(slot_start);
+            let entries = match self.levels.get_mut(level) {
+                Some(slots) => {
+                    self.occupied[level] &= !(1u64 << slot);
+                    std::mem::take(&mut slots[slot])
+                }
+                None => std::mem::take(&mut self.overflow),
+            };
+
+            for key in entries {
+                let tick = match self.positions.get(&key) {
+                    Some(position) => position.tick,
+                    None => continue,
+                };
+                if tick <= target {
+                    self.positions.remove(&key);
+                    expired.push(key);
+                } else {
+                    self.insert(key, tick);
+                }
+            }
+        }
+
+        self.elapsed = self.elapsed.max(target);
+        expired
+    }
+
+    /// Places a key into the slot matching its deadline and records where.
+    fn insert(&mut self, key: K, tick: u64) {
+        let level = Self::level_for(self.elapsed, tick);
+        let (slot, entries) = match self.levels.get_mut(level) {
+            Some(slots) => {
+                let slot = ((tick >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1);
+                self.occupied[level] |= 1u64 << slot;
+                (slot, &mut slots[slot])
+            }
+            None => (0, &mut self.overflow),
+        };
+        let position = Position {
+            tick,
+            level,
+            slot,
+            index: entries.len(),
+        };
+        entries.push(key.clone());
+        self.positions.insert(key, position);
+    }
+
+    /// Removes the slot entry at `position` in O(1).
+    ///
+    /// The last entry of the slot takes its place, so its recorded index is
+    /// updated. The caller is responsible for the key's own position.
+    fn detach(&mut self, position: Position) {
+        let entries = match self.levels.get_mut(position.level) {
+            Some(slots) => &mut slots[position.slot],
+            None => &mut self.overflow,
+        };
+        entries.swap_remove(position.index);
+        if let Some(moved) = entries.get(position.index) {
+            if let Some(moved) = self.positions.get_mut(moved) {
+                moved.index = position.index;
+            }
+        }
+        if entries.is_empty() && position.level != OVERFLOW {
+            self.occupied[position.level] &= !(1u64 << position.slot);
+        }
+    }
+
+    /// Finds the earliest occupied slot, returning its level, index and start tick.
+    ///
+    /// Entries on lower levels always expire before entries on higher levels,
+    /// so the first level with an occupied slot holds the next deadline. The
+    /// overflow list comes last and is due once the wheel reaches the next span.
+    fn next_occupied(&self) -> Option<(usize, usize, u64)> {
+        for level in 0..LEVELS {
+            let occupied = self.occupied[level];
+            if occupied == 0 {
+                continue;
+            }
+
+            let shift = SLOT_BITS * level as u32;
+            let current = ((self.elapsed >> shift) as usize) & (SLOTS - 1);
+            let ahead = occupied & (u64::MAX << current);
+            let slot = if ahead != 0 {
+                ahead.trailing_zeros() as usize
+            } else {
+                occupied.trailing_zeros() as usize
+            };
+
+            let level_span = 1u64 << (shift + SLOT_BITS);
+            let base = self.elapsed & !(level_span - 1);
+            let mut start = base + ((slot as u64) << shift);
+            if slot < current {
+                start += level_span;
+            }
+            return Some((level, slot, start.max(self.elapsed)));
+        }
+        if !self.overflow.is_empty() {
+            let next_span = (self.elapsed | (WHEEL_SPAN - 1)) + 1;
+            return Some((OVERFLOW, 0, next_span));
+        }
+        None
+    }
+
+    /// Picks the level whose slot width matches the distance to `tick`.
+    ///
+    /// Returns [`OVERFLOW`] when `tick` lies outside the top level's span, as
+    /// its top-level slot could otherwise alias the slot currently firing.
+    fn level_for(elapsed: u64, tick: u64) -> usize {
+        let masked = (elapsed ^ tick) | (SLOTS as u64 - 1);
+        let significant = 63 - masked.leading_zeros();
+        ((significant / SLOT_BITS) as usize).min(OVERFLOW)
+    }
+
+    /// Converts a deadline to a tick, rounding up so keys never fire early.
+    fn deadline_tick(&self, deadline: Instant) -> u64 {
+        let since = deadline.saturating_duration_since(self.origin);
+        let mut tick = since.as_millis() as u64;
+        if since.subsec_nanos() % 1_000_000 != 0 {
+            tick += 1;
+        }
+        tick.max(self.elapsed)
+    }
+
+    /// Converts the current time to a tick, rounding down.
+    fn now_tick(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.origin).as_millis() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_expires_in_deadline_order() {
+        let mut wheel = TimerWheel::new();
+        let origin = wheel.origin;
+
+        wheel.schedule("a", origin + Duration::from_millis(10));
+        wheel.schedule("b", origin + Duration::from_millis(500));
+        wheel.schedule("c", origin + Duration::from_secs(90));
+
+        assert!(wheel.advance(origin + Duration::from_millis(5)).is_empty());
+        assert_eq!(wheel.advance(origin + Duration::from_millis(10)), vec!["a"]);
+        assert_eq!(
+            wheel.advance(origin + Duration::from_millis(600)),
+            vec!["b"]
+        );
+        assert!(wheel.advance(origin + Duration::from_secs(89)).is_empty());
+        assert_eq!(wheel.advance(origin + Duration::from_secs(91)), vec!["c"]);
+        assert_eq!(wheel.len(), 0);
+    }
+
+    #[test]
+    fn test_reschedule_and_cancel() {
+        let mut wheel = TimerWheel::new();
+        let origin = wheel.origin;
+
+        wheel.schedule("a", origin + Duration::from_millis(10));
+        wheel.schedule("a", origin + Duration::from_millis(100));
+        wheel.schedule("b", origin + Duration::from_millis(10));
+        wheel.cancel(&"b");
+
+        assert!(wheel.advance(origin + Duration::from_millis(50)).is_empty());
+        assert_eq!(
+            wheel.advance(origin + Duration::from_millis(100)),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn test_reschedule_does_not_accumulate_slot_entries() {
+        let mut wheel = TimerWheel::new();
+        let origin = wheel.origin;
+
+        for i in 0..10_000u64 {
+            wheel.schedule(u64::MAX, origin + Duration::from_millis(3_600_000 + i));
+            wheel.schedule(i, origin + Duration::from_secs(60));
+            wheel.cancel(&i);
+        }
+
+        assert_eq!(wheel.len(), 1);
+        assert_eq!(wheel.slot_entries(), 1);
+        assert!(wheel.advance(origin + Duration::from_secs(3600)).is_empty());
+        assert_eq!(
+            wheel.advance(origin + Duration::from_millis(3_610_000)),
+            vec![u64::MAX]
+        );
+    }
+
+    #[test]
+    fn test_deadline_beyond_wheel_span() {
+        let mut wheel = TimerWheel::new();
+        let origin = wheel.origin;
+        let span = Duration::from_millis(WHEEL_SPAN);
+
+        assert!(wheel.advance(origin + Duration::from_millis(5)).is_empty());
+        wheel.schedule("far", origin + span * 3);
+        wheel.schedule("near", origin + Duration::from_secs(60));
+
+        assert_eq!(
+            wheel.advance(origin + Duration::from_secs(61)),
+            vec!["near"]
+        );
+        assert!(wheel.advance(origin + span).is_empty());
+        assert!(wheel.advance(origin + span * 2 + span / 2).is_empty());
+        assert!(wheel
+            .advance(origin + span * 3 - Duration::from_millis(1))
+            .is_empty());
+        assert_eq!(wheel.advance(origin + span * 3), vec!["far"]);
+        assert_eq!(wheel.len(), 0);
+        assert_eq!(wheel.slot_entries(), 0);
+    }
+
+    #[test]
+    fn test_large_jump_expires_everything_due() {
+        let mut wheel = TimerWheel::new();
+        let origin = wheel.origin;
+
+        for i in 0..1000u64 {
+            wheel.schedule(i, origin + Duration::from_millis(i * 37));
+        }
+
+        let mut expired = wheel.advance(origin + Duration::from_millis(18_500));
+        expired.sort_unstable();
+        assert_eq!(expired, (0..=500).collect::<Vec<_>>());
+
+        let mut rest = wheel.advance(origin + Duration::from_secs(3600));
+        rest.sort_unstable();
+        assert_eq!(rest, (501..1000).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,287 @@
+//! # Invalidation Bus
+//!
+//! Broadcasts invalidations between processes that each keep a local cache
+//! tier, so that removing a key, a tag or a prefix in one process also drops
+//! the stale copies held by every other process.
+//!
+//! An [`InvalidationBus`] publishes [`InvalidationEvent`]s and hands out
+//! [`Subscription`]s that receive the events published by *other* processes;
+//! a process never receives its own events back. Two transports are provided:
+//!
+//! - [`UdpBus`] and [`UnixSocketBus`]: datagram sockets between a fixed set of
+//!   peers, with no external service
+//! - [`RedisBus`] (with the `redis-backend` feature): Redis pub/sub on a channel
+//!
+//! Delivery is best effort on both. A subscriber that falls behind receives
+//! [`InvalidationEvent::Clear`] in place of the events it missed, so a local
+//! tier that applies every event never keeps a value it should have dropped.
+//!
+//! [`InvalidationCache::with_bus`](crate::invalidation::InvalidationCache::with_bus)
+//! and [`TieredBackend::with_bus`](crate::backends::tiered::TieredBackend::with_bus)
+//! publish their invalidations and apply the events of other processes. Other
+//! local tiers implement [`InvalidationListener`] and use [`spawn_listener`].
+//! Publishing is best effort for them as well: a failed publish is logged and
+//! does not fail the cache operation that triggered it.
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! use fncache::backends::memory::MemoryBackend;
+//! use fncache::bus::UdpBus;
+//! use fncache::invalidation::{AsyncCacheInvalidation, InvalidationCache, Tag};
+//! use std::sync::Arc;
+//!
+//! # async fn example() -> fncache::Result<()> {
+//! let bus = UdpBus::bind("0.0.0.0:7400")
+//!     .await?
+//!     .with_peer("10.0.0.2:7400".parse().unwrap())
+//!     .with_peer("10.0.0.3:7400".parse().unwrap());
+//!
+//! let cache = Arc::new(InvalidationCache::new(MemoryBackend::new()).with_bus(Arc::new(bus)));
+//! cache.spawn_bus_listener()?;
+//!
+//! // Also drops the entries tagged "user:123" on 10.0.0.2 and 10.0.0.3.
+//! AsyncCacheInvalidation::invalidate_tag(&*cache, &Tag::new("user:123")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "redis-backend")]
+mod redis;
+mod socket;
+
+use crate::backends::Key;
+use crate::invalidation::Tag;
+use crate::{error::Error, Result};
+use async_trait::async_trait;
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "redis-backend")]
+pub use self::redis::RedisBus;
+pub use socket::UdpBus;
+#[cfg(unix)]
+pub use socket::UnixSocketBus;
+
+/// Number of events a subscriber may fall behind before it is sent a `Clear`.
+const SUBSCRIBER_BACKLOG: usize = 1024;
+
+/// Version byte at the start of every encoded event.
+const WIRE_VERSION: u8 = 1;
+
+/// An invalidation broadcast to other processes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InvalidationEvent {
+    /// A single key was removed.
+    Remove(Key),
+    /// Every key with the tag was invalidated.
+    Tag(Tag),
+    /// Every key starting with the prefix was invalidated.
+    Prefix(String),
+    /// The whole cache was cleared.
+    Clear,
+}
+
+impl InvalidationEvent {
+    /// Encodes the event as it is sent on the wire: a version byte, a kind
+    /// byte, the 8-byte big-endian id of the sending bus and the UTF-8 payload.
+    fn encode(&self, origin: u64) -> Vec<u8> {
+        let (kind, payload) = match self {
+            Self::Remove(key) => (0, key.as_str()),
+            Self::Tag(tag) => (1, tag.as_str()),
+            Self::Prefix(prefix) => (2, prefix.as_str()),
+            Self::Clear => (3, ""),
+        };
+        let mut bytes = Vec::with_capacity(10 + payload.len());
+        bytes.push(WIRE_VERSION);
+        bytes.push(kind);
+        bytes.extend_from_slice(&origin.to_be_bytes());
+        bytes.extend_from_slice(payload.as_bytes());
+        bytes
+    }
+
+    /// Decodes an event encoded by [`InvalidationEvent::encode`], returning
+    /// the id of the sending bus along with it.
+    fn decode(bytes: &[u8]) -> Result<(u64, Self)> {
+        if bytes.len() < 10 || bytes[0] != WIRE_VERSION {
+            return Err(Error::Codec("invalid invalidation event header".into()));
+        }
+        let origin = u64::from_be_bytes(bytes[2..10].try_into().expect("8 bytes"));
+        let payload = std::str::from_utf8(&bytes[10..])
+            .map_err(|e| Error::Codec(format!("invalid invalidation event payload: {}", e)))?;
+        let event = match bytes[1] {
+            0 => Self::Remove(payload.to_string()),
+            1 => Self::Tag(Tag::new(payload)),
+            2 => Self::Prefix(payload.to_string()),
+            3 => Self::Clear,
+            kind => {
+                return Err(Error::Codec(format!(
+                    "unknown invalidation event kind: {}",
+                    kind
+                )))
+            }
+        };
+        Ok((origin, event))
+    }
+}
+
+/// Broadcasts invalidation events between processes.
+///
+/// See the [module documentation](self) for the delivery guarantees.
+#[async_trait]
+pub trait InvalidationBus: Send + Sync + Debug {
+    /// Sends an event to every other process on the bus.
+    async fn publish(&self, event: &InvalidationEvent) -> Result<()>;
+
+    /// Returns a subscription that receives the events published by other
+    /// processes from now on.
+    fn subscribe(&self) -> Subscription;
+}
+
+/// A local cache tier that applies invalidation events from other processes.
+#[async_trait]
+pub trait InvalidationListener: Send + Sync {
+    /// Applies `event` to the local tier without publishing it again.
+    async fn on_invalidation(&self, event: &InvalidationEvent) -> Result<()>;
+}
+
+/// Spawns a task that applies every event of `subscription` to `listener`.
+///
+/// The task holds a weak reference and stops once the listener is dropped or
+/// the bus shuts down. Errors while applying an event are logged.
+pub fn spawn_listener<L>(listener: &Arc<L>, mut subscription: Subscription) -> JoinHandle<()>
+where
+    L: InvalidationListener + 'static,
+{
+    let listener = Arc::downgrade(listener);
+    tokio::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            let Some(listener) = listener.upgrade() else {
+                break;
+            };
+            if let Err(e) = listener.on_invalidation(&event).await {
+                eprintln!("Warning: failed to apply {:?}: {}", event, e);
+            }
+        }
+    })
+}
+
+/// Publishes `event` on `bus`, if any, logging instead of failing.
+pub(crate) async fn publish_best_effort(
+    bus: Option<&Arc<dyn InvalidationBus>>,
+    event: InvalidationEvent,
+) {
+    if let Some(bus) = bus {
+        if let Err(e) = bus.publish(&event).await {
+            eprintln!("Warning: failed to publish {:?}: {}", event, e);
+        }
+    }
+}
+
+/// A stream of events received from other processes.
+#[derive(Debug)]
+pub struct Subscription {
+    receiver: broadcast::Receiver<InvalidationEvent>,
+}
+
+impl Subscription {
+    /// Waits for the next event.
+    ///
+    /// Returns [`InvalidationEvent::Clear`] if the subscriber fell behind and
+    /// missed events, and `None` once the bus has shut down.
+    pub async fn recv(&mut self) -> Option<InvalidationEvent> {
+        match self.receiver.recv().await {
+            Ok(event) => Some(event),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                eprintln!(
+                    "Warning: invalidation subscriber missed {} events, clearing",
+                    missed
+                );
+                Some(InvalidationEvent::Clear)
+            }
+            Err(broadcast::error::RecvError::Closed) => None,
+        }
+    }
+}
+
+/// The receiving side shared by the bus implementations: filters out the
+/// bus's own events and fans the rest out to every subscription.
+#[derive(Debug)]
+struct Fanout {
+    /// Random id of this bus, sent with every event it publishes
+    origin: u64,
+    sender: broadcast::Sender<InvalidationEvent>,
+}
+
+impl Fanout {
+    fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(std::process::id().into());
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BACKLOG);
+        Self {
+            origin: hasher.finish(),
+            sender,
+        }
+    }
+
+    fn encode(&self, event: &InvalidationEvent) -> Vec<u8> {
+        event.encode(self.origin)
+    }
+
+    /// Delivers a received message to the subscriptions unless this bus sent it.
+    fn deliver(&self, bytes: &[u8]) {
+        match InvalidationEvent::decode(bytes) {
+            Ok((origin, _)) if origin == self.origin => {}
+            // No subscribers is not an error: the event is simply dropped.
+            Ok((_, event)) => {
+                let _ = self.sender.send(event);
+            }
+            Err(e) => eprintln!("Warning: dropping invalidation message: {}", e),
+        }
+    }
+
+    fn subscribe(&self) -> Subscription {
+        Subscription {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_round_trip() {
+        let events = [
+            InvalidationEvent::Remove("user:1".to_string()),
+            InvalidationEvent::Tag(Tag::new("tenant:7")),
+            InvalidationEvent::Prefix("products:".to_string()),
+            InvalidationEvent::Clear,
+        ];
+        for event in events {
+            let decoded = InvalidationEvent::decode(&event.encode(42)).unwrap();
+            assert_eq!(decoded, (42, event));
+        }
+
+        assert!(InvalidationEvent::decode(b"garbage").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fanout_skips_own_events() {
+        let fanout = Fanout::new();
+        let mut subscription = fanout.subscribe();
+
+        fanout.deliver(&fanout.encode(&InvalidationEvent::Clear));
+        fanout.deliver(&InvalidationEvent::Remove("key".to_string()).encode(fanout.origin + 1));
+
+        assert_eq!(
+            subscription.recv().await,
+            Some(InvalidationEvent::Remove("key".to_string()))
+        );
+    }
+}
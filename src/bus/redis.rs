@@ -0,0 +1,162 @@
+//! Invalidation bus over Redis pub/sub.
+
+use super::{Fanout, InvalidationBus, InvalidationEvent, Subscription};
+use crate::{error::Error, Result};
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::{aio::ConnectionManager, aio::PubSub, Client, RedisError};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::task::JoinHandle;
+
+/// Delay between attempts to re-establish a lost subscription.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+fn redis_error(e: RedisError) -> Error {
+    Error::Backend(format!("Redis error: {}", e))
+}
+
+async fn subscribe(client: &Client, channel: &str) -> Result<PubSub> {
+    let mut pubsub = client
+        .get_async_connection()
+        .await
+        .map_err(redis_error)?
+        .into_pubsub();
+    pubsub.subscribe(channel).await.map_err(redis_error)?;
+    Ok(pubsub)
+}
+
+/// An invalidation bus over a Redis pub/sub channel.
+///
+/// Every process subscribed to the channel receives the events the others
+/// publish. Redis does not buffer pub/sub messages, so when the subscription
+/// connection drops it is re-established and subscribers are sent
+/// [`InvalidationEvent::Clear`] for whatever they may have missed meanwhile.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use fncache::bus::{InvalidationBus, InvalidationEvent, RedisBus};
+///
+/// # async fn example() -> fncache::Result<()> {
+/// let bus = RedisBus::new("redis://127.0.0.1:6379", "myapp:invalidations").await?;
+/// bus.publish(&InvalidationEvent::Prefix("user:123:".to_string())).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisBus {
+    manager: ConnectionManager,
+    channel: String,
+    fanout: Arc<Fanout>,
+    receiver: JoinHandle<()>,
+}
+
+impl RedisBus {
+    /// Connects to Redis and subscribes to `channel`.
+    ///
+    /// # Errors
+    /// Returns an error if the connection or the subscription fails.
+    pub async fn new(redis_url: &str, channel: &str) -> Result<Self> {
+        let client = Client::open(redis_url)
+            .map_err(|e| Error::Backend(format!("Failed to create Redis client: {}", e)))?;
+        let manager = client
+            .get_connection_manager()
+            .await
+            .map_err(|e| Error::Backend(format!("Failed to connect to Redis: {}", e)))?;
+        let mut pubsub = subscribe(&client, channel).await?;
+        let fanout = Arc::new(Fanout::new());
+
+        let receiver = tokio::spawn({
+            let fanout = Arc::clone(&fanout);
+            let channel = channel.to_string();
+            async move {
+                loop {
+                    {
+                        let mut messages = pubsub.on_message();
+                        while let Some(message) = messages.next().await {
+                            fanout.deliver(message.get_payload_bytes());
+                        }
+                    }
+
+                    eprintln!("Warning: lost Redis invalidation subscription, reconnecting");
+                    pubsub = loop {
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                        match subscribe(&client, &channel).await {
+                            Ok(pubsub) => break pubsub,
+                            Err(e) => eprintln!("Warning: {}", e),
+                        }
+                    };
+                    let _ = fanout.sender.send(InvalidationEvent::Clear);
+                }
+            }
+        });
+
+        Ok(Self {
+            manager,
+            channel: channel.to_string(),
+            fanout,
+            receiver,
+        })
+    }
+}
+
+impl fmt::Debug for RedisBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisBus")
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
+
+impl Drop for RedisBus {
+    fn drop(&mut self) {
+        self.receiver.abort();
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for RedisBus {
+    async fn publish(&self, event: &InvalidationEvent) -> Result<()> {
+        let mut conn = self.manager.clone();
+        redis::cmd("PUBLISH")
+            .arg(&self.channel)
+            .arg(self.fanout.encode(event))
+            .query_async::<_, i64>(&mut conn)
+            .await
+            .map(|_| ())
+            .map_err(redis_error)
+    }
+
+    fn subscribe(&self) -> Subscription {
+        self.fanout.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[tokio::test]
+    #[serial]
+    #[ignore]
+    async fn test_redis_bus_delivers_to_other_processes() -> Result<()> {
+        let url = "redis://127.0.0.1:6379";
+        let a = RedisBus::new(url, "test:invalidations").await?;
+        let b = RedisBus::new(url, "test:invalidations").await?;
+
+        let mut at_a = a.subscribe();
+        let mut at_b = b.subscribe();
+        let event = InvalidationEvent::Remove("key".to_string());
+        a.publish(&event).await?;
+
+        let received = tokio::time::timeout(Duration::from_secs(5), at_b.recv()).await;
+        assert_eq!(received.ok().flatten(), Some(event));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), at_a.recv())
+                .await
+                .is_err(),
+            "a bus must not receive its own events"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+//! Invalidation buses over UDP and Unix datagram sockets.
+//!
+//! Every process binds its own socket and lists the addresses of its peers.
+//! Each event is sent as a single datagram to every peer, so there is no
+//! broker to run, but membership is static and delivery is best effort.
+
+use super::{Fanout, InvalidationBus, InvalidationEvent, Subscription};
+use crate::{error::Error, Result};
+use async_trait::async_trait;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{ToSocketAddrs, UdpSocket};
+use tokio::task::JoinHandle;
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
+
+/// Largest datagram the buses accept.
+const MAX_DATAGRAM: usize = 64 * 1024;
+
+fn socket_error(e: std::io::Error) -> Error {
+    Error::Backend(format!("Invalidation socket error: {}", e))
+}
+
+/// Keeps the first of the send errors, so that one unreachable peer does not
+/// stop the event from reaching the others.
+fn first_error(first: &mut Option<Error>, sent: std::io::Result<usize>) {
+    if let Err(e) = sent {
+        first.get_or_insert(socket_error(e));
+    }
+}
+
+/// An invalidation bus over UDP.
+///
+/// # Examples
+///
+/// ```no_run
+/// use fncache::bus::{InvalidationBus, InvalidationEvent, UdpBus};
+///
+/// # async fn example() -> fncache::Result<()> {
+/// let bus = UdpBus::bind("0.0.0.0:7400")
+///     .await?
+///     .with_peer("10.0.0.2:7400".parse().unwrap());
+///
+/// bus.publish(&InvalidationEvent::Remove("user:123".to_string())).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct UdpBus {
+    socket: Arc<UdpSocket>,
+    peers: Vec<SocketAddr>,
+    fanout: Arc<Fanout>,
+    receiver: JoinHandle<()>,
+}
+
+impl UdpBus {
+    /// Binds a UDP socket and starts receiving events on it.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub async fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        let socket = Arc::new(UdpSocket::bind(addr).await.map_err(socket_error)?);
+        let fanout = Arc::new(Fanout::new());
+
+        let receiver = tokio::spawn({
+            let socket = Arc::clone(&socket);
+            let fanout = Arc::clone(&fanout);
+            async move {
+                let mut buf = vec![0; MAX_DATAGRAM];
+                loop {
+                    match socket.recv_from(&mut buf).await {
+                        Ok((len, _)) => fanout.deliver(&buf[..len]),
+                        Err(e) => eprintln!("Warning: invalidation bus receive failed: {}", e),
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            socket,
+            peers: Vec::new(),
+            fanout,
+            receiver,
+        })
+    }
+
+    /// Adds a peer that events are published to.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    pub fn with_peer(mut self, addr: SocketAddr) -> Self {
+        self.peers.push(addr);
+        self
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket.local_addr().map_err(socket_error)
+    }
+}
+
+impl Drop for UdpBus {
+    fn drop(&mut self) {
+        self.receiver.abort();
+    }
+}
+
+#[async_trait]
+impl InvalidationBus for UdpBus {
+    async fn publish(&self, event: &InvalidationEvent) -> Result<()> {
+        let bytes = self.fanout.encode(event);
+        let mut error = None;
+        for peer in &self.peers {
+            first_error(&mut error, self.socket.send_to(&bytes, peer).await);
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    fn subscribe(&self) -> Subscription {
+        self.fanout.subscribe()
+    }
+}
+
+/// An invalidation bus over Unix datagram sockets, for processes on the
+/// same host.
+///
+/// # Examples
+///
+/// ```no_run
+/// use fncache::bus::{InvalidationBus, InvalidationEvent, UnixSocketBus};
+///
+/// # async fn example() -> fncache::Result<()> {
+/// let bus = UnixSocketBus::bind("/run/myapp/cache-1.sock")
+///     .await?
+///     .with_peer("/run/myapp/cache-2.sock");
+///
+/// bus.publish(&InvalidationEvent::Clear).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct UnixSocketBus {
+    socket: Arc<UnixDatagram>,
+    path: PathBuf,
+    peers: Vec<PathBuf>,
+    fanout: Arc<Fanout>,
+    receiver: JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl UnixSocketBus {
+    /// Binds a Unix datagram socket at `path` and starts receiving events on it.
+    ///
+    /// A socket file left at `path` by a previous run is replaced, and the
+    /// file is removed when the bus is dropped. Must be called from within a
+    /// Tokio runtime.
+    pub async fn bind(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(socket_error(e)),
+        }
+        let socket = Arc::new(UnixDatagram::bind(&path).map_err(socket_error)?);
+        let fanout = Arc::new(Fanout::new());
+
+        let receiver = tokio::spawn({
+            let socket = Arc::clone(&socket);
+            let fanout = Arc::clone(&fanout);
+            async move {
+                let mut buf = vec![0; MAX_DATAGRAM];
+                loop {
+                    match socket.recv(&mut buf).await {
+                        Ok(len) => fanout.deliver(&buf[..len]),
+                        Err(e) => eprintln!("Warning: invalidation bus receive failed: {}", e),
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            socket,
+            path,
+            peers: Vec::new(),
+            fanout,
+            receiver,
+        })
+    }
+
+    /// Adds the socket path of a peer that events are published to.
+    ///
+    /// This is a builder method that returns `self` for method chaining.
+    pub fn with_peer(mut self, path: impl AsRef<Path>) -> Self {
+        self.peers.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Returns the path the socket is bound to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(unix)]
+impl Drop for UnixSocketBus {
+    fn drop(&mut self) {
+        self.receiver.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+#[async_trait]
+impl InvalidationBus for UnixSocketBus {
+    async fn publish(&self, event: &InvalidationEvent) -> Result<()> {
+        let bytes = self.fanout.encode(event);
+        let mut error = None;
+        for peer in &self.peers {
+            first_error(&mut error, self.socket.send_to(&bytes, peer).await);
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    fn subscribe(&self) -> Subscription {
+        self.fanout.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    async fn next(subscription: &mut Subscription) -> Option<InvalidationEvent> {
+        timeout(Duration::from_secs(5), subscription.recv())
+            .await
+            .expect("timed out waiting for an invalidation event")
+    }
+
+    #[tokio::test]
+    async fn test_udp_bus_delivers_to_peers() {
+        let a = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let b = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        let a = a.with_peer(b_addr);
+        let b = b.with_peer(a_addr);
+
+        let mut at_b = b.subscribe();
+        let event = InvalidationEvent::Prefix("user:".to_string());
+        a.publish(&event).await.unwrap();
+        assert_eq!(next(&mut at_b).await, Some(event));
+
+        let mut at_a = a.subscribe();
+        b.publish(&InvalidationEvent::Clear).await.unwrap();
+        assert_eq!(next(&mut at_a).await, Some(InvalidationEvent::Clear));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_bus_delivers_to_peers() {
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let a_path = dir.join(format!("fncache-bus-test-{}-a.sock", id));
+        let b_path = dir.join(format!("fncache-bus-test-{}-b.sock", id));
+
+        let a = UnixSocketBus::bind(&a_path)
+            .await
+            .unwrap()
+            .with_peer(&b_path);
+        let b = UnixSocketBus::bind(&b_path).await.unwrap();
+
+        let mut at_b = b.subscribe();
+        let event = InvalidationEvent::Remove("key".to_string());
+        a.publish(&event).await.unwrap();
+        assert_eq!(next(&mut at_b).await, Some(event));
+
+        drop(a);
+        assert!(!a_path.exists());
+    }
+}
@@ -34,6 +34,10 @@ pub enum Error {
     #[error("Key not found in registered warmers")]
     KeyNotFound,
 
+    /// A configuration value was invalid.
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
     /// An error that doesn't fit into other categories.
     #[error("Cache error: {0}")]
     Other(String),
@@ -0,0 +1,295 @@
+//! ARC eviction policy.
+//!
+//! ARC keeps two resident LRU lists: `T1` for keys seen once recently and
+//! `T2` for keys seen at least twice. Each has a ghost list (`B1`, `B2`) that
+//! remembers recently evicted keys without their values. Re-inserting a ghost
+//! key tells the policy which list was evicted too eagerly, and it adapts the
+//! target size `p` of `T1` accordingly, balancing recency against frequency.
+//!
+//! The policy is not told the cache capacity and uses the number of resident
+//! keys when an eviction is requested. Because the cache asks for room before
+//! it inserts the new key, the adaptation of `p` takes effect from the next
+//! eviction on rather than the current one.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use super::list::{List, Slab};
+use super::{lock, EvictionPolicy, EvictionResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    /// Resident, seen once
+    T1,
+    /// Resident, seen at least twice
+    T2,
+    /// Ghost evicted from `T1`
+    B1,
+    /// Ghost evicted from `T2`
+    B2,
+}
+
+#[derive(Debug)]
+struct ArcNode<K> {
+    key: K,
+    segment: Segment,
+}
+
+#[derive(Debug)]
+struct ArcState<K> {
+    nodes: Slab<ArcNode<K>>,
+    /// Resident and ghost keys
+    index: HashMap<K, usize>,
+    t1: List,
+    t2: List,
+    b1: List,
+    b2: List,
+    /// Target size of `T1`
+    p: usize,
+}
+
+impl<K: Eq + Hash + Clone> ArcState<K> {
+    fn list(&mut self, segment: Segment) -> (&mut List, &mut Slab<ArcNode<K>>) {
+        let list = match segment {
+            Segment::T1 => &mut self.t1,
+            Segment::T2 => &mut self.t2,
+            Segment::B1 => &mut self.b1,
+            Segment::B2 => &mut self.b2,
+        };
+        (list, &mut self.nodes)
+    }
+
+    fn resident(&self) -> usize {
+        self.t1.len() + self.t2.len()
+    }
+
+    /// Moves node `idx` to the most recently used end of `segment`.
+    fn relink(&mut self, idx: usize, segment: Segment) {
+        let (from, nodes) = self.list(self.nodes.get(idx).segment);
+        from.unlink(nodes, idx);
+        let (to, nodes) = self.list(segment);
+        to.push_back(nodes, idx);
+        self.nodes.get_mut(idx).segment = segment;
+    }
+
+    /// Forgets node `idx` entirely.
+    fn remove(&mut self, idx: usize) -> K {
+        let (list, nodes) = self.list(self.nodes.get(idx).segment);
+        list.unlink(nodes, idx);
+        let key = self.nodes.remove(idx).key;
+        self.index.remove(&key);
+        key
+    }
+
+    /// Handles the insertion of a key that is currently a ghost.
+    fn readmit(&mut self, idx: usize) {
+        let capacity = self.resident() + 1;
+        match self.nodes.get(idx).segment {
+            Segment::B1 => {
+                let delta = (self.b2.len() / self.b1.len()).max(1);
+                self.p = (self.p + delta).min(capacity);
+            }
+            Segment::B2 => {
+                let delta = (self.b1.len() / self.b2.len()).max(1);
+                self.p = self.p.saturating_sub(delta);
+            }
+            Segment::T1 | Segment::T2 => {}
+        }
+        self.relink(idx, Segment::T2);
+    }
+
+    fn evict_one(&mut self) -> Option<K> {
+        let capacity = self.resident();
+        let from_t1 = !self.t1.is_empty() && (self.t1.len() > self.p || self.t2.is_empty());
+        let (idx, ghost) = if from_t1 {
+            (self.t1.front()?, Segment::B1)
+        } else {
+            (self.t2.front()?, Segment::B2)
+        };
+        self.relink(idx, ghost);
+
+        // Keep |T1| + |B1| <= c and the whole directory within 2c.
+        while self.t1.len() + self.b1.len() > capacity {
+            match self.b1.front() {
+                Some(oldest) => self.remove(oldest),
+                None => break,
+            };
+        }
+        while self.index.len() > 2 * capacity {
+            match self.b2.front().or_else(|| self.b1.front()) {
+                Some(oldest) => self.remove(oldest),
+                None => break,
+            };
+        }
+
+        Some(self.nodes.get(idx).key.clone())
+    }
+}
+
+/// ARC (Adaptive Replacement Cache) eviction policy.
+///
+/// Self-tunes between recency and frequency based on which recently evicted
+/// keys are requested again. Scans only churn the `T1` list, so the frequently
+/// used keys in `T2` survive them. Every operation is O(1).
+#[derive(Debug)]
+pub struct ArcPolicy<K: Hash + Eq + Clone + Send + Sync + std::fmt::Debug> {
+    state: Mutex<ArcState<K>>,
+}
+
+impl<K> ArcPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    /// Creates a new ARC eviction policy.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(ArcState {
+                nodes: Slab::new(),
+                index: HashMap::new(),
+                t1: List::new(),
+                t2: List::new(),
+                b1: List::new(),
+                b2: List::new(),
+                p: 0,
+            }),
+        }
+    }
+}
+
+impl<K> Default for ArcPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for ArcPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn on_insert(&self, key: &K, _value: &V) {
+        let state = &mut *lock(&self.state);
+        match state.index.get(key) {
+            Some(&idx) => state.readmit(idx),
+            None => {
+                let idx = state.nodes.insert(ArcNode {
+                    key: key.clone(),
+                    segment: Segment::T1,
+                });
+                state.t1.push_back(&mut state.nodes, idx);
+                state.index.insert(key.clone(), idx);
+            }
+        }
+    }
+
+    fn on_access(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            if matches!(state.nodes.get(idx).segment, Segment::T1 | Segment::T2) {
+                state.relink(idx, Segment::T2);
+            }
+        }
+    }
+
+    fn on_remove(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            if matches!(state.nodes.get(idx).segment, Segment::T1 | Segment::T2) {
+                state.remove(idx);
+            }
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.resident()));
+
+        while keys_to_evict.len() < count {
+            let Some(key) = state.evict_one() else {
+                break;
+            };
+            keys_to_evict.push(key);
+        }
+
+        EvictionResult { keys_to_evict }
+    }
+
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!(
+            "ARC Policy: {} entries (t1 {}, t2 {}, b1 {}, b2 {} ghosts, p = {})\n",
+            state.resident(),
+            state.t1.len(),
+            state.t2.len(),
+            state.b1.len(),
+            state.b2.len(),
+            state.p
+        );
+        for (name, list) in [
+            ("t1", &state.t1),
+            ("t2", &state.t2),
+            ("b1", &state.b1),
+            ("b2", &state.b2),
+        ] {
+            for idx in list.iter(&state.nodes) {
+                result.push_str(&format!("  {}: {:?}\n", name, state.nodes.get(idx).key));
+            }
+        }
+        result
+    }
+
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.nodes.clear();
+        state.index.clear();
+        state.t1.clear();
+        state.t2.clear();
+        state.b1.clear();
+        state.b2.clear();
+        state.p = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evict_one(policy: &ArcPolicy<u32>) -> u32 {
+        <ArcPolicy<u32> as EvictionPolicy<u32, ()>>::evict(policy, 1).keys_to_evict[0]
+    }
+
+    #[test]
+    fn test_frequent_keys_survive_a_scan() {
+        let policy: ArcPolicy<u32> = ArcPolicy::new();
+        for key in 0..10 {
+            policy.on_insert(&key, &());
+        }
+        for key in 0..5 {
+            <ArcPolicy<u32> as EvictionPolicy<u32, ()>>::on_access(&policy, &key);
+        }
+
+        for key in 100..200 {
+            let evicted = evict_one(&policy);
+            assert!(evicted >= 5, "frequent key {} was evicted", evicted);
+            policy.on_insert(&key, &());
+        }
+    }
+
+    #[test]
+    fn test_ghost_hit_adapts_target() {
+        let policy: ArcPolicy<u32> = ArcPolicy::new();
+        for key in 0..4 {
+            policy.on_insert(&key, &());
+        }
+
+        assert_eq!(evict_one(&policy), 0);
+        policy.on_insert(&0, &());
+
+        let state = lock(&policy.state);
+        assert_eq!(state.p, 1);
+        assert_eq!(state.nodes.get(state.index[&0]).segment, Segment::T2);
+    }
+}
@@ -0,0 +1,107 @@
+//! FIFO eviction policy.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use super::list::{List, Slab};
+use super::{lock, EvictionPolicy, EvictionResult};
+
+#[derive(Debug)]
+struct FifoState<K> {
+    nodes: Slab<K>,
+    index: HashMap<K, usize>,
+    /// Oldest insertion at the front
+    queue: List,
+}
+
+/// FIFO (First In, First Out) eviction policy.
+///
+/// Discards items in the order they were first inserted, ignoring reads and
+/// updates. This is the cheapest policy and works well when every item is
+/// equally likely to be reused.
+#[derive(Debug)]
+pub struct FifoPolicy<K: Hash + Eq + Clone + Send + Sync + std::fmt::Debug> {
+    state: Mutex<FifoState<K>>,
+}
+
+impl<K> FifoPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    /// Creates a new FIFO eviction policy.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(FifoState {
+                nodes: Slab::new(),
+                index: HashMap::new(),
+                queue: List::new(),
+            }),
+        }
+    }
+}
+
+impl<K> Default for FifoPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for FifoPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn on_insert(&self, key: &K, _value: &V) {
+        let state = &mut *lock(&self.state);
+        if !state.index.contains_key(key) {
+            let idx = state.nodes.insert(key.clone());
+            state.queue.push_back(&mut state.nodes, idx);
+            state.index.insert(key.clone(), idx);
+        }
+    }
+
+    fn on_access(&self, _key: &K) {}
+
+    fn on_remove(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(idx) = state.index.remove(key) {
+            state.queue.unlink(&mut state.nodes, idx);
+            state.nodes.remove(idx);
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.queue.len()));
+
+        while keys_to_evict.len() < count {
+            let Some(idx) = state.queue.pop_front(&mut state.nodes) else {
+                break;
+            };
+            let key = state.nodes.remove(idx);
+            state.index.remove(&key);
+            keys_to_evict.push(key);
+        }
+
+        EvictionResult { keys_to_evict }
+    }
+
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!("FIFO Policy: {} entries\n", state.queue.len());
+        for (i, idx) in state.queue.iter(&state.nodes).enumerate() {
+            result.push_str(&format!("  {}: {:?}\n", i, state.nodes.get(idx)));
+        }
+        result
+    }
+
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.nodes.clear();
+        state.index.clear();
+        state.queue.clear();
+    }
+}
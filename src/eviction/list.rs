@@ -0,0 +1,258 @@
+//! Index-based intrusive doubly-linked lists.
+//!
+//! Eviction policies need to move keys between the ends of ordered lists in
+//! O(1). Instead of pointer-based nodes, every node lives in a [`Slab`] and is
+//! addressed by its index; a [`List`] only stores its head, tail and length
+//! while the `prev`/`next` links live inside the slab slots. A node belongs to
+//! at most one list at a time, which lets a single slab back several lists
+//! (for example the frequency buckets of an LFU policy).
+
+/// Sentinel index used for "no node".
+const NIL: usize = usize::MAX;
+
+#[derive(Debug)]
+struct Slot<T> {
+    value: Option<T>,
+    prev: usize,
+    next: usize,
+}
+
+/// Storage for list nodes, addressed by stable indices.
+#[derive(Debug)]
+pub(crate) struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> Slab<T> {
+    /// Creates an empty slab.
+    pub(crate) fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Stores `value` in an unlinked node and returns its index.
+    pub(crate) fn insert(&mut self, value: T) -> usize {
+        let slot = Slot {
+            value: Some(value),
+            prev: NIL,
+            next: NIL,
+        };
+        match self.free.pop() {
+            Some(idx) => {
+                self.slots[idx] = slot;
+                idx
+            }
+            None => {
+                self.slots.push(slot);
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    /// Frees the node at `idx` and returns its value.
+    ///
+    /// The node must already be unlinked from any list.
+    pub(crate) fn remove(&mut self, idx: usize) -> T {
+        let slot = &mut self.slots[idx];
+        debug_assert!(slot.prev == NIL && slot.next == NIL);
+        self.free.push(idx);
+        slot.value.take().expect("slab slot is vacant")
+    }
+
+    /// Returns a reference to the value at `idx`.
+    pub(crate) fn get(&self, idx: usize) -> &T {
+        self.slots[idx].value.as_ref().expect("slab slot is vacant")
+    }
+
+    /// Returns a mutable reference to the value at `idx`.
+    pub(crate) fn get_mut(&mut self, idx: usize) -> &mut T {
+        self.slots[idx].value.as_mut().expect("slab slot is vacant")
+    }
+
+    /// Removes every node.
+    pub(crate) fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+    }
+}
+
+/// A doubly-linked list whose links are stored in a [`Slab`].
+#[derive(Debug)]
+pub(crate) struct List {
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl List {
+    /// Creates an empty list.
+    pub(crate) const fn new() -> Self {
+        Self {
+            head: NIL,
+            tail: NIL,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of nodes in the list.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the list has no nodes.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of the first node.
+    pub(crate) fn front(&self) -> Option<usize> {
+        (self.head != NIL).then_some(self.head)
+    }
+
+    /// Returns the node following `idx`.
+    pub(crate) fn next<T>(&self, slab: &Slab<T>, idx: usize) -> Option<usize> {
+        let next = slab.slots[idx].next;
+        (next != NIL).then_some(next)
+    }
+
+    /// Returns the node preceding `idx`.
+    pub(crate) fn prev<T>(&self, slab: &Slab<T>, idx: usize) -> Option<usize> {
+        let prev = slab.slots[idx].prev;
+        (prev != NIL).then_some(prev)
+    }
+
+    /// Appends the unlinked node `idx` to the back of the list.
+    pub(crate) fn push_back<T>(&mut self, slab: &mut Slab<T>, idx: usize) {
+        slab.slots[idx].prev = self.tail;
+        slab.slots[idx].next = NIL;
+        match self.tail {
+            NIL => self.head = idx,
+            tail => slab.slots[tail].next = idx,
+        }
+        self.tail = idx;
+        self.len += 1;
+    }
+
+    /// Prepends the unlinked node `idx` to the front of the list.
+    pub(crate) fn push_front<T>(&mut self, slab: &mut Slab<T>, idx: usize) {
+        slab.slots[idx].prev = NIL;
+        slab.slots[idx].next = self.head;
+        match self.head {
+            NIL => self.tail = idx,
+            head => slab.slots[head].prev = idx,
+        }
+        self.head = idx;
+        self.len += 1;
+    }
+
+    /// Inserts the unlinked node `idx` directly after `anchor`.
+    pub(crate) fn insert_after<T>(&mut self, slab: &mut Slab<T>, anchor: usize, idx: usize) {
+        let next = slab.slots[anchor].next;
+        slab.slots[idx].prev = anchor;
+        slab.slots[idx].next = next;
+        slab.slots[anchor].next = idx;
+        match next {
+            NIL => self.tail = idx,
+            next => slab.slots[next].prev = idx,
+        }
+        self.len += 1;
+    }
+
+    /// Detaches node `idx` from the list, leaving it allocated in the slab.
+    pub(crate) fn unlink<T>(&mut self, slab: &mut Slab<T>, idx: usize) {
+        let Slot { prev, next, .. } = slab.slots[idx];
+        match prev {
+            NIL => self.head = next,
+            prev => slab.slots[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => slab.slots[next].prev = prev,
+        }
+        slab.slots[idx].prev = NIL;
+        slab.slots[idx].next = NIL;
+        self.len -= 1;
+    }
+
+    /// Detaches and returns the first node.
+    pub(crate) fn pop_front<T>(&mut self, slab: &mut Slab<T>) -> Option<usize> {
+        let head = self.front()?;
+        self.unlink(slab, head);
+        Some(head)
+    }
+
+    /// Moves node `idx`, which must be in this list, to the back.
+    pub(crate) fn move_to_back<T>(&mut self, slab: &mut Slab<T>, idx: usize) {
+        if self.tail != idx {
+            self.unlink(slab, idx);
+            self.push_back(slab, idx);
+        }
+    }
+
+    /// Iterates over node indices from front to back.
+    pub(crate) fn iter<'a, T>(&self, slab: &'a Slab<T>) -> impl Iterator<Item = usize> + 'a {
+        let mut cursor = self.head;
+        std::iter::from_fn(move || {
+            if cursor == NIL {
+                return None;
+            }
+            let current = cursor;
+            cursor = slab.slots[current].next;
+            Some(current)
+        })
+    }
+
+    /// Forgets every node without touching the slab.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(list: &List, slab: &Slab<u32>) -> Vec<u32> {
+        list.iter(slab).map(|idx| *slab.get(idx)).collect()
+    }
+
+    #[test]
+    fn test_push_unlink_and_reuse() {
+        let mut slab = Slab::new();
+        let mut list = List::new();
+
+        let a = slab.insert(1);
+        let b = slab.insert(2);
+        let c = slab.insert(3);
+        list.push_back(&mut slab, a);
+        list.push_back(&mut slab, b);
+        list.push_front(&mut slab, c);
+        assert_eq!(values(&list, &slab), vec![3, 1, 2]);
+
+        list.move_to_back(&mut slab, c);
+        assert_eq!(values(&list, &slab), vec![1, 2, 3]);
+
+        list.unlink(&mut slab, b);
+        assert_eq!(slab.remove(b), 2);
+        assert_eq!(values(&list, &slab), vec![1, 3]);
+
+        let d = slab.insert(4);
+        assert_eq!(d, b);
+        list.insert_after(&mut slab, a, d);
+        assert_eq!(values(&list, &slab), vec![1, 4, 3]);
+
+        assert_eq!(list.pop_front(&mut slab), Some(a));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.prev(&slab, c), Some(d));
+        assert_eq!(list.next(&slab, c), None);
+    }
+}
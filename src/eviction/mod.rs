@@ -1,12 +1,35 @@
 //! Eviction policy implementations.
 //!
 //! This module provides various cache eviction policies that determine which items
-//! to remove when the cache reaches capacity.
+//! to remove when the cache reaches capacity. Built-in policies are selected with
+//! [`EvictionPolicyKind`]; custom ones implement [`EvictionPolicy`].
+
+mod arc;
+mod fifo;
+mod list;
+mod priority;
+mod random;
+mod sieve;
+mod tinylfu;
+mod two_queue;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
-use std::time::Instant;
+use crate::backends::{Key, Priority, Value};
+use crate::error::Error;
+use list::{List, Slab};
 
-use std::hash::Hash;
-use std::sync::Arc;
+pub use arc::ArcPolicy;
+pub use fifo::FifoPolicy;
+pub use priority::PriorityPolicy;
+pub use random::RandomPolicy;
+pub use sieve::SievePolicy;
+pub use tinylfu::TinyLfuPolicy;
+pub use two_queue::TwoQueuePolicy;
 
 /// Result of an eviction policy decision.
 pub struct EvictionResult<K> {
@@ -22,6 +45,19 @@ pub trait EvictionPolicy<K, V>: Send + Sync + std::fmt::Debug {
     /// Called when an item is inserted into the cache.
     fn on_insert(&self, key: &K, value: &V);
 
+    /// Called instead of `on_insert` when the cache inserts an item with a
+    /// [`Priority`].
+    ///
+    /// `evict` must never select a `Pinned` key, and should only select a
+    /// `High` key when no `Normal` key is left. The default implementation
+    /// forgets pinned keys and treats `High` like `Normal`.
+    fn on_insert_with_priority(&self, key: &K, value: &V, priority: Priority) {
+        match priority {
+            Priority::Normal | Priority::High => self.on_insert(key, value),
+            Priority::Pinned => self.on_remove(key),
+        }
+    }
+
     /// Called when an item is accessed from the cache.
     fn on_access(&self, key: &K);
 
@@ -42,12 +78,26 @@ pub trait EvictionPolicy<K, V>: Send + Sync + std::fmt::Debug {
     fn reset(&self);
 }
 
+/// Locks a policy's state, recovering it if another thread panicked.
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// LRU (Least Recently Used) eviction policy.
 ///
-/// Discards the least recently used items first.
+/// Discards the least recently used items first. Keys are kept in a
+/// doubly-linked recency list, so every operation is O(1).
 #[derive(Debug)]
 pub struct LruPolicy<K: std::hash::Hash + std::cmp::Eq + Clone + Send + Sync + std::fmt::Debug> {
-    access_order: dashmap::DashMap<K, Instant>,
+    state: Mutex<LruState<K>>,
+}
+
+#[derive(Debug)]
+struct LruState<K> {
+    nodes: Slab<K>,
+    index: HashMap<K, usize>,
+    /// Least recently used at the front, most recently used at the back
+    order: List,
 }
 
 impl<K> LruPolicy<K>
@@ -57,77 +107,196 @@ where
     /// Creates a new LRU eviction policy.
     pub fn new() -> Self {
         Self {
-            access_order: dashmap::DashMap::new(),
+            state: Mutex::new(LruState {
+                nodes: Slab::new(),
+                index: HashMap::new(),
+                order: List::new(),
+            }),
         }
     }
 }
 
+impl<K> Default for LruPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<K, V> EvictionPolicy<K, V> for LruPolicy<K>
 where
     K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
 {
     fn on_insert(&self, key: &K, _value: &V) {
-        self.access_order.insert(key.clone(), Instant::now());
+        let state = &mut *lock(&self.state);
+        match state.index.get(key) {
+            Some(&idx) => state.order.move_to_back(&mut state.nodes, idx),
+            None => {
+                let idx = state.nodes.insert(key.clone());
+                state.order.push_back(&mut state.nodes, idx);
+                state.index.insert(key.clone(), idx);
+            }
+        }
     }
 
     fn on_access(&self, key: &K) {
-        if self.access_order.contains_key(key) {
-            self.access_order.insert(key.clone(), Instant::now());
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            state.order.move_to_back(&mut state.nodes, idx);
         }
     }
 
     fn on_remove(&self, key: &K) {
-        self.access_order.remove(key);
+        let state = &mut *lock(&self.state);
+        if let Some(idx) = state.index.remove(key) {
+            state.order.unlink(&mut state.nodes, idx);
+            state.nodes.remove(idx);
+        }
     }
 
     fn evict(&self, count: usize) -> EvictionResult<K> {
-        let mut entries: Vec<(K, Instant)> = self
-            .access_order
-            .iter()
-            .map(|entry| (entry.key().clone(), *entry.value()))
-            .collect();
-
-        entries.sort_by(|a, b| a.1.cmp(&b.1));
-
-        let keys_to_evict = entries
-            .into_iter()
-            .take(count)
-            .map(|(key, _)| {
-                self.access_order.remove(&key);
-                key
-            })
-            .collect();
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.order.len()));
+
+        while keys_to_evict.len() < count {
+            let Some(idx) = state.order.pop_front(&mut state.nodes) else {
+                break;
+            };
+            let key = state.nodes.remove(idx);
+            state.index.remove(&key);
+            keys_to_evict.push(key);
+        }
 
         EvictionResult { keys_to_evict }
     }
 
     fn debug_state(&self) -> String {
-        let mut entries: Vec<(K, Instant)> = self
-            .access_order
-            .iter()
-            .map(|entry| (entry.key().clone(), *entry.value()))
-            .collect();
-
-        entries.sort_by(|a, b| a.1.cmp(&b.1));
-
-        let mut result = format!("LRU Policy: {} entries\n", entries.len());
-        for (i, (key, time)) in entries.iter().enumerate() {
-            result.push_str(&format!("  {}: {:?} @ {:?}\n", i, key, time));
+        let state = lock(&self.state);
+        let mut result = format!("LRU Policy: {} entries\n", state.order.len());
+        for (i, idx) in state.order.iter(&state.nodes).enumerate() {
+            result.push_str(&format!("  {}: {:?}\n", i, state.nodes.get(idx)));
         }
         result
     }
 
     fn reset(&self) {
-        self.access_order.clear();
+        let state = &mut *lock(&self.state);
+        state.nodes.clear();
+        state.index.clear();
+        state.order.clear();
     }
 }
 
 /// LFU (Least Frequently Used) eviction policy.
 ///
-/// Discards the least frequently used items first.
+/// Discards the least frequently used items first, breaking ties by evicting
+/// the key that reached its current count earliest. Keys are grouped into
+/// frequency buckets kept in ascending order, so every operation is O(1).
 #[derive(Debug)]
 pub struct LfuPolicy<K: std::hash::Hash + std::cmp::Eq + Clone + Send + Sync + std::fmt::Debug> {
-    access_count: dashmap::DashMap<K, usize>,
+    state: Mutex<LfuState<K>>,
+}
+
+#[derive(Debug)]
+struct LfuState<K> {
+    items: Slab<LfuItem<K>>,
+    buckets: Slab<FrequencyBucket>,
+    index: HashMap<K, usize>,
+    /// Buckets ordered by ascending frequency
+    frequencies: List,
+}
+
+#[derive(Debug)]
+struct LfuItem<K> {
+    key: K,
+    bucket: usize,
+}
+
+#[derive(Debug)]
+struct FrequencyBucket {
+    count: usize,
+    /// Keys with this access count, in the order they reached it
+    items: List,
+}
+
+impl<K> LfuState<K> {
+    /// Returns the bucket for `count` that directly follows `after`, creating it if needed.
+    ///
+    /// `after` of `None` means the bucket must be the first one.
+    fn bucket_after(&mut self, after: Option<usize>, count: usize) -> usize {
+        let candidate = match after {
+            Some(bucket) => self.frequencies.next(&self.buckets, bucket),
+            None => self.frequencies.front(),
+        };
+        if let Some(bucket) = candidate {
+            if self.buckets.get(bucket).count == count {
+                return bucket;
+            }
+        }
+
+        let bucket = self.buckets.insert(FrequencyBucket {
+            count,
+            items: List::new(),
+        });
+        match after {
+            Some(anchor) => self
+                .frequencies
+                .insert_after(&mut self.buckets, anchor, bucket),
+            None => self.frequencies.push_front(&mut self.buckets, bucket),
+        }
+        bucket
+    }
+
+    /// Adds a new item for `key` with an access count of one.
+    fn push_new(&mut self, key: K) -> usize {
+        let bucket = self.bucket_after(None, 1);
+        let idx = self.items.insert(LfuItem { key, bucket });
+        self.buckets
+            .get_mut(bucket)
+            .items
+            .push_back(&mut self.items, idx);
+        idx
+    }
+
+    /// Detaches `idx` from its bucket, dropping the bucket once it is empty.
+    ///
+    /// Returns the bucket preceding the removed position, which is where a
+    /// replacement bucket would have to be linked.
+    fn detach(&mut self, idx: usize) -> Option<usize> {
+        let bucket = self.items.get(idx).bucket;
+        let items = &mut self.buckets.get_mut(bucket).items;
+        items.unlink(&mut self.items, idx);
+
+        if items.is_empty() {
+            let prev = self.frequencies.prev(&self.buckets, bucket);
+            self.frequencies.unlink(&mut self.buckets, bucket);
+            self.buckets.remove(bucket);
+            prev
+        } else {
+            Some(bucket)
+        }
+    }
+
+    /// Moves `idx` into the next frequency bucket.
+    fn increment(&mut self, idx: usize) {
+        let count = self.buckets.get(self.items.get(idx).bucket).count + 1;
+        let anchor = self.detach(idx);
+        let bucket = self.bucket_after(anchor, count);
+
+        self.buckets
+            .get_mut(bucket)
+            .items
+            .push_back(&mut self.items, idx);
+        self.items.get_mut(idx).bucket = bucket;
+    }
+
+    /// Removes `idx` entirely and returns its key.
+    fn remove(&mut self, idx: usize) -> K {
+        self.detach(idx);
+        self.items.remove(idx).key
+    }
 }
 
 impl<K> LfuPolicy<K>
@@ -137,80 +306,287 @@ where
     /// Creates a new LFU eviction policy.
     pub fn new() -> Self {
         Self {
-            access_count: dashmap::DashMap::new(),
+            state: Mutex::new(LfuState {
+                items: Slab::new(),
+                buckets: Slab::new(),
+                index: HashMap::new(),
+                frequencies: List::new(),
+            }),
         }
     }
 }
 
+impl<K> Default for LfuPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<K, V> EvictionPolicy<K, V> for LfuPolicy<K>
 where
     K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
 {
     fn on_insert(&self, key: &K, _value: &V) {
-        self.access_count.insert(key.clone(), 1);
+        let state = &mut *lock(&self.state);
+        if let Some(idx) = state.index.remove(key) {
+            state.remove(idx);
+        }
+        let idx = state.push_new(key.clone());
+        state.index.insert(key.clone(), idx);
     }
 
     fn on_access(&self, key: &K) {
-        self.access_count
-            .entry(key.clone())
-            .and_modify(|count| *count += 1)
-            .or_insert(1);
+        let state = &mut *lock(&self.state);
+        match state.index.get(key) {
+            Some(&idx) => state.increment(idx),
+            None => {
+                let idx = state.push_new(key.clone());
+                state.index.insert(key.clone(), idx);
+            }
+        }
     }
 
     fn on_remove(&self, key: &K) {
-        self.access_count.remove(key);
+        let state = &mut *lock(&self.state);
+        if let Some(idx) = state.index.remove(key) {
+            state.remove(idx);
+        }
     }
 
     fn evict(&self, count: usize) -> EvictionResult<K> {
-        if count == 0 {
-            return EvictionResult {
-                keys_to_evict: Vec::new(),
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.index.len()));
+
+        while keys_to_evict.len() < count {
+            let Some(bucket) = state.frequencies.front() else {
+                break;
             };
+            let idx = state
+                .buckets
+                .get(bucket)
+                .items
+                .front()
+                .expect("frequency buckets are never empty");
+            let key = state.remove(idx);
+            state.index.remove(&key);
+            keys_to_evict.push(key);
         }
 
-        let mut entries: Vec<(K, usize)> = self
-            .access_count
-            .iter()
-            .map(|entry| (entry.key().clone(), *entry.value()))
-            .collect();
+        EvictionResult { keys_to_evict }
+    }
 
-        if entries.is_empty() {
-            return EvictionResult {
-                keys_to_evict: Vec::new(),
-            };
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!("LFU Policy: {} entries\n", state.index.len());
+        for bucket in state.frequencies.iter(&state.buckets) {
+            let bucket = state.buckets.get(bucket);
+            for idx in bucket.items.iter(&state.items) {
+                result.push_str(&format!(
+                    "  {:?}: {} accesses\n",
+                    state.items.get(idx).key,
+                    bucket.count
+                ));
+            }
         }
+        result
+    }
 
-        entries.sort_by(|a, b| a.1.cmp(&b.1));
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.items.clear();
+        state.buckets.clear();
+        state.index.clear();
+        state.frequencies.clear();
+    }
+}
 
-        let to_take = std::cmp::min(count, entries.len());
-        let keys_to_evict = entries
-            .into_iter()
-            .take(to_take)
-            .map(|(key, _count)| {
-                self.access_count.remove(&key);
-                key
-            })
-            .collect();
+/// Selects the eviction policy of a cache.
+///
+/// Built-in policies can also be parsed from their names, for example from a
+/// configuration file: "lru", "lfu", "tinylfu", "arc", "2q", "sieve", "fifo"
+/// and "random". Parsing is case-insensitive and unknown names are rejected
+/// with [`Error::Config`].
+///
+/// # Examples
+///
+/// ```
+/// use fncache::eviction::EvictionPolicyKind;
+///
+/// let kind: EvictionPolicyKind = "sieve".parse().unwrap();
+/// assert_eq!(kind.name(), "sieve");
+/// assert!("most-recent".parse::<EvictionPolicyKind>().is_err());
+/// ```
+#[derive(Default)]
+pub enum EvictionPolicyKind<K = Key, V = Value> {
+    /// Least Recently Used, see [`LruPolicy`]
+    #[default]
+    Lru,
+    /// Least Frequently Used, see [`LfuPolicy`]
+    Lfu,
+    /// W-TinyLFU, see [`TinyLfuPolicy`]
+    TinyLfu,
+    /// Adaptive Replacement Cache, see [`ArcPolicy`]
+    Arc,
+    /// 2Q, see [`TwoQueuePolicy`]
+    TwoQueue,
+    /// SIEVE, see [`SievePolicy`]
+    Sieve,
+    /// First In, First Out, see [`FifoPolicy`]
+    Fifo,
+    /// Uniformly random, see [`RandomPolicy`]
+    Random,
+    /// A user-provided policy instance
+    Custom(Arc<dyn EvictionPolicy<K, V>>),
+}
 
-        EvictionResult { keys_to_evict }
+impl<K, V> EvictionPolicyKind<K, V>
+where
+    K: Eq + Hash + Clone + Send + Sync + fmt::Debug + 'static,
+    V: Send + Sync + 'static,
+{
+    /// Returns the policy name accepted by [`FromStr`], or "custom".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lru => "lru",
+            Self::Lfu => "lfu",
+            Self::TinyLfu => "tinylfu",
+            Self::Arc => "arc",
+            Self::TwoQueue => "2q",
+            Self::Sieve => "sieve",
+            Self::Fifo => "fifo",
+            Self::Random => "random",
+            Self::Custom(_) => "custom",
+        }
     }
 
-    fn reset(&self) {
-        self.access_count.clear();
+    /// Creates the policy.
+    ///
+    /// Built-in kinds return a fresh, empty policy. `Custom` returns the
+    /// wrapped instance itself.
+    pub fn build(&self) -> Arc<dyn EvictionPolicy<K, V>> {
+        self.build_with_capacity(0)
+    }
+
+    /// Creates the policy for a cache holding up to `capacity` entries.
+    ///
+    /// Capacity-dependent state, such as the W-TinyLFU frequency sketch, is
+    /// sized up front so it is not reset while the cache fills. A capacity
+    /// of 0 means unknown.
+    pub fn build_with_capacity(&self, capacity: usize) -> Arc<dyn EvictionPolicy<K, V>> {
+        match self {
+            Self::Lru => Arc::new(LruPolicy::new()),
+            Self::Lfu => Arc::new(LfuPolicy::new()),
+            Self::TinyLfu => Arc::new(TinyLfuPolicy::with_capacity(capacity)),
+            Self::Arc => Arc::new(ArcPolicy::new()),
+            Self::TwoQueue => Arc::new(TwoQueuePolicy::new()),
+            Self::Sieve => Arc::new(SievePolicy::new()),
+            Self::Fifo => Arc::new(FifoPolicy::new()),
+            Self::Random => Arc::new(RandomPolicy::new()),
+            Self::Custom(policy) => Arc::clone(policy),
+        }
+    }
+
+    /// Creates the policy with support for entry priorities.
+    ///
+    /// Built-in kinds are wrapped in a [`PriorityPolicy`] that evicts `High`
+    /// keys only after every `Normal` key and never evicts `Pinned` keys.
+    /// `Custom` returns the wrapped instance, which handles priorities
+    /// through [`EvictionPolicy::on_insert_with_priority`].
+    ///
+    /// `capacity` is passed to [`EvictionPolicyKind::build_with_capacity`].
+    pub fn build_prioritized(&self, capacity: usize) -> Arc<dyn EvictionPolicy<K, V>> {
+        match self {
+            Self::Custom(policy) => Arc::clone(policy),
+            _ => Arc::new(PriorityPolicy::new(
+                self.build_with_capacity(capacity),
+                self.build_with_capacity(capacity),
+            )),
+        }
+    }
+}
+
+impl<K, V> Clone for EvictionPolicyKind<K, V> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Lru => Self::Lru,
+            Self::Lfu => Self::Lfu,
+            Self::TinyLfu => Self::TinyLfu,
+            Self::Arc => Self::Arc,
+            Self::TwoQueue => Self::TwoQueue,
+            Self::Sieve => Self::Sieve,
+            Self::Fifo => Self::Fifo,
+            Self::Random => Self::Random,
+            Self::Custom(policy) => Self::Custom(Arc::clone(policy)),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for EvictionPolicyKind<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Lru => f.write_str("Lru"),
+            Self::Lfu => f.write_str("Lfu"),
+            Self::TinyLfu => f.write_str("TinyLfu"),
+            Self::Arc => f.write_str("Arc"),
+            Self::TwoQueue => f.write_str("TwoQueue"),
+            Self::Sieve => f.write_str("Sieve"),
+            Self::Fifo => f.write_str("Fifo"),
+            Self::Random => f.write_str("Random"),
+            Self::Custom(policy) => f.debug_tuple("Custom").field(policy).finish(),
+        }
+    }
+}
+
+impl<K, V> FromStr for EvictionPolicyKind<K, V> {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "lru" => Ok(Self::Lru),
+            "lfu" => Ok(Self::Lfu),
+            "tinylfu" => Ok(Self::TinyLfu),
+            "arc" => Ok(Self::Arc),
+            "2q" => Ok(Self::TwoQueue),
+            "sieve" => Ok(Self::Sieve),
+            "fifo" => Ok(Self::Fifo),
+            "random" => Ok(Self::Random),
+            _ => Err(Error::Config(format!("unknown eviction policy: {}", name))),
+        }
     }
 }
 
-/// Factory for creating eviction policies.
-pub fn create_policy<K, V>(policy_type: &str) -> Arc<dyn EvictionPolicy<K, V>>
+/// Factory for creating eviction policies by name.
+///
+/// The policy does not know the cache capacity; prefer
+/// [`create_policy_with_capacity`] when it is known.
+///
+/// Returns [`Error::Config`] for names that are not a built-in policy.
+pub fn create_policy<K, V>(policy_type: &str) -> crate::Result<Arc<dyn EvictionPolicy<K, V>>>
 where
     K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug + 'static,
     V: Send + Sync + 'static,
 {
-    match policy_type.to_lowercase().as_str() {
-        "lru" => Arc::new(LruPolicy::new()),
-        "lfu" => Arc::new(LfuPolicy::new()),
-        _ => Arc::new(LruPolicy::new()),
-    }
+    create_policy_with_capacity(policy_type, 0)
+}
+
+/// Factory for creating eviction policies by name for a cache holding up to
+/// `capacity` entries.
+///
+/// Returns [`Error::Config`] for names that are not a built-in policy.
+pub fn create_policy_with_capacity<K, V>(
+    policy_type: &str,
+    capacity: usize,
+) -> crate::Result<Arc<dyn EvictionPolicy<K, V>>>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug + 'static,
+    V: Send + Sync + 'static,
+{
+    Ok(policy_type
+        .parse::<EvictionPolicyKind<K, V>>()?
+        .build_with_capacity(capacity))
 }
 
 #[cfg(test)]
@@ -252,4 +628,17 @@ mod tests {
         assert_eq!(result.keys_to_evict.len(), 1);
         assert_eq!(result.keys_to_evict[0], "key2".to_string());
     }
+
+    #[test]
+    fn test_create_policy_rejects_unknown_names() {
+        for name in [
+            "lru", "LFU", "tinylfu", "arc", "2q", "sieve", "fifo", "random",
+        ] {
+            assert!(create_policy::<String, i32>(name).is_ok(), "{}", name);
+        }
+        assert!(matches!(
+            create_policy::<String, i32>("mru"),
+            Err(Error::Config(_))
+        ));
+    }
 }
@@ -0,0 +1,169 @@
+//! Priority tiers on top of an eviction policy.
+//!
+//! Keys inserted with [`Priority::Normal`] and [`Priority::High`] are tracked
+//! by two separate instances of the same policy. Evictions are taken from the
+//! normal tier first and only reach the high tier once it is empty. Pinned
+//! keys are not tracked by either tier, so they are never selected.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use super::{lock, EvictionPolicy, EvictionResult};
+use crate::backends::Priority;
+
+/// Eviction policy that ranks keys by [`Priority`] before consulting an
+/// inner policy.
+///
+/// Built by [`EvictionPolicyKind::build_prioritized`](super::EvictionPolicyKind::build_prioritized)
+/// for the built-in policies.
+pub struct PriorityPolicy<K, V> {
+    normal: Arc<dyn EvictionPolicy<K, V>>,
+    high: Arc<dyn EvictionPolicy<K, V>>,
+    /// Priority of every key that is not `Normal`
+    elevated: Mutex<HashMap<K, Priority>>,
+}
+
+impl<K, V> PriorityPolicy<K, V>
+where
+    K: Eq + Hash + Clone + Send + Sync + fmt::Debug,
+{
+    /// Creates a priority policy from two empty instances of the same policy,
+    /// one for `Normal` keys and one for `High` keys.
+    pub fn new(normal: Arc<dyn EvictionPolicy<K, V>>, high: Arc<dyn EvictionPolicy<K, V>>) -> Self {
+        Self {
+            normal,
+            high,
+            elevated: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn tier(&self, priority: Priority) -> Option<&dyn EvictionPolicy<K, V>> {
+        match priority {
+            Priority::Normal => Some(self.normal.as_ref()),
+            Priority::High => Some(self.high.as_ref()),
+            Priority::Pinned => None,
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for PriorityPolicy<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityPolicy")
+            .field("normal", &self.normal)
+            .field("high", &self.high)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for PriorityPolicy<K, V>
+where
+    K: Eq + Hash + Clone + Send + Sync + fmt::Debug,
+{
+    fn on_insert(&self, key: &K, value: &V) {
+        self.on_insert_with_priority(key, value, Priority::Normal);
+    }
+
+    fn on_insert_with_priority(&self, key: &K, value: &V, priority: Priority) {
+        let mut elevated = lock(&self.elevated);
+        let previous = match priority {
+            Priority::Normal => elevated.remove(key),
+            Priority::High | Priority::Pinned => elevated.insert(key.clone(), priority),
+        }
+        .unwrap_or(Priority::Normal);
+
+        if previous != priority {
+            if let Some(tier) = self.tier(previous) {
+                tier.on_remove(key);
+            }
+        }
+        if let Some(tier) = self.tier(priority) {
+            tier.on_insert(key, value);
+        }
+    }
+
+    fn on_access(&self, key: &K) {
+        let priority = lock(&self.elevated)
+            .get(key)
+            .copied()
+            .unwrap_or(Priority::Normal);
+        if let Some(tier) = self.tier(priority) {
+            tier.on_access(key);
+        }
+    }
+
+    fn on_remove(&self, key: &K) {
+        let priority = lock(&self.elevated).remove(key).unwrap_or(Priority::Normal);
+        if let Some(tier) = self.tier(priority) {
+            tier.on_remove(key);
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let mut result = self.normal.evict(count);
+        let remaining = count - result.keys_to_evict.len().min(count);
+        if remaining > 0 {
+            let high = self.high.evict(remaining).keys_to_evict;
+            let mut elevated = lock(&self.elevated);
+            for key in &high {
+                elevated.remove(key);
+            }
+            result.keys_to_evict.extend(high);
+        }
+        result
+    }
+
+    fn debug_state(&self) -> String {
+        let pinned = lock(&self.elevated)
+            .values()
+            .filter(|&&priority| priority == Priority::Pinned)
+            .count();
+        format!(
+            "Priority Policy: {} pinned\nnormal: {}high: {}",
+            pinned,
+            self.normal.debug_state(),
+            self.high.debug_state()
+        )
+    }
+
+    fn reset(&self) {
+        lock(&self.elevated).clear();
+        self.normal.reset();
+        self.high.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eviction::LruPolicy;
+
+    fn lru_tiers() -> PriorityPolicy<u32, ()> {
+        PriorityPolicy::new(Arc::new(LruPolicy::new()), Arc::new(LruPolicy::new()))
+    }
+
+    #[test]
+    fn test_high_priority_keys_are_evicted_last() {
+        let policy = lru_tiers();
+        policy.on_insert_with_priority(&0, &(), Priority::High);
+        policy.on_insert(&1, &());
+        policy.on_insert_with_priority(&2, &(), Priority::Pinned);
+        policy.on_insert(&3, &());
+
+        assert_eq!(policy.evict(2).keys_to_evict, vec![1, 3]);
+        assert_eq!(policy.evict(2).keys_to_evict, vec![0]);
+        assert!(policy.evict(1).keys_to_evict.is_empty());
+    }
+
+    #[test]
+    fn test_changing_priority_moves_key_between_tiers() {
+        let policy = lru_tiers();
+        policy.on_insert_with_priority(&0, &(), Priority::Pinned);
+        policy.on_insert(&1, &());
+        assert_eq!(policy.evict(2).keys_to_evict, vec![1]);
+
+        policy.on_insert(&0, &());
+        assert_eq!(policy.evict(1).keys_to_evict, vec![0]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Random eviction policy.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::Mutex;
+
+use super::{lock, EvictionPolicy, EvictionResult};
+
+#[derive(Debug)]
+struct RandomPolicyState<K> {
+    keys: Vec<K>,
+    /// Position of each key in `keys`
+    index: HashMap<K, usize>,
+    /// xorshift64* state, never zero
+    rng: u64,
+}
+
+impl<K: Eq + Hash> RandomPolicyState<K> {
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Removes the key at `pos` in O(1) by swapping the last key into its place.
+    fn swap_remove(&mut self, pos: usize) -> K {
+        let key = self.keys.swap_remove(pos);
+        self.index.remove(&key);
+        if let Some(moved) = self.keys.get(pos) {
+            if let Some(slot) = self.index.get_mut(moved) {
+                *slot = pos;
+            }
+        }
+        key
+    }
+}
+
+/// Random eviction policy.
+///
+/// Discards uniformly random items. It keeps no recency or frequency state,
+/// so it has no pathological access patterns and very low overhead.
+#[derive(Debug)]
+pub struct RandomPolicy<K: Hash + Eq + Clone + Send + Sync + std::fmt::Debug> {
+    state: Mutex<RandomPolicyState<K>>,
+}
+
+impl<K> RandomPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    /// Creates a new random eviction policy with a randomly chosen seed.
+    pub fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(0);
+        Self::with_seed(hasher.finish())
+    }
+
+    /// Creates a new random eviction policy with a fixed seed.
+    ///
+    /// Two policies with the same seed that see the same operations evict the
+    /// same keys, which is useful for reproducible tests and benchmarks.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: Mutex::new(RandomPolicyState {
+                keys: Vec::new(),
+                index: HashMap::new(),
+                rng: seed.max(1),
+            }),
+        }
+    }
+}
+
+impl<K> Default for RandomPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for RandomPolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn on_insert(&self, key: &K, _value: &V) {
+        let state = &mut *lock(&self.state);
+        if !state.index.contains_key(key) {
+            state.index.insert(key.clone(), state.keys.len());
+            state.keys.push(key.clone());
+        }
+    }
+
+    fn on_access(&self, _key: &K) {}
+
+    fn on_remove(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&pos) = state.index.get(key) {
+            state.swap_remove(pos);
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.keys.len()));
+
+        while keys_to_evict.len() < count && !state.keys.is_empty() {
+            let pos = (state.next_random() % state.keys.len() as u64) as usize;
+            keys_to_evict.push(state.swap_remove(pos));
+        }
+
+        EvictionResult { keys_to_evict }
+    }
+
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!("Random Policy: {} entries\n", state.keys.len());
+        for (i, key) in state.keys.iter().enumerate() {
+            result.push_str(&format!("  {}: {:?}\n", i, key));
+        }
+        result
+    }
+
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.keys.clear();
+        state.index.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evict(policy: &RandomPolicy<u32>, count: usize) -> Vec<u32> {
+        <RandomPolicy<u32> as EvictionPolicy<u32, ()>>::evict(policy, count).keys_to_evict
+    }
+
+    #[test]
+    fn test_same_seed_evicts_same_keys() {
+        let a = RandomPolicy::with_seed(7);
+        let b = RandomPolicy::with_seed(7);
+        for key in 0..100u32 {
+            a.on_insert(&key, &());
+            b.on_insert(&key, &());
+        }
+
+        let evicted = evict(&a, 10);
+        assert_eq!(evicted, evict(&b, 10));
+        assert_ne!(evicted, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,168 @@
+//! SIEVE eviction policy.
+//!
+//! SIEVE keeps items in insertion order with one "visited" bit each. A hand
+//! sweeps from the oldest item towards the newest: visited items have their
+//! bit cleared and are skipped, the first unvisited item is evicted, and the
+//! hand stays where it stopped for the next eviction. Hits only set a bit, so
+//! reads never reorder the queue.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use super::list::{List, Slab};
+use super::{lock, EvictionPolicy, EvictionResult};
+
+#[derive(Debug)]
+struct SieveNode<K> {
+    key: K,
+    visited: bool,
+}
+
+#[derive(Debug)]
+struct SieveState<K> {
+    nodes: Slab<SieveNode<K>>,
+    index: HashMap<K, usize>,
+    /// Oldest insertion at the front
+    queue: List,
+    /// Next node to inspect; `None` restarts at the oldest node
+    hand: Option<usize>,
+}
+
+impl<K: Eq + Hash> SieveState<K> {
+    fn remove(&mut self, idx: usize) -> K {
+        if self.hand == Some(idx) {
+            self.hand = self.queue.next(&self.nodes, idx);
+        }
+        self.queue.unlink(&mut self.nodes, idx);
+        let key = self.nodes.remove(idx).key;
+        self.index.remove(&key);
+        key
+    }
+
+    fn select_victim(&mut self) -> Option<usize> {
+        let mut cursor = self.hand.or_else(|| self.queue.front())?;
+        loop {
+            let node = self.nodes.get_mut(cursor);
+            if !node.visited {
+                self.hand = Some(cursor);
+                return Some(cursor);
+            }
+            node.visited = false;
+            cursor = self
+                .queue
+                .next(&self.nodes, cursor)
+                .or_else(|| self.queue.front())?;
+        }
+    }
+}
+
+/// SIEVE eviction policy.
+///
+/// A FIFO queue with lazy promotion: recently used items survive one sweep
+/// of the eviction hand. It matches or beats LRU on most web workloads while
+/// doing less work on hits.
+#[derive(Debug)]
+pub struct SievePolicy<K: Hash + Eq + Clone + Send + Sync + std::fmt::Debug> {
+    state: Mutex<SieveState<K>>,
+}
+
+impl<K> SievePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    /// Creates a new SIEVE eviction policy.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(SieveState {
+                nodes: Slab::new(),
+                index: HashMap::new(),
+                queue: List::new(),
+                hand: None,
+            }),
+        }
+    }
+}
+
+impl<K> Default for SievePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for SievePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn on_insert(&self, key: &K, _value: &V) {
+        let state = &mut *lock(&self.state);
+        match state.index.get(key) {
+            Some(&idx) => state.nodes.get_mut(idx).visited = true,
+            None => {
+                let idx = state.nodes.insert(SieveNode {
+                    key: key.clone(),
+                    visited: false,
+                });
+                state.queue.push_back(&mut state.nodes, idx);
+                state.index.insert(key.clone(), idx);
+            }
+        }
+    }
+
+    fn on_access(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            state.nodes.get_mut(idx).visited = true;
+        }
+    }
+
+    fn on_remove(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            state.remove(idx);
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.queue.len()));
+
+        while keys_to_evict.len() < count {
+            let Some(idx) = state.select_victim() else {
+                break;
+            };
+            keys_to_evict.push(state.remove(idx));
+        }
+
+        EvictionResult { keys_to_evict }
+    }
+
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!("SIEVE Policy: {} entries\n", state.queue.len());
+        for (i, idx) in state.queue.iter(&state.nodes).enumerate() {
+            let node = state.nodes.get(idx);
+            let hand = if state.hand == Some(idx) {
+                " <- hand"
+            } else {
+                ""
+            };
+            result.push_str(&format!(
+                "  {}: {:?} visited={}{}\n",
+                i, node.key, node.visited, hand
+            ));
+        }
+        result
+    }
+
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.nodes.clear();
+        state.index.clear();
+        state.queue.clear();
+        state.hand = None;
+    }
+}
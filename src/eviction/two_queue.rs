@@ -0,0 +1,250 @@
+//! 2Q eviction policy.
+//!
+//! New keys enter the `A1in` FIFO. Keys evicted from `A1in` are remembered,
+//! without their values, in the `A1out` ghost FIFO. A key that is inserted
+//! again while it is still a ghost has proven it is reused and goes to the
+//! `Am` LRU, which holds the long-term working set. Hits in `A1in` do not
+//! promote, so a burst of reads right after insertion is not mistaken for
+//! long-term popularity.
+//!
+//! The policy is not told the cache capacity. It bounds `A1in` at 25% and
+//! `A1out` at 50% of the keys it holds when an eviction is requested.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use super::list::{List, Slab};
+use super::{lock, EvictionPolicy, EvictionResult};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Queue {
+    /// Resident, seen once
+    In,
+    /// Ghost, recently evicted from `In`
+    Out,
+    /// Resident, reused
+    Main,
+}
+
+#[derive(Debug)]
+struct TwoQueueNode<K> {
+    key: K,
+    queue: Queue,
+}
+
+#[derive(Debug)]
+struct TwoQueueState<K> {
+    nodes: Slab<TwoQueueNode<K>>,
+    /// Resident and ghost keys
+    index: HashMap<K, usize>,
+    a1_in: List,
+    a1_out: List,
+    am: List,
+}
+
+impl<K: Eq + Hash + Clone> TwoQueueState<K> {
+    fn list(&mut self, queue: Queue) -> (&mut List, &mut Slab<TwoQueueNode<K>>) {
+        let list = match queue {
+            Queue::In => &mut self.a1_in,
+            Queue::Out => &mut self.a1_out,
+            Queue::Main => &mut self.am,
+        };
+        (list, &mut self.nodes)
+    }
+
+    fn resident(&self) -> usize {
+        self.a1_in.len() + self.am.len()
+    }
+
+    /// Moves node `idx` to the back of `queue`.
+    fn relink(&mut self, idx: usize, queue: Queue) {
+        let (from, nodes) = self.list(self.nodes.get(idx).queue);
+        from.unlink(nodes, idx);
+        let (to, nodes) = self.list(queue);
+        to.push_back(nodes, idx);
+        self.nodes.get_mut(idx).queue = queue;
+    }
+
+    /// Forgets node `idx` entirely.
+    fn remove(&mut self, idx: usize) -> K {
+        let (list, nodes) = self.list(self.nodes.get(idx).queue);
+        list.unlink(nodes, idx);
+        let key = self.nodes.remove(idx).key;
+        self.index.remove(&key);
+        key
+    }
+
+    fn evict_one(&mut self) -> Option<K> {
+        let resident = self.resident();
+        let in_limit = (resident / 4).max(1);
+        let out_limit = (resident / 2).max(1);
+
+        if self.a1_in.len() <= in_limit && !self.am.is_empty() {
+            let idx = self.am.front()?;
+            return Some(self.remove(idx));
+        }
+
+        let idx = self.a1_in.front()?;
+        self.relink(idx, Queue::Out);
+        while self.a1_out.len() > out_limit {
+            if let Some(ghost) = self.a1_out.front() {
+                self.remove(ghost);
+            }
+        }
+        Some(self.nodes.get(idx).key.clone())
+    }
+}
+
+/// 2Q (Two Queue) eviction policy.
+///
+/// Separates keys seen once from keys that keep coming back, so scans and
+/// one-hit wonders cannot flush the working set. Every operation is O(1).
+#[derive(Debug)]
+pub struct TwoQueuePolicy<K: Hash + Eq + Clone + Send + Sync + std::fmt::Debug> {
+    state: Mutex<TwoQueueState<K>>,
+}
+
+impl<K> TwoQueuePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    /// Creates a new 2Q eviction policy.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(TwoQueueState {
+                nodes: Slab::new(),
+                index: HashMap::new(),
+                a1_in: List::new(),
+                a1_out: List::new(),
+                am: List::new(),
+            }),
+        }
+    }
+}
+
+impl<K> Default for TwoQueuePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> EvictionPolicy<K, V> for TwoQueuePolicy<K>
+where
+    K: Eq + Hash + Clone + Send + Sync + std::fmt::Debug,
+{
+    fn on_insert(&self, key: &K, _value: &V) {
+        let state = &mut *lock(&self.state);
+        match state
+            .index
+            .get(key)
+            .map(|&idx| (idx, state.nodes.get(idx).queue))
+        {
+            Some((_, Queue::In)) => {}
+            Some((idx, Queue::Out | Queue::Main)) => state.relink(idx, Queue::Main),
+            None => {
+                let idx = state.nodes.insert(TwoQueueNode {
+                    key: key.clone(),
+                    queue: Queue::In,
+                });
+                state.a1_in.push_back(&mut state.nodes, idx);
+                state.index.insert(key.clone(), idx);
+            }
+        }
+    }
+
+    fn on_access(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            if state.nodes.get(idx).queue == Queue::Main {
+                state.am.move_to_back(&mut state.nodes, idx);
+            }
+        }
+    }
+
+    fn on_remove(&self, key: &K) {
+        let state = &mut *lock(&self.state);
+        if let Some(&idx) = state.index.get(key) {
+            if state.nodes.get(idx).queue != Queue::Out {
+                state.remove(idx);
+            }
+        }
+    }
+
+    fn evict(&self, count: usize) -> EvictionResult<K> {
+        let state = &mut *lock(&self.state);
+        let mut keys_to_evict = Vec::with_capacity(count.min(state.resident()));
+
+        while keys_to_evict.len() < count {
+            let Some(key) = state.evict_one() else {
+                break;
+            };
+            keys_to_evict.push(key);
+        }
+
+        EvictionResult { keys_to_evict }
+    }
+
+    fn debug_state(&self) -> String {
+        let state = lock(&self.state);
+        let mut result = format!(
+            "2Q Policy: {} entries (a1in {}, am {}, a1out {} ghosts)\n",
+            state.resident(),
+            state.a1_in.len(),
+            state.am.len(),
+            state.a1_out.len()
+        );
+        for (name, list) in [
+            ("a1in", &state.a1_in),
+            ("am", &state.am),
+            ("a1out", &state.a1_out),
+        ] {
+            for idx in list.iter(&state.nodes) {
+                result.push_str(&format!("  {}: {:?}\n", name, state.nodes.get(idx).key));
+            }
+        }
+        result
+    }
+
+    fn reset(&self) {
+        let state = &mut *lock(&self.state);
+        state.nodes.clear();
+        state.index.clear();
+        state.a1_in.clear();
+        state.a1_out.clear();
+        state.am.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evict_one(policy: &TwoQueuePolicy<u32>) -> u32 {
+        <TwoQueuePolicy<u32> as EvictionPolicy<u32, ()>>::evict(policy, 1).keys_to_evict[0]
+    }
+
+    #[test]
+    fn test_ghost_hit_promotes_to_main() {
+        let policy: TwoQueuePolicy<u32> = TwoQueuePolicy::new();
+        for key in 0..4 {
+            policy.on_insert(&key, &());
+        }
+
+        // Key 0 is evicted from A1in and remembered as a ghost.
+        assert_eq!(evict_one(&policy), 0);
+        policy.on_insert(&0, &());
+        {
+            let state = lock(&policy.state);
+            assert_eq!(state.nodes.get(state.index[&0]).queue, Queue::Main);
+        }
+
+        // The reused key outlives older keys that were only seen once.
+        assert_eq!(evict_one(&policy), 1);
+        assert_eq!(evict_one(&policy), 2);
+        assert!(lock(&policy.state).index.contains_key(&0));
+    }
+}
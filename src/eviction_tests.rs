@@ -1,5 +1,6 @@
 use crate::backends::memory::{MemoryBackend, MemoryBackendConfig};
 use crate::backends::CacheBackend;
+use crate::eviction::EvictionPolicyKind;
 use std::time::Duration;
 
 #[cfg(test)]
@@ -10,7 +11,8 @@ mod tests {
     async fn test_lru_eviction_with_capacity_limit() {
         let backend = MemoryBackend::with_config(MemoryBackendConfig {
             max_capacity: 2,
-            eviction_policy: "lru".to_string(),
+            eviction_policy: EvictionPolicyKind::Lru,
+            ..Default::default()
         });
 
         backend
@@ -38,7 +40,8 @@ mod tests {
     async fn test_lfu_eviction_with_capacity_limit() {
         let backend = MemoryBackend::with_config(MemoryBackendConfig {
             max_capacity: 2,
-            eviction_policy: "lfu".to_string(),
+            eviction_policy: EvictionPolicyKind::Lfu,
+            ..Default::default()
         });
 
         backend
@@ -75,7 +78,8 @@ mod tests {
     async fn test_eviction_policy_change() {
         let backend = MemoryBackend::with_config(MemoryBackendConfig {
             max_capacity: 2,
-            eviction_policy: "lfu".to_string(),
+            eviction_policy: EvictionPolicyKind::Lfu,
+            ..Default::default()
         });
 
         backend
@@ -111,7 +115,8 @@ mod tests {
     async fn test_ttl_with_eviction_policy() {
         let backend = MemoryBackend::with_config(MemoryBackendConfig {
             max_capacity: 3,
-            eviction_policy: "lru".to_string(),
+            eviction_policy: EvictionPolicyKind::Lru,
+            ..Default::default()
         });
 
         backend
@@ -151,4 +156,43 @@ mod tests {
         assert!(backend.contains_key(&"key3".to_string()).await.unwrap());
         assert!(backend.contains_key(&"key4".to_string()).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_tinylfu_keeps_hot_keys_through_scan() {
+        async fn hot_keys_after_scan(policy: EvictionPolicyKind) -> usize {
+            let backend = MemoryBackend::new()
+                .with_capacity(50)
+                .with_eviction_policy(policy);
+
+            for i in 0..50 {
+                backend
+                    .set(format!("key{}", i), vec![i], None)
+                    .await
+                    .unwrap();
+            }
+            for _ in 0..5 {
+                for i in 0..10 {
+                    backend.get(&format!("key{}", i)).await.unwrap();
+                }
+            }
+            for i in 0..200 {
+                backend
+                    .set(format!("scan{}", i), vec![0], None)
+                    .await
+                    .unwrap();
+            }
+
+            let mut hot = 0;
+            for i in 0..10 {
+                if backend.contains_key(&format!("key{}", i)).await.unwrap() {
+                    hot += 1;
+                }
+            }
+            assert_eq!(backend.get_store_len().await, 50);
+            hot
+        }
+
+        assert_eq!(hot_keys_after_scan(EvictionPolicyKind::Lru).await, 0);
+        assert_eq!(hot_keys_after_scan(EvictionPolicyKind::TinyLfu).await, 10);
+    }
 }
@@ -25,7 +25,15 @@
 //! Prefix-based invalidation relies on structured key naming to group
 //! related cache entries. For instance, all user profile data might use keys
 //! that start with `user:{id}:`
+//!
+//! ## Multiple Processes
+//!
+//! Tags and prefixes are tracked per process. Attach an
+//! [`InvalidationBus`](crate::bus::InvalidationBus) with
+//! [`InvalidationCache::with_bus`] to broadcast removals and invalidations to
+//! the other processes sharing the bus.
 
+use crate::bus::{self, InvalidationBus, InvalidationEvent, InvalidationListener};
 use crate::serialization::Serializer;
 use crate::{backends::CacheBackend, error::Error, Result};
 use async_trait::async_trait;
@@ -431,6 +439,7 @@ pub struct InvalidationCache<B> {
     backend: Arc<B>,
     tag_to_keys: std::sync::Mutex<std::collections::HashMap<Tag, HashSet<String>>>,
     prefixes: std::sync::Mutex<std::collections::HashMap<String, HashSet<String>>>,
+    bus: Option<Arc<dyn InvalidationBus>>,
 }
 
 impl<B> InvalidationCache<B>
@@ -443,7 +452,54 @@ where
             backend: Arc::new(backend),
             tag_to_keys: std::sync::Mutex::new(std::collections::HashMap::new()),
             prefixes: std::sync::Mutex::new(std::collections::HashMap::new()),
+            bus: None,
+        }
+    }
+
+    /// Broadcast removals, invalidations and clears on `bus`.
+    ///
+    /// Other processes apply them once they call
+    /// [`spawn_bus_listener`](Self::spawn_bus_listener) on their own cache.
+    pub fn with_bus(mut self, bus: Arc<dyn InvalidationBus>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    /// Start applying the invalidations published by other processes.
+    ///
+    /// Returns [`Error::Config`] if no bus was attached with
+    /// [`with_bus`](Self::with_bus).
+    pub fn spawn_bus_listener(self: &Arc<Self>) -> Result<tokio::task::JoinHandle<()>>
+    where
+        B: 'static,
+    {
+        let bus = self
+            .bus
+            .as_ref()
+            .ok_or_else(|| Error::Config("no invalidation bus configured".into()))?;
+        Ok(bus::spawn_listener(self, bus.subscribe()))
+    }
+
+    /// Publish an event on the bus, if one is attached.
+    async fn publish(&self, event: InvalidationEvent) {
+        bus::publish_best_effort(self.bus.as_ref(), event).await;
+    }
+
+    /// Remove the given keys from the backend and the tag and prefix mappings.
+    async fn remove_keys_locally(&self, keys: HashSet<String>) -> Result<()> {
+        for key in keys {
+            self.backend.remove(&key).await?;
+            self.unregister_key(&key);
         }
+        Ok(())
+    }
+
+    /// Remove every key and mapping from this process.
+    async fn clear_locally(&self) -> Result<()> {
+        self.backend.clear().await?;
+        self.tag_to_keys.lock().unwrap().clear();
+        self.prefixes.lock().unwrap().clear();
+        Ok(())
     }
 
     /// Set a value in the cache with associated tags
@@ -485,13 +541,11 @@ where
     /// Remove a value from the cache
     pub async fn remove(&self, key: &str) -> Result<()> {
         let key_string = key.to_string();
-        let result = self.backend.remove(&key_string).await;
+        self.backend.remove(&key_string).await?;
+        self.unregister_key(key);
+        self.publish(InvalidationEvent::Remove(key_string)).await;
 
-        if result.is_ok() {
-            self.unregister_key(key);
-        }
-
-        result
+        Ok(())
     }
 
     /// Register a key with tags for invalidation
@@ -499,10 +553,7 @@ where
         let mut tag_map = self.tag_to_keys.lock().unwrap();
 
         for tag in tags {
-            tag_map
-                .entry(tag)
-                .or_insert_with(HashSet::new)
-                .insert(key.to_string());
+            tag_map.entry(tag).or_default().insert(key.to_string());
         }
 
         self.register_key_with_prefixes(key);
@@ -528,7 +579,7 @@ where
             if i < parts.len() - 1 {
                 prefix_map
                     .entry(current_prefix.clone())
-                    .or_insert_with(HashSet::new)
+                    .or_default()
                     .insert(key.to_string());
             }
         }
@@ -582,7 +633,7 @@ where
     #[cfg(test)]
     pub fn get_tag_map(
         &self,
-    ) -> std::sync::MutexGuard<std::collections::HashMap<Tag, HashSet<String>>> {
+    ) -> std::sync::MutexGuard<'_, std::collections::HashMap<Tag, HashSet<String>>> {
         self.tag_to_keys.lock().unwrap()
     }
 
@@ -590,7 +641,7 @@ where
     #[cfg(test)]
     pub fn get_prefix_map(
         &self,
-    ) -> std::sync::MutexGuard<std::collections::HashMap<String, HashSet<String>>> {
+    ) -> std::sync::MutexGuard<'_, std::collections::HashMap<String, HashSet<String>>> {
         self.prefixes.lock().unwrap()
     }
 }
@@ -616,8 +667,19 @@ where
         self.backend.set(key, value, ttl).await
     }
 
+    async fn set_with_options(
+        &self,
+        key: crate::backends::Key,
+        value: crate::backends::Value,
+        options: crate::backends::SetOptions,
+    ) -> crate::Result<()> {
+        self.backend.set_with_options(key, value, options).await
+    }
+
     async fn remove(&self, key: &crate::backends::Key) -> crate::Result<()> {
-        self.backend.remove(key).await
+        self.backend.remove(key).await?;
+        self.publish(InvalidationEvent::Remove(key.clone())).await;
+        Ok(())
     }
 
     async fn contains_key(&self, key: &crate::backends::Key) -> crate::Result<bool> {
@@ -625,7 +687,18 @@ where
     }
 
     async fn clear(&self) -> crate::Result<()> {
-        self.backend.clear().await
+        self.backend.clear().await?;
+        self.publish(InvalidationEvent::Clear).await;
+        Ok(())
+    }
+
+    async fn get_or_compute(
+        &self,
+        key: crate::backends::Key,
+        options: crate::backends::SetOptions,
+        compute: crate::backends::Compute<'_>,
+    ) -> crate::Result<crate::backends::Value> {
+        self.backend.get_or_compute(key, options, compute).await
     }
 }
 
@@ -640,10 +713,8 @@ where
             .map_err(|e| Error::Other(format!("Failed to create runtime: {}", e)))?;
 
         rt.block_on(async {
-            for key in keys {
-                self.backend.remove(&key).await?;
-                self.unregister_key(&key);
-            }
+            self.remove_keys_locally(keys).await?;
+            self.publish(InvalidationEvent::Tag(tag.clone())).await;
             Ok::<_, Error>(())
         })
     }
@@ -655,10 +726,9 @@ where
             .map_err(|e| Error::Other(format!("Failed to create runtime: {}", e)))?;
 
         rt.block_on(async {
-            for key in keys {
-                self.backend.remove(&key).await?;
-                self.unregister_key(&key);
-            }
+            self.remove_keys_locally(keys).await?;
+            self.publish(InvalidationEvent::Prefix(prefix.to_string()))
+                .await;
             Ok::<_, Error>(())
         })
     }
@@ -699,37 +769,21 @@ where
         prefix_map.get(prefix).cloned().unwrap_or_default()
     }
     async fn invalidate_tag(&self, tag: &Tag) -> Result<()> {
-        let keys = {
-            let tag_map = self.tag_to_keys.lock().unwrap();
-            match tag_map.get(tag) {
-                Some(keys) => keys.clone(),
-                None => return Ok(()),
-            }
-        };
-
-        for key in keys {
-            self.backend.remove(&key).await?;
+        let keys = self.get_keys_by_tag(tag);
+        self.remove_keys_locally(keys).await?;
 
-            self.unregister_key(&key);
-        }
+        // Other processes may hold keys with this tag even if this one does not.
+        self.publish(InvalidationEvent::Tag(tag.clone())).await;
 
         Ok(())
     }
 
     async fn invalidate_prefix(&self, prefix: &str) -> Result<()> {
-        let keys = {
-            let prefix_map = self.prefixes.lock().unwrap();
-            match prefix_map.get(prefix) {
-                Some(keys) => keys.clone(),
-                None => return Ok(()),
-            }
-        };
-
-        for key in keys {
-            self.backend.remove(&key).await?;
+        let keys = self.get_keys_by_prefix(prefix);
+        self.remove_keys_locally(keys).await?;
 
-            self.unregister_key(&key);
-        }
+        self.publish(InvalidationEvent::Prefix(prefix.to_string()))
+            .await;
 
         Ok(())
     }
@@ -759,6 +813,31 @@ where
     }
 }
 
+#[async_trait]
+impl<B> InvalidationListener for InvalidationCache<B>
+where
+    B: CacheBackend + 'static,
+{
+    async fn on_invalidation(&self, event: &InvalidationEvent) -> Result<()> {
+        match event {
+            InvalidationEvent::Remove(key) => {
+                self.backend.remove(key).await?;
+                self.unregister_key(key);
+                Ok(())
+            }
+            InvalidationEvent::Tag(tag) => {
+                let keys = self.get_keys_by_tag(tag);
+                self.remove_keys_locally(keys).await
+            }
+            InvalidationEvent::Prefix(prefix) => {
+                let keys = self.get_keys_by_prefix(prefix);
+                self.remove_keys_locally(keys).await
+            }
+            InvalidationEvent::Clear => self.clear_locally().await,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -773,4 +852,47 @@ mod tests {
         assert_eq!(tag2, tag3);
         assert_eq!(tag1.as_str(), "user:123");
     }
+
+    #[tokio::test]
+    async fn test_bus_invalidates_tags_in_other_processes() {
+        use crate::backends::memory::MemoryBackend;
+        use crate::bus::UdpBus;
+        use std::time::Duration;
+
+        let a_bus = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let b_bus = UdpBus::bind("127.0.0.1:0").await.unwrap();
+        let a_bus = a_bus.with_peer(b_bus.local_addr().unwrap());
+
+        let a = Arc::new(InvalidationCache::new(MemoryBackend::new()).with_bus(Arc::new(a_bus)));
+        let b = Arc::new(InvalidationCache::new(MemoryBackend::new()).with_bus(Arc::new(b_bus)));
+        b.spawn_bus_listener().unwrap();
+
+        let tag = Tag::new("user:123");
+        b.set_with_tags("profile".to_string(), 1u32, None, [tag.clone()])
+            .await
+            .unwrap();
+
+        // `a` never saw the key, but the invalidation still reaches `b`.
+        AsyncCacheInvalidation::invalidate_tag(&*a, &tag)
+            .await
+            .unwrap();
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while b.get::<u32>("profile").await.unwrap().is_some() {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "the tagged key was not invalidated"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(b.get_keys_by_tag(&tag).is_empty());
+    }
+
+    #[test]
+    fn test_spawn_bus_listener_requires_a_bus() {
+        let cache = Arc::new(InvalidationCache::new(
+            crate::backends::memory::MemoryBackend::new(),
+        ));
+        assert!(matches!(cache.spawn_bus_listener(), Err(Error::Config(_))));
+    }
 }
@@ -69,13 +69,14 @@ use std::hash::{Hash, Hasher};
 /// // Explicitly selecting compile-time key derivation
 /// let strategy2 = KeyDerivation::CompileTime;
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum KeyDerivation {
     /// Runtime key derivation: uses actual parameter values to compute cache keys.
     ///
     /// With this strategy, functions are only considered cache hits when called with
     /// identical parameter values. This is the default strategy and provides the most
     /// precise caching behavior.
+    #[default]
     Runtime,
 
     /// Compile-time key derivation: uses function signature information only.
@@ -87,12 +88,6 @@ pub enum KeyDerivation {
     CompileTime,
 }
 
-impl Default for KeyDerivation {
-    fn default() -> Self {
-        KeyDerivation::Runtime
-    }
-}
-
 /// Generate a compile-time key for a function.
 ///
 /// This creates a deterministic hash based on the function name,
@@ -40,9 +40,10 @@
 //! ```
 
 use backends::CacheBackend;
-use std::sync::{Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 
 pub mod backends;
+pub mod bus;
 pub mod error;
 pub mod eviction;
 pub mod invalidation;
@@ -70,7 +71,18 @@ pub use backends::wasm::WasmStorageBackend;
 
 /// Internal structure to hold the cache backend
 #[derive(Debug)]
-pub struct GlobalCache(Box<dyn CacheBackend + Send + Sync>);
+pub struct GlobalCache(Arc<dyn CacheBackend + Send + Sync>);
+
+impl GlobalCache {
+    /// Returns a shared handle to the backend.
+    ///
+    /// Unlike the global cache guard, the handle may be held across long
+    /// awaits, such as a single-flight computation, without blocking other
+    /// callers.
+    pub fn backend(&self) -> Arc<dyn CacheBackend + Send + Sync> {
+        Arc::clone(&self.0)
+    }
+}
 
 #[cfg(not(any(debug_assertions, feature = "test-utils")))]
 static GLOBAL_CACHE: OnceLock<Mutex<GlobalCache>> = OnceLock::new();
@@ -79,6 +91,10 @@ static GLOBAL_CACHE: OnceLock<Mutex<GlobalCache>> = OnceLock::new();
 static GLOBAL_CACHE: OnceLock<Mutex<GlobalCache>> = OnceLock::new();
 
 pub use backends::memory::MemoryBackend;
+pub use backends::tiered::TieredBackend;
+
+#[cfg(feature = "file-backend")]
+pub use backends::file::FileBackend;
 
 /// Re-export of the proc macro for convenience.
 ///
@@ -130,7 +146,7 @@ pub fn init_global_cache<B>(backend: B) -> Result<()>
 where
     B: CacheBackend + Send + Sync + 'static,
 {
-    let global_cache = GlobalCache(Box::new(backend));
+    let global_cache = GlobalCache(Arc::new(backend));
     GLOBAL_CACHE
         .set(Mutex::new(global_cache))
         .map_err(|_| error::Error::AlreadyInitialized)?;
@@ -143,7 +159,7 @@ pub fn init_global_cache<B>(backend: B) -> Result<()>
 where
     B: CacheBackend + Send + Sync + 'static,
 {
-    let global_cache = GlobalCache(Box::new(backend));
+    let global_cache = GlobalCache(Arc::new(backend));
     GLOBAL_CACHE
         .set(Mutex::new(global_cache))
         .map_err(|_| error::Error::AlreadyInitialized)?;
@@ -203,6 +219,15 @@ impl CacheBackend for GlobalCache {
         self.0.set(key, value, ttl).await
     }
 
+    async fn set_with_options(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        options: backends::SetOptions,
+    ) -> Result<()> {
+        self.0.set_with_options(key, value, options).await
+    }
+
     async fn remove(&self, key: &String) -> Result<()> {
         self.0.remove(key).await
     }
@@ -214,12 +239,21 @@ impl CacheBackend for GlobalCache {
     async fn clear(&self) -> Result<()> {
         self.0.clear().await
     }
+
+    async fn get_or_compute(
+        &self,
+        key: String,
+        options: backends::SetOptions,
+        compute: backends::Compute<'_>,
+    ) -> Result<Vec<u8>> {
+        self.0.get_or_compute(key, options, compute).await
+    }
 }
 
 /// Common prelude for using the library.
 pub mod prelude {
     pub use crate::{
-        backends::{Backend, CacheBackend},
+        backends::{Backend, CacheBackend, RemovalCause, SetOptions},
         error::Error,
         fncache, global_cache, init_global_cache,
         metrics::Metrics,
@@ -35,11 +35,7 @@ impl LatencyMetric {
 
     /// Returns the average latency as a Duration.
     pub fn average_duration(&self) -> Duration {
-        if self.count == 0 {
-            Duration::from_nanos(0)
-        } else {
-            Duration::from_nanos((self.total_ns / self.count) as u64)
-        }
+        Duration::from_nanos(self.total_ns.checked_div(self.count).unwrap_or(0))
     }
 }
 
@@ -185,21 +181,17 @@ impl Metrics {
         let count = self.entry_count();
         let bytes = self.total_bytes();
 
-        if count == 0 {
-            0
-        } else {
-            bytes / count
-        }
+        bytes.checked_div(count).unwrap_or(0)
     }
 
     /// Returns latency metrics for get operations.
     pub fn get_latency(&self) -> LatencyMetric {
-        self.get_latency.lock().unwrap().clone()
+        *self.get_latency.lock().unwrap()
     }
 
     /// Returns latency metrics for set operations.
     pub fn set_latency(&self) -> LatencyMetric {
-        self.set_latency.lock().unwrap().clone()
+        *self.set_latency.lock().unwrap()
     }
 
     /// Returns the average latency for get operations in nanoseconds.
@@ -2,6 +2,7 @@
 
 use crate::backends::memory::{MemoryBackend, MemoryBackendConfig};
 use crate::backends::CacheBackend;
+use crate::eviction::EvictionPolicyKind;
 
 #[cfg(test)]
 mod tests {
@@ -11,7 +12,8 @@ mod tests {
     async fn test_advanced_metrics() {
         let backend = MemoryBackend::with_config(MemoryBackendConfig {
             max_capacity: 10,
-            eviction_policy: "lru".to_string(),
+            eviction_policy: EvictionPolicyKind::Lru,
+            ..Default::default()
         });
 
         let metrics = backend.metrics();
@@ -92,7 +92,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(any(feature = "file-backend", feature = "rocksdb-backend"))]
+    #[cfg(feature = "bincode")]
     fn test_bincode_serializer() {
         let serializer = BincodeSerializer::new();
 
@@ -180,7 +180,7 @@ where
         let mut tasks = self.tasks.lock().await;
 
         for key in keys_to_warm {
-            if !tasks.contains_key(&key) {
+            if let std::collections::hash_map::Entry::Vacant(slot) = tasks.entry(key.clone()) {
                 let key_clone = key.to_owned();
                 let warmer_self_clone = self.clone();
                 let warmers_clone = self.warmers.clone();
@@ -207,7 +207,7 @@ where
                     }
                 });
 
-                tasks.insert(key, handle);
+                slot.insert(handle);
             }
         }
 
@@ -139,3 +139,32 @@ fn test_different_argument_types() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_tti_expiration() -> Result<()> {
+    setup_test_cache()?;
+
+    #[fncache::fncache(ttl = 10, tti = 1)]
+    async fn get_idle_timestamp() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    }
+
+    let timestamp1 = get_idle_timestamp().await;
+    sleep(Duration::from_millis(600)).await;
+    let timestamp2 = get_idle_timestamp().await;
+    sleep(Duration::from_millis(600)).await;
+    let timestamp3 = get_idle_timestamp().await;
+    assert_eq!(timestamp1, timestamp2);
+    assert_eq!(timestamp1, timestamp3);
+
+    sleep(Duration::from_millis(1500)).await;
+
+    let timestamp4 = get_idle_timestamp().await;
+    assert_ne!(timestamp1, timestamp4);
+
+    Ok(())
+}
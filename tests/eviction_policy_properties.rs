@@ -0,0 +1,323 @@
+//! Property tests comparing the eviction policies against simple reference models.
+//!
+//! The reference models keep their state in plain vectors and pick victims by
+//! scanning, which is slow but obviously correct. Random operation sequences
+//! must produce exactly the same evictions from both implementations.
+//! Adaptive and randomized policies (W-TinyLFU, ARC, 2Q, random) have no
+//! simple reference model, so only their bookkeeping is checked.
+
+use fncache::eviction::{
+    ArcPolicy, EvictionPolicy, FifoPolicy, LfuPolicy, LruPolicy, RandomPolicy, SievePolicy,
+    TinyLfuPolicy, TwoQueuePolicy,
+};
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Insert(u8),
+    Access(u8),
+    Remove(u8),
+    Evict(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let key = 0u8..16;
+    prop_oneof![
+        4 => key.clone().prop_map(Op::Insert),
+        4 => key.clone().prop_map(Op::Access),
+        1 => key.prop_map(Op::Remove),
+        1 => (0usize..4).prop_map(Op::Evict),
+    ]
+}
+
+/// LRU reference: keys ordered from least to most recently used.
+#[derive(Default)]
+struct LruModel {
+    order: Vec<u8>,
+}
+
+impl LruModel {
+    fn touch(&mut self, key: u8) {
+        self.order.retain(|k| *k != key);
+        self.order.push(key);
+    }
+
+    fn apply(&mut self, op: &Op) -> Vec<u8> {
+        match *op {
+            Op::Insert(key) => self.touch(key),
+            Op::Access(key) => {
+                if self.order.contains(&key) {
+                    self.touch(key);
+                }
+            }
+            Op::Remove(key) => self.order.retain(|k| *k != key),
+            Op::Evict(count) => {
+                let count = count.min(self.order.len());
+                return self.order.drain(..count).collect();
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// LFU reference: `(key, count, seq)` where `seq` records when the key
+/// reached its current count, so ties go to the key that got there first.
+#[derive(Default)]
+struct LfuModel {
+    entries: Vec<(u8, usize, u64)>,
+    clock: u64,
+}
+
+impl LfuModel {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn apply(&mut self, op: &Op) -> Vec<u8> {
+        match *op {
+            Op::Insert(key) => {
+                let seq = self.tick();
+                self.entries.retain(|(k, _, _)| *k != key);
+                self.entries.push((key, 1, seq));
+            }
+            Op::Access(key) => {
+                let seq = self.tick();
+                match self.entries.iter_mut().find(|(k, _, _)| *k == key) {
+                    Some(entry) => {
+                        entry.1 += 1;
+                        entry.2 = seq;
+                    }
+                    None => self.entries.push((key, 1, seq)),
+                }
+            }
+            Op::Remove(key) => self.entries.retain(|(k, _, _)| *k != key),
+            Op::Evict(count) => {
+                let mut evicted = Vec::new();
+                for _ in 0..count {
+                    let Some(pos) = self
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, (_, count, seq))| (*count, *seq))
+                        .map(|(pos, _)| pos)
+                    else {
+                        break;
+                    };
+                    evicted.push(self.entries.remove(pos).0);
+                }
+                return evicted;
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// FIFO reference: keys in first-insertion order.
+#[derive(Default)]
+struct FifoModel {
+    order: Vec<u8>,
+}
+
+impl FifoModel {
+    fn apply(&mut self, op: &Op) -> Vec<u8> {
+        match *op {
+            Op::Insert(key) => {
+                if !self.order.contains(&key) {
+                    self.order.push(key);
+                }
+            }
+            Op::Access(_) => {}
+            Op::Remove(key) => self.order.retain(|k| *k != key),
+            Op::Evict(count) => {
+                let count = count.min(self.order.len());
+                return self.order.drain(..count).collect();
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// SIEVE reference: `(key, visited)` in insertion order plus the hand position.
+#[derive(Default)]
+struct SieveModel {
+    queue: Vec<(u8, bool)>,
+    hand: Option<usize>,
+}
+
+impl SieveModel {
+    fn position(&self, key: u8) -> Option<usize> {
+        self.queue.iter().position(|(k, _)| *k == key)
+    }
+
+    fn remove_at(&mut self, pos: usize) -> u8 {
+        let (key, _) = self.queue.remove(pos);
+        self.hand = match self.hand {
+            Some(hand) if hand > pos => Some(hand - 1),
+            Some(hand) if hand == pos && pos == self.queue.len() => None,
+            hand => hand,
+        };
+        key
+    }
+
+    fn apply(&mut self, op: &Op) -> Vec<u8> {
+        match *op {
+            Op::Insert(key) => match self.position(key) {
+                Some(pos) => self.queue[pos].1 = true,
+                None => self.queue.push((key, false)),
+            },
+            Op::Access(key) => {
+                if let Some(pos) = self.position(key) {
+                    self.queue[pos].1 = true;
+                }
+            }
+            Op::Remove(key) => {
+                if let Some(pos) = self.position(key) {
+                    self.remove_at(pos);
+                }
+            }
+            Op::Evict(count) => {
+                let mut evicted = Vec::new();
+                while evicted.len() < count && !self.queue.is_empty() {
+                    let mut cursor = self.hand.unwrap_or(0);
+                    while self.queue[cursor].1 {
+                        self.queue[cursor].1 = false;
+                        cursor = (cursor + 1) % self.queue.len();
+                    }
+                    self.hand = Some(cursor);
+                    evicted.push(self.remove_at(cursor));
+                }
+                return evicted;
+            }
+        }
+        Vec::new()
+    }
+}
+
+fn apply_policy(policy: &dyn EvictionPolicy<u8, ()>, op: &Op) -> Vec<u8> {
+    match *op {
+        Op::Insert(key) => policy.on_insert(&key, &()),
+        Op::Access(key) => policy.on_access(&key),
+        Op::Remove(key) => policy.on_remove(&key),
+        Op::Evict(count) => return policy.evict(count).keys_to_evict,
+    }
+    Vec::new()
+}
+
+/// Checks that a policy evicts exactly the requested number of keys, only
+/// ever evicts keys that are currently inserted, and eventually evicts all of them.
+fn check_tracked_keys(
+    policy: &dyn EvictionPolicy<u8, ()>,
+    ops: &[Op],
+) -> Result<(), TestCaseError> {
+    let mut tracked = BTreeSet::new();
+
+    for op in ops {
+        let evicted = apply_policy(policy, op);
+        match *op {
+            Op::Insert(key) => {
+                tracked.insert(key);
+            }
+            Op::Remove(key) => {
+                tracked.remove(&key);
+            }
+            Op::Access(_) => {}
+            Op::Evict(count) => {
+                prop_assert_eq!(evicted.len(), count.min(tracked.len()));
+                for key in evicted {
+                    prop_assert!(tracked.remove(&key), "evicted untracked key {}", key);
+                }
+            }
+        }
+    }
+
+    let mut rest = apply_policy(policy, &Op::Evict(usize::MAX));
+    rest.sort_unstable();
+    prop_assert_eq!(rest, tracked.into_iter().collect::<Vec<_>>());
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn lru_matches_reference_model(ops in prop::collection::vec(op(), 0..200)) {
+        let policy = LruPolicy::<u8>::new();
+        let mut model = LruModel::default();
+
+        for op in &ops {
+            prop_assert_eq!(apply_policy(&policy, op), model.apply(op), "after {:?}", op);
+        }
+
+        let remaining = model.order.len();
+        prop_assert_eq!(apply_policy(&policy, &Op::Evict(usize::MAX)), model.apply(&Op::Evict(remaining)));
+    }
+
+    #[test]
+    fn lfu_matches_reference_model(ops in prop::collection::vec(op(), 0..200)) {
+        let policy = LfuPolicy::<u8>::new();
+        let mut model = LfuModel::default();
+
+        for op in &ops {
+            prop_assert_eq!(apply_policy(&policy, op), model.apply(op), "after {:?}", op);
+        }
+
+        let remaining = model.entries.len();
+        prop_assert_eq!(apply_policy(&policy, &Op::Evict(usize::MAX)), model.apply(&Op::Evict(remaining)));
+    }
+
+    #[test]
+    fn fifo_matches_reference_model(ops in prop::collection::vec(op(), 0..200)) {
+        let policy = FifoPolicy::<u8>::new();
+        let mut model = FifoModel::default();
+
+        for op in &ops {
+            prop_assert_eq!(apply_policy(&policy, op), model.apply(op), "after {:?}", op);
+        }
+
+        let remaining = model.order.len();
+        prop_assert_eq!(apply_policy(&policy, &Op::Evict(usize::MAX)), model.apply(&Op::Evict(remaining)));
+    }
+
+    #[test]
+    fn sieve_matches_reference_model(ops in prop::collection::vec(op(), 0..200)) {
+        let policy = SievePolicy::<u8>::new();
+        let mut model = SieveModel::default();
+
+        for op in &ops {
+            prop_assert_eq!(apply_policy(&policy, op), model.apply(op), "after {:?}", op);
+        }
+
+        let remaining = model.queue.len();
+        prop_assert_eq!(apply_policy(&policy, &Op::Evict(usize::MAX)), model.apply(&Op::Evict(remaining)));
+    }
+
+    #[test]
+    fn adaptive_policies_only_evict_tracked_keys(ops in prop::collection::vec(op(), 0..300)) {
+        check_tracked_keys(&TinyLfuPolicy::<u8>::new(), &ops)?;
+        check_tracked_keys(&ArcPolicy::<u8>::new(), &ops)?;
+        check_tracked_keys(&TwoQueuePolicy::<u8>::new(), &ops)?;
+        check_tracked_keys(&RandomPolicy::<u8>::with_seed(42), &ops)?;
+    }
+
+    #[test]
+    fn reset_forgets_every_key(ops in prop::collection::vec(op(), 0..50)) {
+        let lru = LruPolicy::<u8>::new();
+        let lfu = LfuPolicy::<u8>::new();
+        let tinylfu = TinyLfuPolicy::<u8>::new();
+        let arc = ArcPolicy::<u8>::new();
+        let two_queue = TwoQueuePolicy::<u8>::new();
+        let sieve = SievePolicy::<u8>::new();
+        let fifo = FifoPolicy::<u8>::new();
+        let random = RandomPolicy::<u8>::new();
+        let policies: [&dyn EvictionPolicy<u8, ()>; 8] =
+            [&lru, &lfu, &tinylfu, &arc, &two_queue, &sieve, &fifo, &random];
+
+        for policy in policies {
+            for op in &ops {
+                apply_policy(policy, op);
+            }
+            policy.reset();
+            prop_assert!(policy.evict(usize::MAX).keys_to_evict.is_empty());
+        }
+    }
+}
@@ -2,7 +2,6 @@
 //!
 //! These tests verify the LRU and LFU eviction policies work correctly.
 
-use fncache;
 use serial_test::serial;
 use std::cell::Cell;
 use std::thread;
@@ -12,9 +11,11 @@ use std::time::Duration;
 #[serial]
 fn test_lru_eviction() {
     let capacity = 2;
-    let mut config = fncache::backends::memory::MemoryBackendConfig::default();
-    config.max_capacity = capacity;
-    config.eviction_policy = "lru".to_string();
+    let config = fncache::backends::memory::MemoryBackendConfig {
+        max_capacity: capacity,
+        eviction_policy: fncache::eviction::EvictionPolicyKind::Lru,
+        ..Default::default()
+    };
 
     let backend = fncache::backends::memory::MemoryBackend::with_config(config);
 
@@ -57,9 +58,11 @@ fn test_lru_eviction() {
 #[ignore]
 fn test_lfu_eviction() {
     let capacity = 3;
-    let mut config = fncache::backends::memory::MemoryBackendConfig::default();
-    config.max_capacity = capacity;
-    config.eviction_policy = "lfu".to_string();
+    let config = fncache::backends::memory::MemoryBackendConfig {
+        max_capacity: capacity,
+        eviction_policy: fncache::eviction::EvictionPolicyKind::Lfu,
+        ..Default::default()
+    };
 
     let backend = fncache::backends::memory::MemoryBackend::with_config(config);
 
@@ -73,7 +76,7 @@ fn test_lfu_eviction() {
     fncache::invalidate_all_cache_entries();
 
     thread_local! {
-        static COUNTER: Cell<u32> = Cell::new(0);
+        static COUNTER: Cell<u32> = const { Cell::new(0) };
     }
     COUNTER.with(|c| c.set(0));
 
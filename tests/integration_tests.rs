@@ -7,6 +7,7 @@ use fncache::{self, MemoryBackend};
 use fncache_macros::fncache;
 use serde::{Deserialize, Serialize};
 use serial_test::serial;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::thread;
 use std::time::{Duration, SystemTime};
 
@@ -23,13 +24,11 @@ fn test_basic_memory_caching() {
     fncache::reset_global_cache_for_testing();
     let _ = fncache::init_global_cache(MemoryBackend::new());
 
-    static mut COUNTER: u32 = 0;
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
 
     #[fncache(ttl = 60)]
     fn get_data(id: u32, name: &str) -> TestData {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
 
         TestData {
             id,
@@ -43,13 +42,9 @@ fn test_basic_memory_caching() {
 
     assert_eq!(result1, result2);
 
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
     let result3 = get_data(2, "test");
-    unsafe {
-        assert_eq!(COUNTER, 2);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
     assert_ne!(result1, result3);
 }
 
@@ -59,13 +54,11 @@ fn test_ttl_expiration() {
     fncache::reset_global_cache_for_testing();
     let _ = fncache::init_global_cache(MemoryBackend::new());
 
-    static mut COUNTER: u32 = 0;
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
 
     #[fncache(ttl = 1)]
     fn get_timestamp() -> u64 {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
         SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -75,15 +68,11 @@ fn test_ttl_expiration() {
     let result1 = get_timestamp();
     let result2 = get_timestamp();
     assert_eq!(result1, result2);
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 
     thread::sleep(Duration::from_secs(2));
     let result3 = get_timestamp();
-    unsafe {
-        assert_eq!(COUNTER, 2);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
     assert_ne!(result1, result3);
 }
 
@@ -92,13 +81,11 @@ async fn test_async_caching() {
     fncache::reset_global_cache_for_testing();
     let _ = fncache::init_global_cache(MemoryBackend::new());
 
-    static mut COUNTER: u32 = 0;
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
 
     #[fncache(ttl = 60)]
     async fn fetch_data(id: u32) -> Result<TestData, Error> {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
 
         tokio::time::sleep(Duration::from_millis(50)).await;
 
@@ -114,9 +101,7 @@ async fn test_async_caching() {
 
     assert_eq!(result1, result2);
 
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 }
 
 mod function_signatures {
@@ -125,19 +110,15 @@ mod function_signatures {
     #[test]
     #[serial]
     fn test_function_returning_result() {
-        let _ = fncache::reset_global_cache_for_testing();
+        fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(MemoryBackend::new());
 
-        static mut COUNTER: u32 = 0;
-        unsafe {
-            COUNTER = 0;
-        }
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        COUNTER.store(0, Ordering::SeqCst);
 
         #[fncache(ttl = 30)]
         fn fallible_result_function(succeed: bool) -> Result<String, String> {
-            unsafe {
-                COUNTER += 1;
-            }
+            COUNTER.fetch_add(1, Ordering::SeqCst);
 
             if succeed {
                 Ok("success".to_string())
@@ -150,35 +131,27 @@ mod function_signatures {
         let result2 = fallible_result_function(true).unwrap();
 
         assert_eq!(result1, result2);
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 
         let err1 = fallible_result_function(false).unwrap_err();
         let err2 = fallible_result_function(false).unwrap_err();
 
         assert_eq!(err1, err2);
-        unsafe {
-            assert_eq!(COUNTER, 2);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
     }
 
     #[test]
     #[serial]
     fn test_function_returning_option() {
-        let _ = fncache::reset_global_cache_for_testing();
+        fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(MemoryBackend::new());
 
-        static mut COUNTER: u32 = 0;
-        unsafe {
-            COUNTER = 0;
-        }
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        COUNTER.store(0, Ordering::SeqCst);
 
         #[fncache(ttl = 30)]
         fn optional_function(has_value: bool) -> Option<String> {
-            unsafe {
-                COUNTER += 1;
-            }
+            COUNTER.fetch_add(1, Ordering::SeqCst);
 
             if has_value {
                 Some("found".to_string())
@@ -191,18 +164,14 @@ mod function_signatures {
         let result2 = optional_function(true).unwrap();
 
         assert_eq!(result1, result2);
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 
         let none1 = optional_function(false);
         let none2 = optional_function(false);
 
         assert_eq!(none1, none2);
         assert_eq!(none1, None);
-        unsafe {
-            assert_eq!(COUNTER, 2);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
     }
 }
 
@@ -221,13 +190,11 @@ mod file_backend_tests {
         fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(backend);
 
-        static mut COUNTER: u32 = 0;
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
 
         #[fncache(ttl = 3600)]
         fn persistent_data(id: u32) -> TestData {
-            unsafe {
-                COUNTER += 1;
-            }
+            COUNTER.fetch_add(1, Ordering::SeqCst);
 
             TestData {
                 id,
@@ -238,9 +205,7 @@ mod file_backend_tests {
 
         let result1 = persistent_data(100);
 
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 
         let backend = FileBackend::new(path).unwrap();
         fncache::reset_global_cache_for_testing();
@@ -249,9 +214,7 @@ mod file_backend_tests {
 
         assert_eq!(result1, result2);
 
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
     }
 }
 
@@ -265,13 +228,11 @@ mod key_derivation_tests {
         fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(MemoryBackend::new());
 
-        static mut COUNTER: u32 = 0;
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
 
         #[fncache(ttl = 60, key_derivation = "compile_time")]
         fn keyed_function(a: u32, b: &str) -> String {
-            unsafe {
-                COUNTER += 1;
-            }
+            COUNTER.fetch_add(1, Ordering::SeqCst);
             format!("{}-{}", a, b)
         }
 
@@ -279,9 +240,7 @@ mod key_derivation_tests {
         let result2 = keyed_function(42, "test");
 
         assert_eq!(result1, result2);
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
     }
 }
 
@@ -292,9 +251,11 @@ mod eviction_tests {
     #[serial]
     fn test_lru_eviction() {
         let capacity = 2;
-        let mut config = fncache::backends::memory::MemoryBackendConfig::default();
-        config.max_capacity = capacity;
-        config.eviction_policy = "lru".to_string();
+        let config = fncache::backends::memory::MemoryBackendConfig {
+            max_capacity: capacity,
+            eviction_policy: fncache::eviction::EvictionPolicyKind::Lru,
+            ..Default::default()
+        };
 
         let backend = fncache::backends::memory::MemoryBackend::with_config(config);
 
@@ -336,9 +297,11 @@ mod eviction_tests {
     #[serial]
     fn test_lfu_eviction() {
         let capacity = 3;
-        let mut config = fncache::backends::memory::MemoryBackendConfig::default();
-        config.max_capacity = capacity;
-        config.eviction_policy = "lfu".to_string();
+        let config = fncache::backends::memory::MemoryBackendConfig {
+            max_capacity: capacity,
+            eviction_policy: fncache::eviction::EvictionPolicyKind::Lfu,
+            ..Default::default()
+        };
 
         let backend = fncache::backends::memory::MemoryBackend::with_config(config);
 
@@ -385,13 +348,11 @@ mod wasm_tests {
         fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(backend);
 
-        static mut COUNTER: u32 = 0;
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
 
         #[fncache(ttl = 60)]
         fn browser_data(id: u32) -> TestData {
-            unsafe {
-                COUNTER += 1;
-            }
+            COUNTER.fetch_add(1, Ordering::SeqCst);
 
             TestData {
                 id,
@@ -404,9 +365,7 @@ mod wasm_tests {
         let result2 = browser_data(1);
 
         assert_eq!(result1, result2);
-        unsafe {
-            assert_eq!(COUNTER, 1);
-        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
     }
 }
 
@@ -439,14 +398,13 @@ mod error_handling_tests {
 #[cfg(feature = "memory")]
 mod concurrent_tests {
     use super::*;
-    use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Barrier};
     use std::thread;
 
     #[test]
     #[serial]
     fn test_concurrent_access() {
-        let _ = fncache::reset_global_cache_for_testing();
+        fncache::reset_global_cache_for_testing();
         let _ = fncache::init_global_cache(MemoryBackend::new());
 
         static COUNTER: AtomicU32 = AtomicU32::new(0);
@@ -505,7 +463,7 @@ mod integration_scenario_tests {
         fn get_user(&self, user_id: u32) -> Result<TestData, Error> {
             Ok(TestData {
                 id: user_id,
-                name: format!("User-{}", user_id),
+                name: format!("{}/users/{}", self.base_url, user_id),
                 values: vec![100, 200, 300],
             })
         }
@@ -514,7 +472,7 @@ mod integration_scenario_tests {
         fn get_product(&self, product_id: u32) -> Result<TestData, Error> {
             Ok(TestData {
                 id: product_id,
-                name: format!("Product-{}", product_id),
+                name: format!("{}/products/{}", self.base_url, product_id),
                 values: vec![400, 500, 600],
             })
         }
@@ -547,16 +505,12 @@ async fn run_async_caching_tests() {
     fncache::reset_global_cache_for_testing();
     let _ = fncache::init_global_cache(MemoryBackend::new());
 
-    static mut COUNTER: u32 = 0;
-    unsafe {
-        COUNTER = 0;
-    }
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    COUNTER.store(0, Ordering::SeqCst);
 
     #[fncache(ttl = 60)]
     async fn fetch_data_for_core_test(id: u32) -> Result<TestData, fncache::prelude::Error> {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
 
         tokio::time::sleep(std::time::Duration::from_millis(50)).await;
 
@@ -571,22 +525,16 @@ async fn run_async_caching_tests() {
     let result2 = fetch_data_for_core_test(1).await.unwrap();
 
     assert_eq!(result1, result2);
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 }
 
 fn run_basic_memory_caching_test() {
-    static mut COUNTER: u32 = 0;
-    unsafe {
-        COUNTER = 0;
-    }
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    COUNTER.store(0, Ordering::SeqCst);
 
     #[fncache(ttl = 60)]
     fn get_data_for_core_test(id: u32, name: &str) -> TestData {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
 
         TestData {
             id,
@@ -600,22 +548,16 @@ fn run_basic_memory_caching_test() {
 
     assert_eq!(result1, result2);
 
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 }
 
 fn run_ttl_expiration_test() {
-    static mut COUNTER: u32 = 0;
-    unsafe {
-        COUNTER = 0;
-    }
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    COUNTER.store(0, Ordering::SeqCst);
 
     #[fncache(ttl = 1)]
     fn get_data_with_ttl_for_core_test(id: u32) -> TestData {
-        unsafe {
-            COUNTER += 1;
-        }
+        COUNTER.fetch_add(1, Ordering::SeqCst);
 
         TestData {
             id,
@@ -627,17 +569,13 @@ fn run_ttl_expiration_test() {
     let result1 = get_data_with_ttl_for_core_test(1);
     let result2 = get_data_with_ttl_for_core_test(1);
     assert_eq!(result1, result2);
-    unsafe {
-        assert_eq!(COUNTER, 1);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 1);
 
     std::thread::sleep(std::time::Duration::from_secs(2));
 
     let result3 = get_data_with_ttl_for_core_test(1);
     assert_eq!(result1, result3);
-    unsafe {
-        assert_eq!(COUNTER, 2);
-    }
+    assert_eq!(COUNTER.load(Ordering::SeqCst), 2);
 }
 
 #[test]
@@ -0,0 +1,33 @@
+//! `#[fncache(pinned)]` entries stay cached under eviction pressure.
+//!
+//! Runs in its own test binary because it needs a capacity-limited global
+//! cache, and the global cache can only be initialized once per process.
+
+use fncache::{backends::memory::MemoryBackend, init_global_cache};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CONFIG_LOADS: AtomicUsize = AtomicUsize::new(0);
+
+#[fncache::fncache(ttl = 60, pinned)]
+fn load_config() -> String {
+    CONFIG_LOADS.fetch_add(1, Ordering::SeqCst);
+    "config".to_string()
+}
+
+#[fncache::fncache(ttl = 60)]
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+#[test]
+fn test_pinned_function_survives_eviction() {
+    init_global_cache(MemoryBackend::new().with_capacity(4)).unwrap();
+
+    assert_eq!(load_config(), "config");
+    for x in 0..20 {
+        assert_eq!(square(x), x * x);
+    }
+    assert_eq!(load_config(), "config");
+
+    assert_eq!(CONFIG_LOADS.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,117 @@
+//! `#[fncache(single_flight)]` functions go through `CacheBackend::get_or_compute`.
+//!
+//! Runs in its own test binary because it initializes the global cache, which
+//! can only be initialized once per process.
+
+use async_trait::async_trait;
+use fncache::{
+    backends::{memory::MemoryBackend, CacheBackend, Compute, Key, SetOptions, Value},
+    init_global_cache,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+
+static INIT: Once = Once::new();
+static REPORT_BUILDS: AtomicUsize = AtomicUsize::new(0);
+static PROFILE_LOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// A memory backend whose `get_or_compute` lets one caller compute a key at a
+/// time, as a backend shared between processes does across processes.
+#[derive(Debug)]
+struct SingleFlightBackend {
+    inner: MemoryBackend,
+    flight: tokio::sync::Mutex<()>,
+}
+
+#[async_trait]
+impl CacheBackend for SingleFlightBackend {
+    async fn get(&self, key: &Key) -> fncache::Result<Option<Value>> {
+        self.inner.get(key).await
+    }
+
+    async fn set(&self, key: Key, value: Value, ttl: Option<Duration>) -> fncache::Result<()> {
+        self.inner.set(key, value, ttl).await
+    }
+
+    async fn remove(&self, key: &Key) -> fncache::Result<()> {
+        self.inner.remove(key).await
+    }
+
+    async fn contains_key(&self, key: &Key) -> fncache::Result<bool> {
+        self.inner.contains_key(key).await
+    }
+
+    async fn clear(&self) -> fncache::Result<()> {
+        self.inner.clear().await
+    }
+
+    async fn get_or_compute(
+        &self,
+        key: Key,
+        options: SetOptions,
+        compute: Compute<'_>,
+    ) -> fncache::Result<Value> {
+        let _flight = self.flight.lock().await;
+        self.inner.get_or_compute(key, options, compute).await
+    }
+}
+
+fn init() {
+    INIT.call_once(|| {
+        init_global_cache(SingleFlightBackend {
+            inner: MemoryBackend::new(),
+            flight: tokio::sync::Mutex::new(()),
+        })
+        .unwrap();
+    });
+}
+
+#[fncache::fncache(ttl = 60)]
+async fn user_name(id: u32) -> String {
+    format!("user-{}", id)
+}
+
+#[fncache::fncache(ttl = 60, single_flight)]
+async fn build_report(id: u32) -> Result<String, String> {
+    REPORT_BUILDS.fetch_add(1, Ordering::SeqCst);
+    if id == 0 {
+        return Err("no report for user 0".to_string());
+    }
+    // Calling another cached function must not deadlock on the global cache.
+    let name = user_name(id).await;
+    Ok(format!("report for {}", name))
+}
+
+// Takes its argument by value: the body must be expanded only once.
+#[fncache::fncache(ttl = 60, single_flight)]
+async fn load_profile(name: String) -> String {
+    PROFILE_LOADS.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    format!("profile of {}", name)
+}
+
+#[tokio::test]
+async fn test_single_flight_function_is_computed_once() {
+    init();
+
+    assert_eq!(build_report(7).await, Ok("report for user-7".to_string()));
+    assert_eq!(build_report(7).await, Ok("report for user-7".to_string()));
+    assert_eq!(REPORT_BUILDS.load(Ordering::SeqCst), 1);
+
+    assert!(build_report(0).await.is_err());
+    assert_eq!(REPORT_BUILDS.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_single_flight_concurrent_callers_compute_once() {
+    init();
+
+    let callers: Vec<_> = (0..8)
+        .map(|_| tokio::spawn(load_profile("ada".to_string())))
+        .collect();
+    for caller in callers {
+        assert_eq!(caller.await.unwrap(), "profile of ada");
+    }
+    assert_eq!(PROFILE_LOADS.load(Ordering::SeqCst), 1);
+}
@@ -2,6 +2,7 @@
 
 use fncache::backends::memory::{MemoryBackend, MemoryBackendConfig};
 use fncache::backends::CacheBackend;
+use fncache::eviction::EvictionPolicyKind;
 use futures::executor::block_on;
 use serial_test::serial;
 use std::sync::Arc;
@@ -11,7 +12,7 @@ use std::sync::Arc;
 fn test_thread_safe_lru_eviction() {
     let config = MemoryBackendConfig {
         max_capacity: 2,
-        eviction_policy: "lru".to_string(),
+        eviction_policy: EvictionPolicyKind::Lru,
         ..Default::default()
     };
     let backend = Arc::new(MemoryBackend::with_config(config));
@@ -53,7 +54,7 @@ fn test_thread_safe_lru_eviction() {
 fn test_thread_safe_lfu_eviction() {
     let config = MemoryBackendConfig {
         max_capacity: 2,
-        eviction_policy: "lfu".to_string(),
+        eviction_policy: EvictionPolicyKind::Lfu,
         ..Default::default()
     };
     let backend = Arc::new(MemoryBackend::with_config(config));